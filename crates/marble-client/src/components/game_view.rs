@@ -6,6 +6,8 @@
 use std::collections::{HashMap, HashSet};
 
 use gloo::events::EventListener;
+use marble_proto::map::MapInfo;
+use marble_proto::room::Readiness;
 use wasm_bindgen::JsCast;
 use web_sys::MouseEvent;
 use yew::prelude::*;
@@ -190,6 +192,36 @@ fn game_view_inner(props: &GameViewInnerProps) -> Html {
         });
     }
 
+    // 로비 준비 상태 보고 (맵 로드, P2P 연결, 준비 버튼). 호스트는 항상 준비 상태
+    let ready_pressed = use_state(|| false);
+    {
+        let room_service = room_service.clone();
+        let in_lobby = matches!(*game_phase, GamePhase::InLobby);
+        let room_id = room_service.room_id();
+        let readiness = Readiness {
+            map_loaded: bevy.initialized && !bevy_game_state.map_name.is_empty(),
+            p2p_connected: is_connected,
+            ready: props.is_host || *ready_pressed,
+        };
+        use_effect_with((in_lobby, room_id, readiness), move |(in_lobby, room_id, readiness)| {
+            if *in_lobby && room_id.is_some() {
+                room_service.set_ready(*readiness);
+            }
+        });
+    }
+
+    // 호스트: 로비 맵 선택 목록 (ListMaps)
+    let lobby_maps = use_state(Vec::<MapInfo>::new);
+    {
+        let room_service = room_service.clone();
+        let lobby_maps = lobby_maps.clone();
+        use_effect_with(props.is_host, move |is_host| {
+            if *is_host {
+                room_service.list_maps(Callback::from(move |maps| lobby_maps.set(maps)));
+            }
+        });
+    }
+
     // 호스트: Bevy 플레이어 상태를 감시하여 도착 보고 (gRPC ReportArrival)
     // deps를 도착한 플레이어 (name, arrival_frame) 집합으로 한정하여 live_rank 등 변경 시 재실행 방지
     {
@@ -369,8 +401,26 @@ fn game_view_inner(props: &GameViewInnerProps) -> Html {
                 return;
             }
 
-            // Generate deterministic seed
-            let seed = js_sys::Date::now() as u64;
+            // Starting past an unmet ready check needs the host's explicit confirmation
+            let force = match room_service.ready_status() {
+                Some((ready, required)) if ready < required => {
+                    let message = format!(
+                        "준비되지 않은 플레이어가 있습니다 (준비 {}/{}명). 그래도 시작할까요?",
+                        ready, required
+                    );
+                    let confirmed = web_sys::window()
+                        .and_then(|w| w.confirm_with_message(&message).ok())
+                        .unwrap_or(false);
+                    if !confirmed {
+                        return;
+                    }
+                    true
+                }
+                _ => false,
+            };
+
+            // The server issues each round's seed; GameStart carries it with the round
+            let (round, seed) = room_service.round_and_seed();
 
             // 1. Set sync host status
             let cmd = serde_json::json!({"type": "set_sync_host", "is_host": true});
@@ -440,12 +490,17 @@ fn game_view_inner(props: &GameViewInnerProps) -> Html {
             }
 
             // 8. Broadcast game start to peers via Bevy P2P
-            if let Err(e) = send_command(r#"{"type":"broadcast_game_start"}"#) {
+            let cmd = serde_json::json!({
+                "type": "broadcast_game_start",
+                "round": round,
+                "seed": seed
+            });
+            if let Err(e) = send_command(&cmd.to_string()) {
                 tracing::error!("Failed to broadcast game start: {:?}", e);
             }
 
-            // 9. Report game start to server via gRPC
-            room_service.start_game(bevy_game_state.frame);
+            // 9. Report game start to server via gRPC (forced only once confirmed above)
+            room_service.start_game(bevy_game_state.frame, force);
 
             // 10. Transition to playing phase
             game_phase.set(GamePhase::Playing);
@@ -496,6 +551,12 @@ fn game_view_inner(props: &GameViewInnerProps) -> Html {
 
     let gamerule = bevy_game_state.gamerule.clone();
 
+    // Synchronized start countdown (seconds left, on the local wall clock)
+    let countdown_secs = bevy_game_state.race_start_at_ms.and_then(|start_at_ms| {
+        let remaining_ms = start_at_ms - js_sys::Date::now();
+        (remaining_ms > 0.0).then(|| (remaining_ms / 1000.0).ceil() as u32)
+    });
+
     // Determine phases
     let in_lobby = matches!(*game_phase, GamePhase::InLobby);
     let in_ended = matches!(*game_phase, GamePhase::Ended);
@@ -518,6 +579,21 @@ fn game_view_inner(props: &GameViewInnerProps) -> Html {
         .display_name(&my_user_id)
         .unwrap_or_else(|| player_id.clone());
 
+    // Ready badge for a peer (peer_id → user_id → readiness from WatchRoom)
+    let peer_ready = |peer_id_str: &str| -> bool {
+        room_service
+            .player_name(peer_id_str)
+            .is_some_and(|user_id| room_service.is_user_ready(&user_id))
+    };
+    let me_ready = room_service.is_user_ready(&my_user_id);
+    let ready_badge = |ready: bool| -> Html {
+        if ready {
+            html! { <span class="lobby-ready-badge">{"준비"}</span> }
+        } else {
+            html! {}
+        }
+    };
+
     // Build sorted player list for lobby (host → me → others alphabetically)
     let lobby_player_items = {
         let host_peer_id = p2p.host_peer_id();
@@ -540,6 +616,7 @@ fn game_view_inner(props: &GameViewInnerProps) -> Html {
                     <span class="lobby-player-name">{&my_display_name}</span>
                     <span class="lobby-host-badge">{"호스트"}</span>
                     <span class="lobby-me-badge">{"나"}</span>
+                    {ready_badge(me_ready)}
                 </div>
             });
         } else {
@@ -549,6 +626,7 @@ fn game_view_inner(props: &GameViewInnerProps) -> Html {
             {
                 let host_id = host_peer.peer_id.to_string();
                 let host_name = resolve_display_name(&host_id, host_peer);
+                let host_ready = peer_ready(&host_id);
                 let conn_class = if host_peer.connected { "connected" } else { "disconnected" };
                 let conn_dot = if host_peer.connected { "\u{25CF}" } else { "\u{25CB}" };
                 items.push(html! {
@@ -556,6 +634,7 @@ fn game_view_inner(props: &GameViewInnerProps) -> Html {
                         <span class={classes!("lobby-connection-indicator", conn_class)}>{conn_dot}</span>
                         <span class="lobby-player-name">{host_name}</span>
                         <span class="lobby-host-badge">{"호스트"}</span>
+                        {ready_badge(host_ready)}
                     </div>
                 });
             }
@@ -565,6 +644,7 @@ fn game_view_inner(props: &GameViewInnerProps) -> Html {
                     <span class="lobby-connection-indicator connected">{"\u{25CF}"}</span>
                     <span class="lobby-player-name">{&my_display_name}</span>
                     <span class="lobby-me-badge">{"나"}</span>
+                    {ready_badge(me_ready)}
                 </div>
             });
         }
@@ -579,12 +659,14 @@ fn game_view_inner(props: &GameViewInnerProps) -> Html {
             }
             let peer_id_str = peer.peer_id.to_string();
             let peer_name = resolve_display_name(&peer_id_str, peer);
+            let ready = peer_ready(&peer_id_str);
             let conn_class = if peer.connected { "connected" } else { "disconnected" };
             let conn_dot = if peer.connected { "\u{25CF}" } else { "\u{25CB}" };
             items.push(html! {
                 <div class="lobby-player-item">
                     <span class={classes!("lobby-connection-indicator", conn_class)}>{conn_dot}</span>
                     <span class="lobby-player-name">{peer_name}</span>
+                    {ready_badge(ready)}
                 </div>
             });
         }
@@ -592,6 +674,39 @@ fn game_view_inner(props: &GameViewInnerProps) -> Html {
         items.into_iter().collect::<Html>()
     };
 
+    // Map picker (host): UpdateRoom, then MapChange broadcast to peers
+    let on_map_change = {
+        let room_service = room_service.clone();
+        Callback::from(move |e: Event| {
+            if let Some(select) = e.target_dyn_into::<web_sys::HtmlSelectElement>() {
+                room_service.change_map(select.value());
+            }
+        })
+    };
+    let current_map_id = room_service.map_id();
+    let map_options = lobby_maps
+        .iter()
+        .map(|map| {
+            html! {
+                <option value={map.map_id.clone()} selected={map.map_id == current_map_id}>
+                    {&map.name}
+                </option>
+            }
+        })
+        .collect::<Html>();
+
+    // Ready toggle (non-host)
+    let on_toggle_ready = {
+        let ready_pressed = ready_pressed.clone();
+        Callback::from(move |_: MouseEvent| ready_pressed.set(!*ready_pressed))
+    };
+    let start_label = match room_service.ready_status() {
+        Some((ready, required)) if ready < required => {
+            format!("강제 시작 (준비 {}/{}명)", ready, required)
+        }
+        _ => format!("게임 시작 ({}명)", peers.len() + 1),
+    };
+
     html! {
         <div class="game-view fullscreen">
             // NOTE: Game canvas is now managed globally by App.rs
@@ -645,6 +760,23 @@ fn game_view_inner(props: &GameViewInnerProps) -> Html {
                                 </div>
                             </div>
 
+                            // Map picker (host, before the game starts)
+                            if props.is_host && room_service.server_room_state() == Some(1) {
+                                <div class="lobby-map">
+                                    <div class="lobby-players-header">{"맵"}</div>
+                                    <select
+                                        class="lobby-map-select"
+                                        onchange={on_map_change}
+                                        disabled={room_service.is_changing_map()}
+                                    >
+                                        <option value="" selected={current_map_id.is_empty()}>
+                                            {"기본 맵"}
+                                        </option>
+                                        {map_options}
+                                    </select>
+                                </div>
+                            }
+
                             // Start button (host) or waiting message (non-host)
                             <div class="lobby-actions">
                                 if props.is_host {
@@ -656,12 +788,22 @@ fn game_view_inner(props: &GameViewInnerProps) -> Html {
                                             class="lobby-start-btn"
                                             onclick={on_start_game}
                                         >
-                                            {format!("게임 시작 ({}명)", peers.len() + 1)}
+                                            {start_label}
                                         </button>
                                     } else {
                                         <p class="lobby-connecting">{"서버에 연결 중..."}</p>
                                     }
                                 } else {
+                                    <button
+                                        class={classes!("lobby-ready-btn", ready_pressed.then_some("ready"))}
+                                        onclick={on_toggle_ready}
+                                    >
+                                        if *ready_pressed {
+                                            {"준비 취소"}
+                                        } else {
+                                            {"준비"}
+                                        }
+                                    </button>
                                     <p class="lobby-waiting">{"호스트가 게임을 시작할 때까지 대기 중..."}</p>
                                 }
                             </div>
@@ -669,6 +811,13 @@ fn game_view_inner(props: &GameViewInnerProps) -> Html {
                     </div>
                 }
 
+                // Countdown until the synchronized race start
+                if let Some(secs) = countdown_secs {
+                    <div class="game-overlay countdown-overlay">
+                        <span class="countdown-number">{secs}</span>
+                    </div>
+                }
+
                 // Left side: Peer list (only when playing)
                 if !in_lobby {
                     <PeerList
@@ -165,6 +165,8 @@ pub fn peer_instance_card(props: &PeerInstanceCardProps) -> Html {
                 let req = JoinRoomRequest {
                     room_id: target_room.clone(),
                     role: None,
+                    password: String::new(),
+                    invite_token: String::new(),
                 };
 
                 match grpc.borrow_mut().join_room(req).await {
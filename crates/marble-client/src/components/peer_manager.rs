@@ -21,7 +21,7 @@ pub enum PeerStatus {
 /// Presence of a known user_id.
 #[derive(Debug, Clone)]
 pub enum UserPresence {
-    /// Present in GetRoomUsers but no active P2P connection.
+    /// Present in the room (WatchRoom) but no active P2P connection.
     InRoom,
     /// Has an active P2P connection via `peer_id`.
     Connected { peer_id: String },
@@ -31,7 +31,7 @@ pub enum UserPresence {
 pub struct PeerManager {
     /// peer_id → status
     peers: HashMap<String, PeerStatus>,
-    /// user_id → presence (authoritative source: GetRoomUsers)
+    /// user_id → presence (authoritative source: WatchRoom)
     users: HashMap<String, UserPresence>,
     /// user_id → display_name
     display_names: HashMap<String, String>,
@@ -168,7 +168,7 @@ impl PeerManager {
     // Room users (authoritative)
     // ========================================================================
 
-    /// Update the authoritative user list from GetRoomUsers.
+    /// Update the authoritative user list from WatchRoom.
     pub fn update_room_users(&mut self, user_ids: Vec<String>) {
         // Remove users no longer in the room
         self.users.retain(|uid, _| user_ids.contains(uid));
@@ -8,11 +8,17 @@ use std::collections::HashMap;
 use std::rc::Rc;
 
 use gloo::timers::callback::Interval;
+use marble_core::{MapLimits, RouletteConfig};
+use marble_proto::map::map_service_client::MapServiceClient;
+use marble_proto::map::{GetMapRequest, ListMapsRequest, MapInfo, MapSort};
 use marble_proto::room::room_service_client::RoomServiceClient;
 use marble_proto::room::{
-    CreateRoomRequest, GetRoomUsersRequest, JoinRoomRequest, PlayerResult, RegisterPeerIdRequest,
-    ReportArrivalRequest, ResolvePeerIdsRequest, RoomUser, StartGameRequest,
+    CreateRoomRequest, GetTopologyRequest, JoinRoomRequest, PeerTopology, PlayerResult, Readiness,
+    ReadyCheck, RegisterPeerIdRequest, ReportArrivalRequest, ResolvePeerIdsRequest, RoomInfo,
+    RoomRole, RoomUser, SetReadyRequest, StartGameRequest, UpdateRoomRequest, WatchRoomRequest,
+    room_event,
 };
+use prost::Message as _;
 
 use super::peer_manager::PeerManager;
 use marble_proto::user::user_service_client::UserServiceClient;
@@ -73,18 +79,34 @@ struct RoomServiceInner {
     // In-flight flags for async operations
     resolve_in_flight: bool,
     get_users_in_flight: bool,
-    get_room_users_in_flight: bool,
+    topology_in_flight: bool,
+
+    // WatchRoom stream (epoch bumps on join/leave so a stale stream stops applying events)
+    room_watch_active: bool,
+    room_watch_epoch: u32,
+    last_room_watch_ms: f64,
 
     // Bevy polling state
     last_peers_version: u64,
     last_pongs_version: u64,
-    last_room_users_poll_ms: f64,
 
-    // Server game state (from JoinRoom / ReportArrival responses)
+    // Server game state (from JoinRoom / ReportArrival responses and WatchRoom events)
     server_room_state: Option<i32>,  // proto RoomState (1=WAITING, 2=PLAYING, 3=ENDED)
     server_game_results: Vec<PlayerResult>,
     server_game_ended: bool,
 
+    // Lobby ready-check (rule from JoinRoom, members and readiness from WatchRoom)
+    ready_check: Option<ReadyCheck>,
+    members: HashMap<String, RoomUser>,
+
+    // Room map (from JoinRoom / UpdateRoom; empty = built-in default map)
+    map_id: String,
+    map_change_in_flight: bool,
+
+    // Current round and its seed (from RoomInfo); the host sends both in GameStart
+    round: u64,
+    rng_seed: u64,
+
     // Version setter — bumped on every state change to trigger re-render
     version_setter: Option<UseStateHandle<u32>>,
 }
@@ -105,13 +127,21 @@ impl RoomServiceInner {
             register_in_flight: false,
             resolve_in_flight: false,
             get_users_in_flight: false,
-            get_room_users_in_flight: false,
+            topology_in_flight: false,
+            room_watch_active: false,
+            room_watch_epoch: 0,
+            last_room_watch_ms: 0.0,
             last_peers_version: 0,
             last_pongs_version: 0,
-            last_room_users_poll_ms: 0.0,
             server_room_state: None,
             server_game_results: Vec::new(),
             server_game_ended: false,
+            ready_check: None,
+            members: HashMap::new(),
+            map_id: String::new(),
+            map_change_in_flight: false,
+            round: 0,
+            rng_seed: 0,
             version_setter: None,
         }
     }
@@ -122,6 +152,120 @@ impl RoomServiceInner {
             setter.set(**setter + 1);
         }
     }
+
+    /// Drop the current WatchRoom stream; the polling loop opens a new one.
+    fn stop_room_watch(&mut self) {
+        self.room_watch_active = false;
+        self.room_watch_epoch = self.room_watch_epoch.wrapping_add(1);
+        self.last_room_watch_ms = 0.0;
+    }
+
+    /// Hand a topology to Bevy: the member keys to verify P2P messages against and the
+    /// server relay to connect (relayed players and bridges).
+    fn apply_topology(&self, topology: &PeerTopology) {
+        marble_core::bevy::wasm_entry::update_member_keys(&topology.encode_to_vec());
+        marble_core::bevy::wasm_entry::set_relay_url(&with_socket_token(
+            &topology.relay_url,
+            &self.auth_token,
+        ));
+    }
+
+    /// Take over room-level state from JoinRoom, a snapshot or a `RoomUpdated` event.
+    fn apply_room_info(&mut self, room: &RoomInfo) {
+        self.ready_check = room.ready_check;
+        self.map_id = room.map_id.clone();
+        self.round = room.round;
+        self.rng_seed = room.game_state.as_ref().map_or(0, |gs| gs.rng_seed);
+        marble_core::bevy::wasm_entry::set_map_limits(
+            room.map_limits.as_ref().map(MapLimits::from_proto),
+        );
+    }
+
+    /// Replace the member list and tell PeerManager who is in the room.
+    fn set_members(&mut self, users: Vec<RoomUser>) {
+        self.members = users.into_iter().map(|u| (u.user_id.clone(), u)).collect();
+        let user_ids = self.members.keys().cloned().collect();
+        self.peer_manager.update_room_users(user_ids);
+    }
+
+    /// Apply one WatchRoom event.
+    fn apply_room_event(&mut self, event: room_event::Event) {
+        match event {
+            room_event::Event::Snapshot(snapshot) => {
+                if let Some(room) = snapshot.room {
+                    self.apply_room_info(&room);
+                    self.server_room_state = Some(room.state);
+                    self.server_game_ended = room.state == 3; // ROOM_STATE_ENDED
+                    self.server_game_results =
+                        room.game_state.map(|gs| gs.results).unwrap_or_default();
+                }
+                self.set_members(snapshot.users);
+            }
+            room_event::Event::MemberJoined(joined) => {
+                if let Some(user) = joined.user {
+                    let mut users: Vec<RoomUser> = self.members.values().cloned().collect();
+                    users.retain(|u| u.user_id != user.user_id);
+                    users.push(user);
+                    self.set_members(users);
+                }
+            }
+            room_event::Event::MemberLeft(left) => {
+                // The server closes our stream after kicking us; stop watching for good
+                if left.kicked && left.user_id == self.player_id {
+                    if let RoomState::Active { room_id, .. } = &self.room_state {
+                        self.room_state = RoomState::Error {
+                            room_id: room_id.clone(),
+                            message: "Kicked from the room".to_string(),
+                        };
+                    }
+                    return;
+                }
+                let mut users: Vec<RoomUser> = self.members.values().cloned().collect();
+                users.retain(|u| u.user_id != left.user_id);
+                self.set_members(users);
+            }
+            room_event::Event::RoleChanged(changed) => {
+                if let Some(user) = self.members.get_mut(&changed.user_id) {
+                    user.role = changed.role;
+                    user.is_host = changed.is_host;
+                }
+            }
+            room_event::Event::ReadinessChanged(changed) => {
+                if let Some(user) = self.members.get_mut(&changed.user_id) {
+                    user.readiness = changed.readiness;
+                }
+            }
+            room_event::Event::StateChanged(changed) => {
+                self.server_room_state = Some(changed.state);
+                self.round = changed.round;
+                match changed.state {
+                    // ROOM_STATE_WAITING: the host reset the room for the next round
+                    1 => {
+                        self.server_game_ended = false;
+                        self.server_game_results.clear();
+                    }
+                    // ROOM_STATE_ENDED
+                    3 => self.server_game_ended = true,
+                    _ => {}
+                }
+            }
+            room_event::Event::GameResultsChanged(changed) => {
+                self.server_game_results = changed.results;
+            }
+            room_event::Event::RoomUpdated(updated) => {
+                if let Some(room) = updated.room {
+                    self.apply_room_info(&room);
+                }
+            }
+            room_event::Event::TopologyChanged(changed) => {
+                // Carries the current member keys, so joiners verify without a GetTopology,
+                // and the relay URL once we are moved to (or off) the server relay
+                if let Some(topology) = changed.topology {
+                    self.apply_topology(&topology);
+                }
+            }
+        }
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -204,6 +348,8 @@ impl RoomServiceHandle {
                     JoinRoomRequest {
                         room_id: room_id.clone(),
                         role: None,
+                        password: String::new(),
+                        invite_token: String::new(),
                     },
                     &token,
                 ))
@@ -219,6 +365,8 @@ impl RoomServiceHandle {
                             JoinRoomRequest {
                                 room_id: room_id.clone(),
                                 role: None,
+                                password: String::new(),
+                                invite_token: String::new(),
                             },
                             &token,
                         ))
@@ -230,13 +378,20 @@ impl RoomServiceHandle {
                 other => other,
             };
 
-            let (signaling_url, is_host, server_state, game_results) = match join_resp {
+            let (signaling_url, is_host, server_state, game_results, ready_check) = match join_resp
+            {
                 Ok(resp) => {
                     let resp = resp.into_inner();
+                    // Our P2P messages are signed with the issued key; members' are
+                    // verified against the keys in the topology
+                    marble_core::bevy::wasm_entry::set_signing_key(&resp.signing_key);
+                    if let Some(topology) = &resp.topology {
+                        inner.borrow().apply_topology(topology);
+                    }
                     let sig_url = resp
                         .topology
                         .as_ref()
-                        .map(|t| t.signaling_url.clone())
+                        .map(|t| with_socket_token(&t.signaling_url, &token))
                         .unwrap_or_default();
                     let host = resp
                         .room
@@ -248,7 +403,11 @@ impl RoomServiceHandle {
                         .and_then(|r| r.game_state.as_ref())
                         .map(|gs| gs.results.clone())
                         .unwrap_or_default();
-                    (sig_url, host, state, results)
+                    let ready_check = resp.room.as_ref().and_then(|r| r.ready_check);
+                    if let Some(room) = &resp.room {
+                        inner.borrow_mut().apply_room_info(room);
+                    }
+                    (sig_url, host, state, results, ready_check)
                 }
                 Err(e) => {
                     let mut inner_mut = inner.borrow_mut();
@@ -276,13 +435,16 @@ impl RoomServiceHandle {
                 inner_mut.peer_manager.reset();
                 inner_mut.resolve_in_flight = false;
                 inner_mut.get_users_in_flight = false;
-                inner_mut.get_room_users_in_flight = false;
+                inner_mut.topology_in_flight = false;
+                inner_mut.stop_room_watch();
                 inner_mut.last_peers_version = 0;
                 inner_mut.last_pongs_version = 0;
-                inner_mut.last_room_users_poll_ms = 0.0;
                 inner_mut.server_room_state = Some(server_state);
                 inner_mut.server_game_results = game_results;
                 inner_mut.server_game_ended = game_ended;
+                inner_mut.ready_check = ready_check;
+                inner_mut.members.clear();
+                inner_mut.map_change_in_flight = false;
                 inner_mut.bump_version();
             }
 
@@ -330,6 +492,8 @@ impl RoomServiceHandle {
                         max_players,
                         room_name: String::new(),
                         is_public: true,
+                        password: String::new(),
+                        ready_check: None,
                     },
                     &token,
                 ))
@@ -347,6 +511,8 @@ impl RoomServiceHandle {
                                 max_players,
                                 room_name: String::new(),
                                 is_public: true,
+                                password: String::new(),
+                                ready_check: None,
                             },
                             &token,
                         ))
@@ -387,17 +553,25 @@ impl RoomServiceHandle {
         inner.room_state = RoomState::Idle;
         inner.peer_manager.reset();
         inner.get_users_in_flight = false;
-        inner.get_room_users_in_flight = false;
+        inner.topology_in_flight = false;
+        inner.stop_room_watch();
         inner.peer_registered = false;
         inner.peer_register_confirmed = false;
         inner.register_in_flight = false;
         inner.resolve_in_flight = false;
         inner.last_peers_version = 0;
         inner.last_pongs_version = 0;
-        inner.last_room_users_poll_ms = 0.0;
         inner.server_room_state = None;
         inner.server_game_results = Vec::new();
         inner.server_game_ended = false;
+        inner.ready_check = None;
+        inner.members.clear();
+        inner.map_id = String::new();
+        inner.map_change_in_flight = false;
+        inner.round = 0;
+        inner.rng_seed = 0;
+        marble_core::bevy::wasm_entry::set_relay_url("");
+        marble_core::bevy::wasm_entry::set_map_limits(None);
         inner.bump_version();
         tracing::info!("RoomService: left room");
     }
@@ -434,7 +608,7 @@ impl RoomServiceHandle {
             .unwrap_or_else(|| format!("User-{}", &user_id[..user_id.len().min(8)]))
     }
 
-    /// Get the authoritative room users (from GetRoomUsers RPC).
+    /// Get the authoritative room users (from the WatchRoom stream).
     pub fn room_users(&self) -> HashMap<String, super::peer_manager::UserPresence> {
         self.inner.borrow().peer_manager.room_users().clone()
     }
@@ -443,8 +617,8 @@ impl RoomServiceHandle {
     // Game operations (fire-and-forget gRPC)
     // =======================================================================
 
-    /// Report game start to server (host only).
-    pub fn start_game(&self, start_frame: u64) {
+    /// Report game start to server (host only). `force` overrides the room's ready check.
+    pub fn start_game(&self, start_frame: u64, force: bool) {
         let inner_rc = self.inner.clone();
         let room_id;
         let token;
@@ -466,6 +640,7 @@ impl RoomServiceHandle {
                 StartGameRequest {
                     room_id: room_id.clone(),
                     start_frame,
+                    force,
                 },
                 &token,
             );
@@ -486,6 +661,7 @@ impl RoomServiceHandle {
                             StartGameRequest {
                                 room_id: room_id.clone(),
                                 start_frame,
+                                force,
                             },
                             &token,
                         );
@@ -518,6 +694,202 @@ impl RoomServiceHandle {
         });
     }
 
+    /// Report this member's lobby readiness to the server.
+    pub fn set_ready(&self, readiness: Readiness) {
+        let inner_rc = self.inner.clone();
+        let room_id;
+        let token;
+        {
+            let inner = inner_rc.borrow();
+            room_id = match &inner.room_state {
+                RoomState::Active { room_id, .. } => room_id.clone(),
+                _ => return,
+            };
+            token = inner.auth_token.clone();
+        }
+
+        spawn_local(async move {
+            let Some(mut grpc) = create_grpc_client() else {
+                return;
+            };
+            let mut token = token;
+            let req = attach_auth(
+                SetReadyRequest {
+                    room_id: room_id.clone(),
+                    readiness: Some(readiness),
+                },
+                &token,
+            );
+            match grpc.set_ready(req).await {
+                Ok(_) => {
+                    tracing::debug!(room_id = %room_id, ?readiness, "RoomService: readiness set");
+                }
+                Err(e) if is_unauthenticated(&e) => {
+                    tracing::info!("RoomService: SetReady auth failed, attempting re-login");
+                    if let Some(new_token) = relogin(&inner_rc).await {
+                        token = Some(new_token);
+                        let req = attach_auth(
+                            SetReadyRequest {
+                                room_id: room_id.clone(),
+                                readiness: Some(readiness),
+                            },
+                            &token,
+                        );
+                        if let Err(e) = grpc.set_ready(req).await {
+                            tracing::warn!(
+                                room_id = %room_id,
+                                error = %e,
+                                "RoomService: SetReady RPC failed after re-login"
+                            );
+                        }
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        room_id = %room_id,
+                        error = %e,
+                        "RoomService: SetReady RPC failed"
+                    );
+                }
+            }
+        });
+    }
+
+    /// Change the lobby map (host only).
+    ///
+    /// Pins the map on the server with `UpdateRoom`, then loads the pinned revision and
+    /// broadcasts it to the peers as a `MapChange`. An empty `map_id` selects the built-in map.
+    pub fn change_map(&self, map_id: String) {
+        let inner_rc = self.inner.clone();
+        let room_id;
+        let token;
+        {
+            let mut inner = inner_rc.borrow_mut();
+            room_id = match &inner.room_state {
+                RoomState::Active {
+                    room_id,
+                    is_host: true,
+                    ..
+                } => room_id.clone(),
+                _ => return,
+            };
+            if inner.map_change_in_flight || inner.map_id == map_id {
+                return;
+            }
+            inner.map_change_in_flight = true;
+            token = inner.auth_token.clone();
+        }
+
+        spawn_local(async move {
+            let result = change_map_grpc(&inner_rc, &room_id, &map_id, token).await;
+            let mut inner = inner_rc.borrow_mut();
+            inner.map_change_in_flight = false;
+            match result {
+                Ok((map_revision, config)) => {
+                    let cmd = serde_json::json!({
+                        "type": "broadcast_map_change",
+                        "map_id": map_id,
+                        "map_revision": map_revision,
+                        "config": config,
+                    });
+                    if let Err(e) = marble_core::bevy::wasm_entry::send_command(&cmd.to_string()) {
+                        tracing::error!("RoomService: failed to broadcast map change: {:?}", e);
+                    }
+                    tracing::info!(
+                        room_id = %room_id,
+                        map_id = %map_id,
+                        map_revision,
+                        "RoomService: map changed"
+                    );
+                    inner.map_id = map_id;
+                }
+                Err(message) => {
+                    tracing::warn!(
+                        room_id = %room_id,
+                        map_id = %map_id,
+                        error = %message,
+                        "RoomService: map change failed"
+                    );
+                }
+            }
+            inner.bump_version();
+        });
+    }
+
+    /// Current room map (empty = built-in default map).
+    pub fn map_id(&self) -> String {
+        self.inner.borrow().map_id.clone()
+    }
+
+    /// Current round and its server-issued seed, sent in GameStart as
+    /// `session_version` and `seed`.
+    pub fn round_and_seed(&self) -> (u64, u64) {
+        let inner = self.inner.borrow();
+        (inner.round, inner.rng_seed)
+    }
+
+    /// Whether a map change is being applied.
+    pub fn is_changing_map(&self) -> bool {
+        self.inner.borrow().map_change_in_flight
+    }
+
+    /// Fetch the most played maps for the lobby map picker.
+    pub fn list_maps(&self, on_maps: Callback<Vec<MapInfo>>) {
+        let token = self.inner.borrow().auth_token.clone();
+        spawn_local(async move {
+            let Some(mut grpc) = create_map_grpc_client() else {
+                return;
+            };
+            let req = attach_auth(
+                ListMapsRequest {
+                    page_size: 20,
+                    sort: MapSort::MostPlayed as i32,
+                    ..Default::default()
+                },
+                &token,
+            );
+            match grpc.list_maps(req).await {
+                Ok(resp) => on_maps.emit(resp.into_inner().maps),
+                Err(e) => {
+                    tracing::warn!(error = %e, "RoomService: ListMaps RPC failed");
+                }
+            }
+        });
+    }
+
+    /// Ready participants and the number the room requires before starting.
+    ///
+    /// `None` when the room has no ready check.
+    pub fn ready_status(&self) -> Option<(u32, u32)> {
+        let inner = self.inner.borrow();
+        let check = inner.ready_check.filter(|c| c.required)?;
+        let participants = inner
+            .members
+            .values()
+            .filter(|u| u.role == i32::from(RoomRole::Participant));
+        let (participants, ready) = participants.fold((0, 0), |(count, ready), u| {
+            (
+                count + 1,
+                ready + u32::from(u.readiness.is_some_and(is_fully_ready)),
+            )
+        });
+        let required = match check.quorum {
+            0 => participants,
+            quorum => quorum.min(participants),
+        };
+        Some((ready, required))
+    }
+
+    /// Whether a member is fully ready (as last reported by WatchRoom).
+    pub fn is_user_ready(&self, user_id: &str) -> bool {
+        self.inner
+            .borrow()
+            .members
+            .get(user_id)
+            .and_then(|u| u.readiness)
+            .is_some_and(is_fully_ready)
+    }
+
     /// Report player arrival to server (host only).
     pub fn report_arrival(&self, arrived_user_id: &str, arrival_frame: u64, rank: u32) {
         let inner_rc = self.inner.clone();
@@ -700,6 +1072,12 @@ fn create_grpc_client() -> Option<RoomServiceClient<Client>> {
     )))
 }
 
+fn create_map_grpc_client() -> Option<MapServiceClient<Client>> {
+    Some(MapServiceClient::new(Client::new(
+        crate::services::endpoint::grpc_base_url(),
+    )))
+}
+
 fn create_user_grpc_client() -> Option<UserServiceClient<Client>> {
     Some(UserServiceClient::new(Client::new(
         crate::services::endpoint::grpc_base_url(),
@@ -716,6 +1094,81 @@ fn attach_auth<T>(msg: T, token: &Option<String>) -> tonic::Request<T> {
     req
 }
 
+/// Append the JWT to a room WebSocket URL (signaling, relay); browsers can not set headers
+/// on WebSockets, so the server reads it from `?token=`.
+fn with_socket_token(url: &str, token: &Option<String>) -> String {
+    match token {
+        Some(token) if !url.is_empty() => format!("{url}?token={token}"),
+        _ => url.to_string(),
+    }
+}
+
+/// Pin a map on the server and load the pinned revision, checked against the map limits
+/// peers apply to `MapChange`. Returns the revision and the map.
+async fn change_map_grpc(
+    inner: &Rc<RefCell<RoomServiceInner>>,
+    room_id: &str,
+    map_id: &str,
+    token: Option<String>,
+) -> Result<(u32, RouletteConfig), String> {
+    let mut grpc = create_grpc_client().ok_or("Failed to create gRPC client")?;
+    let mut token = token;
+    let request = || UpdateRoomRequest {
+        room_id: room_id.to_string(),
+        map_id: Some(map_id.to_string()),
+        ..Default::default()
+    };
+    let resp = match grpc.update_room(attach_auth(request(), &token)).await {
+        Err(e) if is_unauthenticated(&e) => {
+            tracing::info!("RoomService: UpdateRoom auth failed, attempting re-login");
+            token = relogin(inner).await;
+            if token.is_none() {
+                return Err(e.message().to_string());
+            }
+            grpc.update_room(attach_auth(request(), &token)).await
+        }
+        other => other,
+    }
+    .map_err(|e| e.message().to_string())?;
+    let map_revision = resp
+        .into_inner()
+        .room
+        .map(|r| r.map_revision)
+        .unwrap_or_default();
+
+    let config = if map_id.is_empty() {
+        RouletteConfig::default_classic()
+    } else {
+        let mut grpc = create_map_grpc_client().ok_or("Failed to create gRPC client")?;
+        let map = grpc
+            .get_map(attach_auth(
+                GetMapRequest {
+                    map_id: map_id.to_string(),
+                    revision: Some(map_revision),
+                },
+                &token,
+            ))
+            .await
+            .map_err(|e| e.message().to_string())?
+            .into_inner()
+            .map
+            .ok_or("GetMap returned no map")?;
+        serde_json::from_str::<RouletteConfig>(&map.data).map_err(|e| e.to_string())?
+    };
+    if let Some(violation) = config.validate(&marble_core::bevy::wasm_entry::map_limits()).first() {
+        return Err(format!(
+            "map exceeds limits: {}: {}",
+            violation.field, violation.description
+        ));
+    }
+    Ok((map_revision, config))
+}
+
+/// Whether a member has loaded the map, joined the mesh and marked itself ready.
+fn is_fully_ready(readiness: Readiness) -> bool {
+    readiness.map_loaded && readiness.p2p_connected && readiness.ready
+}
+
 /// Check if a tonic error indicates authentication failure.
 fn is_unauthenticated(status: &tonic::Status) -> bool {
     status.code() == tonic::Code::Unauthenticated
@@ -1160,29 +1613,28 @@ pub fn room_service_provider(props: &RoomServiceProviderProps) -> Html {
                 }
 
                 // =============================================================
-                // Task 4: GetRoomUsers (every ~2 seconds)
+                // Task 4: WatchRoom stream (reopened ~2 seconds after it ends)
                 // =============================================================
                 let now_ms = js_sys::Date::now();
-                let get_room_users_in_flight = inner.borrow().get_room_users_in_flight;
-                let last_poll = inner.borrow().last_room_users_poll_ms;
+                let room_watch_active = inner.borrow().room_watch_active;
+                let last_watch = inner.borrow().last_room_watch_ms;
 
-                if !get_room_users_in_flight && (now_ms - last_poll >= 2000.0) {
-                    inner.borrow_mut().get_room_users_in_flight = true;
-                    inner.borrow_mut().last_room_users_poll_ms = now_ms;
+                if !room_watch_active && (now_ms - last_watch >= 2000.0) {
+                    let epoch = {
+                        let mut inner_mut = inner.borrow_mut();
+                        inner_mut.room_watch_active = true;
+                        inner_mut.last_room_watch_ms = now_ms;
+                        inner_mut.room_watch_epoch
+                    };
                     let inner_c = inner.clone();
                     let room_id = room_id.clone();
 
                     spawn_local(async move {
-                        if let Some(room_users) = get_room_users_grpc(&room_id, &inner_c).await {
-                            let user_ids: Vec<String> = room_users
-                                .iter()
-                                .map(|u| u.user_id.clone())
-                                .collect();
-                            let mut inner_mut = inner_c.borrow_mut();
-                            inner_mut.peer_manager.update_room_users(user_ids);
-                            inner_mut.bump_version();
+                        watch_room_grpc(&room_id, epoch, &inner_c).await;
+                        let mut inner_mut = inner_c.borrow_mut();
+                        if inner_mut.room_watch_epoch == epoch {
+                            inner_mut.room_watch_active = false;
                         }
-                        inner_c.borrow_mut().get_room_users_in_flight = false;
                     });
                 }
 
@@ -1267,6 +1719,23 @@ pub fn room_service_provider(props: &RoomServiceProviderProps) -> Html {
                         );
                     }
                 }
+
+                // =============================================================
+                // Task 8: GetTopology — member key refresh (on unknown origin)
+                // =============================================================
+                let topology_in_flight = inner.borrow().topology_in_flight;
+                if !topology_in_flight && marble_core::bevy::wasm_entry::take_member_keys_stale() {
+                    inner.borrow_mut().topology_in_flight = true;
+                    let inner_c = inner.clone();
+                    let room_id = room_id.clone();
+
+                    spawn_local(async move {
+                        if let Some(topology) = get_topology_grpc(&room_id, &inner_c).await {
+                            inner_c.borrow().apply_topology(&topology);
+                        }
+                        inner_c.borrow_mut().topology_in_flight = false;
+                    });
+                }
             });
 
             move || drop(interval)
@@ -1427,45 +1896,107 @@ async fn resolve_peer_ids_grpc(
     }
 }
 
-async fn get_room_users_grpc(
+/// Follow the room's WatchRoom stream until it ends or the room is left.
+async fn watch_room_grpc(room_id: &str, epoch: u32, inner: &Rc<RefCell<RoomServiceInner>>) {
+    let Some(mut grpc) = create_grpc_client() else {
+        return;
+    };
+
+    let mut token = inner.borrow().auth_token.clone();
+    let req = attach_auth(
+        WatchRoomRequest {
+            room_id: room_id.to_string(),
+        },
+        &token,
+    );
+
+    let resp = match grpc.watch_room(req).await {
+        Err(e) if is_unauthenticated(&e) => {
+            tracing::info!("RoomService: WatchRoom auth failed, attempting re-login");
+            let Some(new_token) = relogin(inner).await else {
+                return;
+            };
+            token = Some(new_token);
+            let req = attach_auth(
+                WatchRoomRequest {
+                    room_id: room_id.to_string(),
+                },
+                &token,
+            );
+            grpc.watch_room(req).await
+        }
+        other => other,
+    };
+
+    let mut stream = match resp {
+        Ok(resp) => resp.into_inner(),
+        Err(e) => {
+            tracing::warn!(error = %e, "RoomService: WatchRoom failed");
+            return;
+        }
+    };
+    tracing::debug!("RoomService: watching room");
+
+    loop {
+        match stream.message().await {
+            Ok(Some(event)) => {
+                let mut inner_mut = inner.borrow_mut();
+                if inner_mut.room_watch_epoch != epoch {
+                    return;
+                }
+                if let Some(event) = event.event {
+                    inner_mut.apply_room_event(event);
+                    inner_mut.bump_version();
+                }
+            }
+            Ok(None) => {
+                tracing::debug!("RoomService: WatchRoom stream ended");
+                return;
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "RoomService: WatchRoom stream failed");
+                return;
+            }
+        }
+    }
+}
+
+async fn get_topology_grpc(
     room_id: &str,
     inner: &Rc<RefCell<RoomServiceInner>>,
-) -> Option<Vec<RoomUser>> {
+) -> Option<PeerTopology> {
     let Some(mut grpc) = create_grpc_client() else {
         return None;
     };
 
     let mut token = inner.borrow().auth_token.clone();
     let req = attach_auth(
-        GetRoomUsersRequest {
+        GetTopologyRequest {
             room_id: room_id.to_string(),
         },
         &token,
     );
 
-    match grpc.get_room_users(req).await {
+    match grpc.get_topology(req).await {
         Ok(resp) => {
-            let users = resp.into_inner().users;
-            tracing::debug!(
-                count = users.len(),
-                "RoomService: fetched room users"
-            );
-            Some(users)
+            let topology = resp.into_inner().topology;
+            tracing::debug!("RoomService: refreshed topology");
+            topology
         }
         Err(e) if is_unauthenticated(&e) => {
-            tracing::info!("RoomService: GetRoomUsers auth failed, attempting re-login");
+            tracing::info!("RoomService: GetTopology auth failed, attempting re-login");
             if let Some(new_token) = relogin(inner).await {
                 token = Some(new_token);
                 let req = attach_auth(
-                    GetRoomUsersRequest {
+                    GetTopologyRequest {
                         room_id: room_id.to_string(),
                     },
                     &token,
                 );
-                match grpc.get_room_users(req).await {
-                    Ok(resp) => Some(resp.into_inner().users),
+                match grpc.get_topology(req).await {
+                    Ok(resp) => resp.into_inner().topology,
                     Err(e) => {
-                        tracing::warn!(error = %e, "RoomService: GetRoomUsers failed after re-login");
+                        tracing::warn!(error = %e, "RoomService: GetTopology failed after re-login");
                         None
                     }
                 }
@@ -1474,7 +2005,7 @@ async fn get_room_users_grpc(
             }
         }
         Err(e) => {
-            tracing::warn!(error = %e, "RoomService: GetRoomUsers failed");
+            tracing::warn!(error = %e, "RoomService: GetTopology failed");
             None
         }
     }
@@ -102,6 +102,7 @@ pub struct GameStateSummary {
     pub frame: u64,
     pub gamerule: String,
     pub map_name: String,
+    pub race_start_at_ms: Option<f64>,
 }
 
 /// Editor state summary.
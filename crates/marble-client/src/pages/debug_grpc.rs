@@ -84,6 +84,8 @@ pub fn debug_grpc_page() -> Html {
                     max_players: max_players_val,
                     room_name: String::new(),
                     is_public: true,
+                    password: String::new(),
+                    ready_check: None,
                 };
                 let result = client.borrow_mut().create_room(req).await;
                 match result {
@@ -115,6 +117,8 @@ pub fn debug_grpc_page() -> Html {
                 let req = JoinRoomRequest {
                     room_id,
                     role: None,
+                    password: String::new(),
+                    invite_token: String::new(),
                 };
                 let result = client.borrow_mut().join_room(req).await;
                 match result {
@@ -146,6 +150,7 @@ pub fn debug_grpc_page() -> Html {
                 let req = StartGameRequest {
                     room_id,
                     start_frame: 0,
+                    force: false,
                 };
                 let result = client.borrow_mut().start_game(req).await;
                 match result {
@@ -179,6 +184,7 @@ pub fn debug_grpc_page() -> Html {
                 let req = KickPlayerRequest {
                     room_id,
                     target_user_id: target_player,
+                    ban: false,
                 };
                 let result = client.borrow_mut().kick_player(req).await;
                 match result {
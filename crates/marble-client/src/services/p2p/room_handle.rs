@@ -252,15 +252,9 @@ impl P2pRoomHandle {
 
         state_handle.set(P2pConnectionState::Connecting);
 
-        // Get topology info
-        let topology = inner.borrow().topology.clone();
-        let topo = topology.unwrap_or(PeerTopology {
-            signaling_url: String::new(),
-            mesh_group: 0,
-            is_bridge: false,
-            connect_to: vec![],
-            bridge_peers: vec![],
-        });
+        // Get topology info; the relay (relayed players, bridges) is connected by
+        // RoomServiceProvider from the topologies it receives
+        let topo = inner.borrow().topology.clone().unwrap_or_default();
 
         let player_id = inner.borrow().player_id.clone();
         let is_host = inner.borrow().is_host;
@@ -193,6 +193,10 @@ mod tests {
                 user_id: "p3".to_string(),
                 peer_id: "peer3".to_string(),
             }],
+            relayed: false,
+            relay_url: String::new(),
+            member_keys: HashMap::new(),
+            host_user_id: String::new(),
         }
     }
 
@@ -0,0 +1,224 @@
+//! Wall-clock synchronization for the race start.
+//!
+//! Peers estimate their clock offset to each remote user (in practice the host)
+//! from `Ping`/`Pong` round trips (NTP-style), so the host can announce the race
+//! start as a wall-clock instant and every client begins the same frame at the
+//! same moment. Estimates are keyed by user id, not peer id, since the exchange
+//! may be relayed by other peers.
+
+use std::collections::{HashMap, VecDeque};
+
+use bevy::prelude::*;
+
+use crate::physics::PHYSICS_DT;
+
+/// Countdown between the host pressing start and the first simulated frame (ms).
+pub const RACE_COUNTDOWN_MS: f64 = 3000.0;
+
+/// Round-trip samples kept per user.
+const SAMPLE_WINDOW: usize = 8;
+
+/// Samples needed before the estimate is considered settled.
+const SETTLED_SAMPLES: usize = 4;
+
+/// Ping interval while the estimate is still settling (ms).
+const WARMUP_PING_INTERVAL_MS: f64 = 500.0;
+
+/// Ping interval once the estimate has settled (ms).
+const STEADY_PING_INTERVAL_MS: f64 = 5000.0;
+
+/// A single clock measurement against a remote user.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClockSample {
+    /// Remote clock minus local clock (ms).
+    pub offset_ms: f64,
+    /// Round-trip time of the exchange (ms).
+    pub rtt_ms: f64,
+}
+
+impl ClockSample {
+    /// Builds a sample from one round trip.
+    ///
+    /// `sent_at` and `received_at` are local wall-clock times; `peer_time` is
+    /// the remote clock when it replied. The reply is assumed to have been
+    /// stamped halfway through the round trip.
+    pub fn from_round_trip(sent_at: f64, peer_time: f64, received_at: f64) -> Option<Self> {
+        if received_at < sent_at {
+            return None;
+        }
+        Some(Self {
+            offset_ms: peer_time - (sent_at + received_at) / 2.0,
+            rtt_ms: received_at - sent_at,
+        })
+    }
+}
+
+/// Per-user clock offset estimates.
+///
+/// Keeps a small window of samples per user and trusts the one with the
+/// lowest RTT, since its midpoint assumption has the smallest error bound.
+#[derive(Resource, Debug, Default)]
+pub struct ClockSync {
+    samples: HashMap<String, VecDeque<ClockSample>>,
+    last_ping_ms: HashMap<String, f64>,
+}
+
+impl ClockSync {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a sample for a user, evicting the oldest beyond the window.
+    pub fn record(&mut self, user_id: &str, sample: ClockSample) {
+        let samples = self.samples.entry(user_id.to_string()).or_default();
+        if samples.len() >= SAMPLE_WINDOW {
+            samples.pop_front();
+        }
+        samples.push_back(sample);
+    }
+
+    /// Estimated offset (remote minus local, ms) of a user's clock.
+    pub fn offset_ms(&self, user_id: &str) -> Option<f64> {
+        self.samples
+            .get(user_id)?
+            .iter()
+            .min_by(|a, b| a.rtt_ms.total_cmp(&b.rtt_ms))
+            .map(|s| s.offset_ms)
+    }
+
+    /// Converts a timestamp on a user's clock to the local clock.
+    ///
+    /// Falls back to the raw timestamp when no sample exists yet.
+    pub fn to_local_ms(&self, user_id: &str, remote_ms: f64) -> f64 {
+        remote_ms - self.offset_ms(user_id).unwrap_or(0.0)
+    }
+
+    /// Whether a user should be pinged now; records the ping when it should.
+    ///
+    /// Pings quickly until enough samples are collected, then backs off.
+    pub fn due_for_ping(&mut self, user_id: &str, now_ms: f64) -> bool {
+        let settled = self
+            .samples
+            .get(user_id)
+            .is_some_and(|s| s.len() >= SETTLED_SAMPLES);
+        let interval = if settled {
+            STEADY_PING_INTERVAL_MS
+        } else {
+            WARMUP_PING_INTERVAL_MS
+        };
+
+        match self.last_ping_ms.get(user_id) {
+            Some(&last) if now_ms - last < interval => false,
+            _ => {
+                self.last_ping_ms.insert(user_id.to_string(), now_ms);
+                true
+            }
+        }
+    }
+
+    /// Drops all state for a user.
+    pub fn remove(&mut self, user_id: &str) {
+        self.samples.remove(user_id);
+        self.last_ping_ms.remove(user_id);
+    }
+}
+
+/// A scheduled synchronized race start.
+///
+/// The simulation holds until the local wall clock reaches `start_at_ms`,
+/// then runs from `start_frame` at the fixed timestep.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RaceStart {
+    /// Start instant on the local wall clock (ms since epoch).
+    pub start_at_ms: f64,
+    /// Frame simulated first at the start instant.
+    pub start_frame: u64,
+    /// Whether the world has been restored to the start state (the host is
+    /// ready immediately; peers once the start snapshot is applied).
+    pub ready: bool,
+}
+
+impl RaceStart {
+    pub fn new(start_at_ms: f64, start_frame: u64, ready: bool) -> Self {
+        Self {
+            start_at_ms,
+            start_frame,
+            ready,
+        }
+    }
+
+    /// Time left until the start instant (ms), zero once started.
+    pub fn remaining_ms(&self, now_ms: f64) -> f64 {
+        (self.start_at_ms - now_ms).max(0.0)
+    }
+
+    /// Frame the simulation should have reached at `now_ms`, or `None`
+    /// before the start instant.
+    pub fn target_frame(&self, now_ms: f64) -> Option<u64> {
+        if now_ms < self.start_at_ms {
+            return None;
+        }
+        let elapsed_secs = (now_ms - self.start_at_ms) / 1000.0;
+        Some(self.start_frame + (elapsed_secs / f64::from(PHYSICS_DT)).round() as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offset_uses_lowest_rtt_sample() {
+        let mut clock = ClockSync::new();
+        assert_eq!(clock.offset_ms("host"), None);
+
+        // Remote clock is 1000ms ahead; the slow exchange has an asymmetric delay.
+        clock.record(
+            "host",
+            ClockSample::from_round_trip(0.0, 1150.0, 200.0).unwrap(),
+        );
+        clock.record(
+            "host",
+            ClockSample::from_round_trip(500.0, 1510.0, 520.0).unwrap(),
+        );
+
+        assert_eq!(clock.offset_ms("host"), Some(1000.0));
+        assert_eq!(clock.to_local_ms("host", 5000.0), 4000.0);
+        assert_eq!(clock.to_local_ms("other", 5000.0), 5000.0);
+
+        assert!(ClockSample::from_round_trip(10.0, 0.0, 5.0).is_none());
+    }
+
+    #[test]
+    fn test_ping_interval_backs_off_once_settled() {
+        let mut clock = ClockSync::new();
+        assert!(clock.due_for_ping("host", 0.0));
+        assert!(!clock.due_for_ping("host", 100.0));
+        assert!(clock.due_for_ping("host", 600.0));
+
+        for _ in 0..SETTLED_SAMPLES {
+            clock.record(
+                "host",
+                ClockSample {
+                    offset_ms: 0.0,
+                    rtt_ms: 10.0,
+                },
+            );
+        }
+        assert!(!clock.due_for_ping("host", 1200.0));
+        assert!(clock.due_for_ping("host", 600.0 + STEADY_PING_INTERVAL_MS));
+
+        clock.remove("host");
+        assert_eq!(clock.offset_ms("host"), None);
+    }
+
+    #[test]
+    fn test_race_start_target_frame() {
+        let start = RaceStart::new(10_000.0, 100, true);
+        assert_eq!(start.target_frame(9_999.0), None);
+        assert_eq!(start.remaining_ms(9_000.0), 1000.0);
+        assert_eq!(start.target_frame(10_000.0), Some(100));
+        assert_eq!(start.target_frame(11_000.0), Some(160));
+        assert_eq!(start.remaining_ms(11_000.0), 0.0);
+    }
+}
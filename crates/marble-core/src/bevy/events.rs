@@ -136,7 +136,20 @@ pub struct DeleteObjectEvent {
 
 /// Message fired when the host should broadcast a GameStart to all peers.
 #[derive(Message, Debug, Clone, Default)]
-pub struct BroadcastGameStartEvent;
+pub struct BroadcastGameStartEvent {
+    /// Server round (`RoomInfo.round`), sent as `GameStart.session_version`.
+    pub round: u64,
+    /// Server-issued seed of the round (`RoomInfo.game_state.rng_seed`).
+    pub seed: u64,
+}
+
+/// Message fired when the host should broadcast a MapChange to all peers.
+#[derive(Message, Debug, Clone)]
+pub struct BroadcastMapChangeEvent {
+    pub map_id: String,
+    pub map_revision: u32,
+    pub config: crate::map::RouletteConfig,
+}
 
 /// Message fired when a peer requests a sync snapshot from the host.
 #[derive(Message, Debug, Clone)]
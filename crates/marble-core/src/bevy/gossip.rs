@@ -3,14 +3,151 @@
 //! Moved from marble-client to marble-core so Bevy systems can directly
 //! access the gossip handler for P2P communication.
 
-use std::collections::HashSet;
+use std::collections::{HashMap, VecDeque};
 
 use bevy::prelude::Resource;
+use ed25519_dalek::SigningKey;
 use marble_proto::play::P2pMessage;
+use marble_proto::play::p2p_message::Payload;
 
 #[cfg(target_arch = "wasm32")]
 use matchbox_socket::PeerId;
 
+use crate::bevy::message_auth::sign_message;
+
+/// Reliable, ordered channel for control and social messages.
+///
+/// Channel indices follow the order `init_p2p_socket` opens them in.
+pub const RELIABLE_CHANNEL: usize = 0;
+/// Unreliable, unordered channel for latency-sensitive sync traffic.
+pub const UNRELIABLE_CHANNEL: usize = 1;
+/// Reliable channel for bulk snapshots, so they never hold up control messages.
+pub const SNAPSHOT_CHANNEL: usize = 2;
+
+/// Default capacity of the seen-message cache.
+const SEEN_CACHE_CAPACITY: usize = 10000;
+
+/// Traffic class of a payload, deciding its channel, default TTL and priority.
+///
+/// Variants are declared from highest to lowest priority.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MessageClass {
+    /// Game lifecycle (GameStart, MapChange).
+    Control,
+    /// Frame hashes; a late hash is useless, the next one follows shortly.
+    Sync,
+    /// Snapshot exchange (SyncRequest, SyncState).
+    Snapshot,
+    /// RTT probes (Ping, Pong).
+    Probe,
+    /// Chat and reactions.
+    Social,
+}
+
+impl MessageClass {
+    pub fn of(payload: &Payload) -> Self {
+        match payload {
+            Payload::GameStart(_) | Payload::MapChange(_) => Self::Control,
+            Payload::FrameHash(_) => Self::Sync,
+            Payload::SyncRequest(_) | Payload::SyncState(_) => Self::Snapshot,
+            Payload::Ping(_) | Payload::Pong(_) => Self::Probe,
+            _ => Self::Social,
+        }
+    }
+
+    /// Class of a message; payload-less messages rank lowest.
+    pub fn of_message(msg: &P2pMessage) -> Self {
+        msg.payload.as_ref().map_or(Self::Social, Self::of)
+    }
+
+    /// Channel the class is sent on.
+    pub fn channel(self) -> usize {
+        match self {
+            Self::Control | Self::Social => RELIABLE_CHANNEL,
+            Self::Sync | Self::Probe => UNRELIABLE_CHANNEL,
+            Self::Snapshot => SNAPSHOT_CHANNEL,
+        }
+    }
+
+    /// TTL of newly created messages.
+    ///
+    /// Broadcasts get enough hops to cross two groups via their bridges. Point-to-point
+    /// classes get a single hop, which is what the server relay needs to pass them on.
+    pub fn default_ttl(self) -> u32 {
+        match self {
+            Self::Control | Self::Sync | Self::Social => 3,
+            Self::Snapshot | Self::Probe => 1,
+        }
+    }
+
+    /// Whether relaying may be skipped when a poll brings more traffic than the relay budget.
+    pub fn is_sheddable(self) -> bool {
+        matches!(self, Self::Probe | Self::Social)
+    }
+}
+
+/// Bounded least-recently-seen cache of message ids.
+///
+/// Seeing an id again refreshes it, so ids still circulating in the mesh are the last to
+/// be evicted.
+pub struct SeenCache {
+    capacity: usize,
+    /// Id -> tick of its last sighting.
+    entries: HashMap<String, u64>,
+    /// Sightings in order; entries whose tick is outdated are skipped on eviction.
+    order: VecDeque<(u64, String)>,
+    tick: u64,
+}
+
+impl SeenCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            tick: 0,
+        }
+    }
+
+    pub fn contains(&self, message_id: &str) -> bool {
+        self.entries.contains_key(message_id)
+    }
+
+    /// Record a sighting. Returns true if the id was not in the cache.
+    pub fn insert(&mut self, message_id: &str) -> bool {
+        self.tick += 1;
+        let fresh = self
+            .entries
+            .insert(message_id.to_string(), self.tick)
+            .is_none();
+        self.order.push_back((self.tick, message_id.to_string()));
+
+        while self.entries.len() > self.capacity {
+            let Some((tick, id)) = self.order.pop_front() else {
+                break;
+            };
+            if self.entries.get(&id) == Some(&tick) {
+                self.entries.remove(&id);
+            }
+        }
+        // Drop outdated sightings once they dominate the queue
+        if self.order.len() > self.capacity * 2 {
+            let entries = &self.entries;
+            self.order
+                .retain(|(tick, id)| entries.get(id) == Some(tick));
+        }
+        fresh
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
 /// Wrapper for gossip message handling.
 pub struct GossipMessage {
     pub message: P2pMessage,
@@ -22,13 +159,13 @@ pub struct GossipMessage {
 #[derive(Resource)]
 pub struct GossipHandler {
     /// Seen message IDs for deduplication.
-    seen_messages: HashSet<String>,
-    /// Maximum seen messages cache size.
-    max_cache_size: usize,
+    seen_messages: SeenCache,
     /// My mesh group ID.
     my_group: u32,
     /// Whether this node is a bridge.
     is_bridge: bool,
+    /// Signs created messages (copied from `MessageAuth`).
+    signing_key: Option<SigningKey>,
     /// Connected peers in the same group.
     #[cfg(target_arch = "wasm32")]
     group_peers: Vec<PeerId>,
@@ -40,10 +177,10 @@ pub struct GossipHandler {
 impl GossipHandler {
     pub fn new(my_group: u32, is_bridge: bool) -> Self {
         Self {
-            seen_messages: HashSet::new(),
-            max_cache_size: 10000,
+            seen_messages: SeenCache::new(SEEN_CACHE_CAPACITY),
             my_group,
             is_bridge,
+            signing_key: None,
             #[cfg(target_arch = "wasm32")]
             group_peers: Vec::new(),
             #[cfg(target_arch = "wasm32")]
@@ -58,6 +195,11 @@ impl GossipHandler {
         self.bridge_peers = bridge_peers;
     }
 
+    /// Set the key that signs created messages.
+    pub fn set_signing_key(&mut self, signing_key: Option<SigningKey>) {
+        self.signing_key = signing_key;
+    }
+
     /// Update bridge status.
     pub fn set_bridge_status(&mut self, is_bridge: bool) {
         self.is_bridge = is_bridge;
@@ -68,35 +210,20 @@ impl GossipHandler {
         self.seen_messages.contains(message_id)
     }
 
-    /// Mark message as seen.
-    pub fn mark_seen(&mut self, message_id: String) {
-        // Evict old entries if cache is full
-        if self.seen_messages.len() >= self.max_cache_size {
-            let to_remove: Vec<_> = self
-                .seen_messages
-                .iter()
-                .take(self.max_cache_size / 2)
-                .cloned()
-                .collect();
-            for id in to_remove {
-                self.seen_messages.remove(&id);
-            }
-        }
-        self.seen_messages.insert(message_id);
+    /// Mark message as seen. Returns true if it was not seen before.
+    pub fn mark_seen(&mut self, message_id: &str) -> bool {
+        self.seen_messages.insert(message_id)
     }
 
     /// Process an incoming message and determine relay targets.
     /// Returns (should_process, relay_targets).
     #[cfg(target_arch = "wasm32")]
     pub fn handle_incoming(&mut self, msg: &P2pMessage, from_peer: PeerId) -> (bool, Vec<PeerId>) {
-        // Check for duplicate
-        if self.is_seen(&msg.message_id) {
+        // Check for duplicate (and mark as seen)
+        if !self.mark_seen(&msg.message_id) {
             return (false, vec![]);
         }
 
-        // Mark as seen
-        self.mark_seen(msg.message_id.clone());
-
         // Check TTL
         if msg.ttl == 0 {
             return (true, vec![]);
@@ -111,37 +238,14 @@ impl GossipHandler {
     /// Get peers to relay message to.
     #[cfg(target_arch = "wasm32")]
     fn get_relay_targets(&self, origin_group: u32, exclude_peer: PeerId) -> Vec<PeerId> {
-        let mut targets: Vec<PeerId> = Vec::new();
-
-        if self.is_bridge && origin_group != self.my_group {
-            // Message from another group via bridge
-            for peer in &self.group_peers {
-                if *peer != exclude_peer {
-                    targets.push(*peer);
-                }
-            }
-        } else if self.is_bridge && origin_group == self.my_group {
-            // Message from my group, I'm a bridge
-            for peer in &self.group_peers {
-                if *peer != exclude_peer {
-                    targets.push(*peer);
-                }
-            }
-            for peer in &self.bridge_peers {
-                if *peer != exclude_peer {
-                    targets.push(*peer);
-                }
-            }
-        } else {
-            // Normal node: relay to connected group peers
-            for peer in &self.group_peers {
-                if *peer != exclude_peer {
-                    targets.push(*peer);
-                }
-            }
-        }
-
-        targets
+        relay_targets(
+            self.is_bridge,
+            self.my_group,
+            origin_group,
+            &self.group_peers,
+            &self.bridge_peers,
+            exclude_peer,
+        )
     }
 
     /// Prepare message for relay (decrement TTL).
@@ -152,36 +256,46 @@ impl GossipHandler {
             origin_group: msg.origin_group,
             origin_user: msg.origin_user.clone(),
             payload: msg.payload.clone(),
+            signature: msg.signature.clone(),
         }
     }
 
-    /// Create a new outgoing message.
-    pub fn create_message(
+    /// Create a new outgoing message with the default TTL of its class, signed
+    /// once a signing key is set.
+    pub fn create_message(&mut self, player_id: &str, payload: Payload) -> P2pMessage {
+        let ttl = MessageClass::of(&payload).default_ttl();
+        self.create_message_with_ttl(player_id, payload, ttl)
+    }
+
+    /// Create a new outgoing message with an explicit TTL, e.g. a probe addressed to a
+    /// user who may be several hops away.
+    pub fn create_message_with_ttl(
         &mut self,
         player_id: &str,
+        payload: Payload,
         ttl: u32,
-        payload: marble_proto::play::p2p_message::Payload,
     ) -> P2pMessage {
         let message_id = uuid::Uuid::new_v4().to_string();
-        self.mark_seen(message_id.clone());
+        self.mark_seen(&message_id);
 
-        P2pMessage {
+        let mut msg = P2pMessage {
             message_id,
             ttl,
             origin_group: self.my_group,
             origin_user: player_id.to_string(),
             payload: Some(payload),
+            signature: Vec::new(),
+        };
+        if let Some(key) = &self.signing_key {
+            sign_message(key, &mut msg);
         }
+        msg
     }
 
     /// Get all peers (for broadcasting own messages).
     #[cfg(target_arch = "wasm32")]
     pub fn get_all_peers(&self) -> Vec<PeerId> {
-        let mut all: Vec<PeerId> = self.group_peers.clone();
-        if self.is_bridge {
-            all.extend(self.bridge_peers.iter().copied());
-        }
-        all
+        broadcast_targets(self.is_bridge, &self.group_peers, &self.bridge_peers)
     }
 
     /// Get my group ID.
@@ -190,6 +304,47 @@ impl GossipHandler {
     }
 }
 
+/// Relay rule shared by the handler and the server's gossip simulator.
+///
+/// Every node relays to its group peers except the sender; a bridge additionally
+/// forwards messages that originated in its own group to the bridges of other groups.
+pub fn relay_targets<P: Copy + PartialEq>(
+    is_bridge: bool,
+    my_group: u32,
+    origin_group: u32,
+    group_peers: &[P],
+    bridge_peers: &[P],
+    exclude_peer: P,
+) -> Vec<P> {
+    let mut targets: Vec<P> = group_peers
+        .iter()
+        .copied()
+        .filter(|peer| *peer != exclude_peer)
+        .collect();
+    if is_bridge && origin_group == my_group {
+        targets.extend(
+            bridge_peers
+                .iter()
+                .copied()
+                .filter(|peer| *peer != exclude_peer),
+        );
+    }
+    targets
+}
+
+/// Peers a node sends its own messages to.
+pub fn broadcast_targets<P: Copy>(
+    is_bridge: bool,
+    group_peers: &[P],
+    bridge_peers: &[P],
+) -> Vec<P> {
+    let mut all = group_peers.to_vec();
+    if is_bridge {
+        all.extend_from_slice(bridge_peers);
+    }
+    all
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -201,8 +356,83 @@ mod tests {
         let msg_id = "test-123".to_string();
         assert!(!handler.is_seen(&msg_id));
 
-        handler.mark_seen(msg_id.clone());
+        assert!(handler.mark_seen(&msg_id));
         assert!(handler.is_seen(&msg_id));
+        assert!(!handler.mark_seen(&msg_id));
+    }
+
+    #[test]
+    fn test_seen_cache_evicts_least_recently_seen() {
+        let mut cache = SeenCache::new(3);
+        for id in ["a", "b", "c"] {
+            assert!(cache.insert(id));
+        }
+        // A duplicate of "a" keeps it alive; "b" is now the oldest
+        assert!(!cache.insert("a"));
+        assert!(cache.insert("d"));
+        assert!(cache.contains("a"));
+        assert!(!cache.contains("b"));
+        assert!(cache.contains("c"));
+        assert_eq!(cache.len(), 3);
+
+        // Repeated sightings do not grow the queue without bound
+        for _ in 0..100 {
+            cache.insert("a");
+        }
+        assert!(cache.order.len() <= 6);
+        assert!(cache.contains("c") && cache.contains("d"));
+    }
+
+    #[test]
+    fn test_message_classes() {
+        use marble_proto::play::{FrameHash, Ping, SyncState};
+
+        let hash = Payload::FrameHash(FrameHash { frame: 1, hash: 2 });
+        let snapshot = Payload::SyncState(SyncState::default());
+        let ping = Payload::Ping(Ping {
+            timestamp: 0.0,
+            to_user: String::new(),
+        });
+
+        assert_eq!(MessageClass::of(&hash).channel(), UNRELIABLE_CHANNEL);
+        assert_eq!(MessageClass::of(&snapshot).channel(), SNAPSHOT_CHANNEL);
+        assert!(MessageClass::Control < MessageClass::of(&hash));
+        assert!(MessageClass::of(&ping).is_sheddable());
+
+        let mut handler = GossipHandler::new(0, false);
+        assert_eq!(handler.create_message("p1", hash).ttl, 3);
+        assert_eq!(handler.create_message("p1", ping.clone()).ttl, 1);
+        assert_eq!(handler.create_message_with_ttl("p1", ping, 3).ttl, 3);
+    }
+
+    #[test]
+    fn test_created_messages_are_signed() {
+        use marble_proto::play::Ping;
+        use marble_proto::room::PeerTopology;
+
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let mut auth = crate::bevy::MessageAuth::new();
+        auth.set_topology(&PeerTopology {
+            member_keys: [("p1".to_string(), key.verifying_key().to_bytes().to_vec())].into(),
+            ..Default::default()
+        });
+
+        let mut handler = GossipHandler::new(0, false);
+        let ping = Payload::Ping(Ping {
+            timestamp: 0.0,
+            to_user: String::new(),
+        });
+        assert!(
+            handler
+                .create_message("p1", ping.clone())
+                .signature
+                .is_empty()
+        );
+
+        handler.set_signing_key(Some(key));
+        let msg = handler.create_message("p1", ping);
+        assert_eq!(auth.verify(&msg), Ok(()));
+        assert_eq!(auth.verify(&handler.prepare_for_relay(&msg)), Ok(()));
     }
 
     #[test]
@@ -214,9 +444,35 @@ mod tests {
             origin_group: 0,
             origin_user: "p1".to_string(),
             payload: None,
+            signature: Vec::new(),
         };
         // Prepare for relay should decrement TTL
         let relayed = handler.prepare_for_relay(&msg);
         assert_eq!(relayed.ttl, 0); // saturating_sub on 0
     }
+
+    #[test]
+    fn test_relay_targets() {
+        let group = [1, 2, 3];
+        let bridges = [10, 20];
+
+        // Normal node: group peers except the sender
+        assert_eq!(relay_targets(false, 0, 0, &group, &bridges, 2), vec![1, 3]);
+        // Bridge relays its own group's messages to other groups too
+        assert_eq!(
+            relay_targets(true, 0, 0, &group, &bridges, 1),
+            vec![2, 3, 10, 20]
+        );
+        // ...but keeps messages from other groups inside its group
+        assert_eq!(
+            relay_targets(true, 0, 1, &group, &bridges, 10),
+            vec![1, 2, 3]
+        );
+
+        assert_eq!(broadcast_targets(false, &group, &bridges), vec![1, 2, 3]);
+        assert_eq!(
+            broadcast_targets(true, &group, &bridges),
+            vec![1, 2, 3, 10, 20]
+        );
+    }
 }
@@ -0,0 +1,286 @@
+//! Origin authentication for P2P messages.
+//!
+//! The server issues every room member an Ed25519 key bound to their user id at
+//! `JoinRoom` and publishes the public keys in the topology. Messages are signed
+//! by their origin and verified on receipt before they are applied or relayed,
+//! so neither a peer nor a relay can speak for another user or for the host.
+
+use std::collections::HashMap;
+
+use bevy::prelude::Resource;
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use marble_proto::play::P2pMessage;
+use marble_proto::play::p2p_message::Payload;
+use marble_proto::room::PeerTopology;
+use prost::Message as _;
+
+/// Why an incoming message was rejected.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum AuthError {
+    #[error("No key for origin '{0}'")]
+    UnknownOrigin(String),
+
+    #[error("Invalid signature from '{0}'")]
+    BadSignature(String),
+
+    #[error("Host payload from non-host '{0}'")]
+    NotHost(String),
+
+    #[error("Payload names '{claimed}' but was sent by '{origin}'")]
+    UserMismatch { origin: String, claimed: String },
+}
+
+/// Whether only the current host may originate a payload.
+pub fn is_host_only(payload: &Payload) -> bool {
+    matches!(
+        payload,
+        Payload::GameStart(_)
+            | Payload::MapChange(_)
+            | Payload::FrameHash(_)
+            | Payload::SyncState(_)
+    )
+}
+
+/// User a payload speaks for, if it names one.
+fn claimed_user(payload: &Payload) -> Option<&str> {
+    match payload {
+        Payload::PlayerJoined(joined) => Some(&joined.user_id),
+        Payload::PlayerInput(input) => Some(&input.user_id),
+        Payload::ChatMessage(chat) => Some(&chat.user_id),
+        Payload::Reaction(reaction) => Some(&reaction.user_id),
+        _ => None,
+    }
+}
+
+/// Bytes covered by the signature: the message with its TTL zeroed (relays
+/// decrement it) and without the signature itself.
+pub fn signing_bytes(msg: &P2pMessage) -> Vec<u8> {
+    P2pMessage {
+        ttl: 0,
+        signature: Vec::new(),
+        ..msg.clone()
+    }
+    .encode_to_vec()
+}
+
+/// Signs a message in place.
+pub fn sign_message(key: &SigningKey, msg: &mut P2pMessage) {
+    msg.signature = key.sign(&signing_bytes(msg)).to_bytes().to_vec();
+}
+
+/// This member's signing key and the public keys of the room.
+///
+/// Outlives the socket: keys arrive from `JoinRoom` and topology refreshes,
+/// independent of when the P2P socket is (re)created.
+#[derive(Resource, Default)]
+pub struct MessageAuth {
+    signing_key: Option<SigningKey>,
+    member_keys: HashMap<String, VerifyingKey>,
+    host_user_id: String,
+    /// Bumped on every topology update, so held messages know when to retry
+    key_generation: u64,
+}
+
+impl MessageAuth {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Installs the key issued by `JoinRoom`; a malformed key clears it.
+    /// Returns whether a key is installed.
+    pub fn set_signing_key(&mut self, key: &[u8]) -> bool {
+        self.signing_key = <[u8; 32]>::try_from(key)
+            .ok()
+            .map(|bytes| SigningKey::from_bytes(&bytes));
+        self.signing_key.is_some()
+    }
+
+    pub fn signing_key(&self) -> Option<&SigningKey> {
+        self.signing_key.as_ref()
+    }
+
+    /// Replaces the member keys and host with those of a topology.
+    ///
+    /// Malformed keys are skipped, so their owners stay unverifiable.
+    pub fn set_topology(&mut self, topology: &PeerTopology) {
+        self.member_keys = topology
+            .member_keys
+            .iter()
+            .filter_map(|(user_id, key)| {
+                let bytes = <[u8; 32]>::try_from(key.as_slice()).ok()?;
+                Some((user_id.clone(), VerifyingKey::from_bytes(&bytes).ok()?))
+            })
+            .collect();
+        self.host_user_id.clone_from(&topology.host_user_id);
+        self.key_generation += 1;
+    }
+
+    pub fn host_user_id(&self) -> &str {
+        &self.host_user_id
+    }
+
+    pub fn key_generation(&self) -> u64 {
+        self.key_generation
+    }
+
+    /// Checks that a message was signed by its origin, that host payloads come
+    /// from the host, and that payloads naming a user were sent by that user.
+    pub fn verify(&self, msg: &P2pMessage) -> Result<(), AuthError> {
+        let origin = &msg.origin_user;
+        let key = self
+            .member_keys
+            .get(origin)
+            .ok_or_else(|| AuthError::UnknownOrigin(origin.clone()))?;
+        let signature = Signature::from_slice(&msg.signature)
+            .map_err(|_| AuthError::BadSignature(origin.clone()))?;
+        key.verify_strict(&signing_bytes(msg), &signature)
+            .map_err(|_| AuthError::BadSignature(origin.clone()))?;
+
+        let Some(payload) = &msg.payload else {
+            return Ok(());
+        };
+        if is_host_only(payload) && *origin != self.host_user_id {
+            return Err(AuthError::NotHost(origin.clone()));
+        }
+        if let Some(claimed) = claimed_user(payload)
+            && claimed != origin.as_str()
+        {
+            return Err(AuthError::UserMismatch {
+                origin: origin.clone(),
+                claimed: claimed.to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use marble_proto::play::{ChatMessage, FrameHash};
+
+    fn key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    fn auth_for(members: &[(&str, &SigningKey)], host: &str) -> MessageAuth {
+        let mut auth = MessageAuth::new();
+        auth.set_topology(&PeerTopology {
+            member_keys: members
+                .iter()
+                .map(|(user_id, key)| {
+                    (
+                        (*user_id).to_string(),
+                        key.verifying_key().to_bytes().to_vec(),
+                    )
+                })
+                .collect(),
+            host_user_id: host.to_string(),
+            ..Default::default()
+        });
+        auth
+    }
+
+    fn signed(key: &SigningKey, origin: &str, payload: Payload) -> P2pMessage {
+        let mut msg = P2pMessage {
+            message_id: "m1".to_string(),
+            ttl: 3,
+            origin_group: 0,
+            origin_user: origin.to_string(),
+            payload: Some(payload),
+            signature: Vec::new(),
+        };
+        sign_message(key, &mut msg);
+        msg
+    }
+
+    fn chat(user_id: &str, content: &str) -> Payload {
+        Payload::ChatMessage(ChatMessage {
+            user_id: user_id.to_string(),
+            content: content.to_string(),
+            timestamp_ms: 0,
+        })
+    }
+
+    #[test]
+    fn test_signature_survives_relay_but_not_tampering() {
+        let (host, alice) = (key(1), key(2));
+        let auth = auth_for(&[("host", &host), ("alice", &alice)], "host");
+
+        let mut msg = signed(&alice, "alice", chat("alice", "hi"));
+        assert_eq!(auth.verify(&msg), Ok(()));
+
+        // Relays only decrement the TTL
+        msg.ttl -= 1;
+        assert_eq!(auth.verify(&msg), Ok(()));
+
+        let mut tampered = msg.clone();
+        tampered.payload = Some(chat("alice", "forged"));
+        assert!(matches!(
+            auth.verify(&tampered),
+            Err(AuthError::BadSignature(_))
+        ));
+
+        let mut unsigned = msg.clone();
+        unsigned.signature.clear();
+        assert!(matches!(
+            auth.verify(&unsigned),
+            Err(AuthError::BadSignature(_))
+        ));
+    }
+
+    #[test]
+    fn test_forged_origins_are_rejected() {
+        let (host, alice, mallory) = (key(1), key(2), key(3));
+        let auth = auth_for(&[("host", &host), ("alice", &alice)], "host");
+
+        // Claiming another member's origin without their key
+        let forged = signed(&mallory, "alice", chat("alice", "hi"));
+        assert!(matches!(
+            auth.verify(&forged),
+            Err(AuthError::BadSignature(_))
+        ));
+        let stranger = signed(&mallory, "mallory", chat("mallory", "hi"));
+        assert_eq!(
+            auth.verify(&stranger),
+            Err(AuthError::UnknownOrigin("mallory".to_string()))
+        );
+
+        // A joiner's held message verifies once the refreshed keys arrive
+        let refreshed = auth_for(
+            &[("host", &host), ("alice", &alice), ("mallory", &mallory)],
+            "host",
+        );
+        assert_eq!(refreshed.key_generation(), 1);
+        assert_eq!(refreshed.verify(&stranger), Ok(()));
+
+        // Speaking for someone else inside a validly signed message
+        let impersonation = signed(&alice, "alice", chat("host", "hi"));
+        assert!(matches!(
+            auth.verify(&impersonation),
+            Err(AuthError::UserMismatch { .. })
+        ));
+
+        // Host payloads only from the host
+        let hash = Payload::FrameHash(FrameHash { frame: 1, hash: 2 });
+        assert!(is_host_only(&hash));
+        assert_eq!(
+            auth.verify(&signed(&alice, "alice", hash.clone())),
+            Err(AuthError::NotHost("alice".to_string()))
+        );
+        assert_eq!(auth.verify(&signed(&host, "host", hash)), Ok(()));
+    }
+
+    #[test]
+    fn test_signing_key_install() {
+        let mut auth = MessageAuth::new();
+        assert!(auth.signing_key().is_none());
+        assert!(auth.set_signing_key(&key(1).to_bytes()));
+        assert_eq!(
+            auth.signing_key().map(SigningKey::verifying_key),
+            Some(key(1).verifying_key())
+        );
+        assert!(!auth.set_signing_key(&[0; 16]));
+        assert!(auth.signing_key().is_none());
+    }
+}
@@ -4,9 +4,11 @@
 //! including physics simulation via direct Rapier2D integration, ECS components,
 //! resources, and systems for both game play and editor modes.
 
+pub mod clock_sync;
 pub mod components;
 pub mod events;
 pub mod gossip;
+pub mod message_auth;
 pub mod plugin;
 pub mod rapier_plugin;
 pub mod resources;
@@ -20,13 +22,17 @@ pub(crate) mod test_utils;
 #[cfg(target_arch = "wasm32")]
 pub mod p2p_socket;
 #[cfg(target_arch = "wasm32")]
+pub mod relay_socket;
+#[cfg(target_arch = "wasm32")]
 pub mod wasm_entry;
 
 #[cfg(target_arch = "wasm32")]
 pub use wasm_entry::*;
 
+pub use clock_sync::{ClockSync, RaceStart};
 pub use components::*;
 pub use events::*;
+pub use message_auth::MessageAuth;
 pub use plugin::{AppMode, EditorState, MarbleHeadlessPlugin, MarbleUnifiedPlugin};
 pub use rapier_plugin::{
     CollisionEvent, CollisionEventFlags, MarblePhysicsPlugin, PhysicsBody, PhysicsCollider,
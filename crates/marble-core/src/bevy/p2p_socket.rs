@@ -7,7 +7,12 @@
 use std::collections::HashMap;
 
 use bevy::prelude::*;
+use marble_proto::play::P2pMessage;
 use matchbox_socket::{PeerId, WebRtcSocket};
+use prost::Message as _;
+
+use crate::bevy::gossip::MessageClass;
+use crate::bevy::relay_socket::{RELAY_PEER_ID, RelaySocket};
 
 /// Wrapper around `WebRtcSocket` that implements Send/Sync for WASM.
 ///
@@ -31,10 +36,52 @@ pub struct P2pSocketRes {
     pub player_id: String,
     /// Whether this client is the game host.
     pub is_host: bool,
-    /// The host's peer ID (if known).
+    /// The host's peer ID while directly connected (set from `peer_player_map`).
     pub host_peer_id: Option<PeerId>,
     /// Currently connected peer IDs.
     pub connected_peers: Vec<PeerId>,
     /// Mapping from peer_id to player_id (resolved via server).
     pub peer_player_map: HashMap<PeerId, String>,
+    /// Server relay connection, while the topology carries a `relay_url`.
+    pub relay: Option<RelaySocket>,
+    /// Relay URL version applied to `relay` (see `wasm_entry::set_relay_url`).
+    pub relay_version: u64,
+}
+
+impl P2pSocketRes {
+    /// Send a message to `peers` on the channel of its class; [`RELAY_PEER_ID`] goes to
+    /// the server relay.
+    pub fn send_message(&mut self, msg: &P2pMessage, peers: impl IntoIterator<Item = PeerId>) {
+        let channel = self
+            .socket
+            .0
+            .channel_mut(MessageClass::of_message(msg).channel());
+        let data = msg.encode_to_vec();
+        for peer in peers {
+            if peer == RELAY_PEER_ID {
+                if let Some(relay) = &self.relay {
+                    relay.send(&data);
+                }
+            } else {
+                channel.send(data.clone().into_boxed_slice(), peer);
+            }
+        }
+    }
+
+    /// Connected peer of `user_id`, per the server-resolved `peer_player_map`.
+    pub fn connected_peer_of(&self, user_id: &str) -> Option<PeerId> {
+        self.connected_peers
+            .iter()
+            .copied()
+            .find(|peer| self.peer_player_map.get(peer).is_some_and(|u| u == user_id))
+    }
+
+    /// Gossip neighbours: the connected WebRTC peers plus the relay while it is set.
+    pub fn gossip_peers(&self) -> Vec<PeerId> {
+        let mut peers = self.connected_peers.clone();
+        if self.relay.is_some() {
+            peers.push(RELAY_PEER_ID);
+        }
+        peers
+    }
 }
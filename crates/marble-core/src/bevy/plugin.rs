@@ -6,7 +6,9 @@
 
 use bevy::prelude::*;
 
+use crate::bevy::clock_sync::ClockSync;
 use crate::bevy::events::*;
+use crate::bevy::message_auth::MessageAuth;
 use crate::bevy::rapier_plugin::{MarblePhysicsPlugin, PhysicsSet};
 use crate::bevy::resources::*;
 use crate::bevy::state_store::StateStores;
@@ -93,6 +95,8 @@ impl Plugin for MarbleHeadlessPlugin {
             .insert_resource(ObjectEntityMap::default())
             .insert_resource(InitialTransforms::default())
             .insert_resource(SyncState::default())
+            .insert_resource(ClockSync::default())
+            .insert_resource(MessageAuth::default())
             .insert_resource(systems::LiveRankings::default())
             .insert_resource(self.command_queue.clone().unwrap_or_default())
             .insert_resource(self.state_stores.clone().unwrap_or_default());
@@ -133,6 +137,7 @@ impl Plugin for MarbleHeadlessPlugin {
 
         // P2P sync messages
         app.add_message::<BroadcastGameStartEvent>()
+            .add_message::<BroadcastMapChangeEvent>()
             .add_message::<SyncSnapshotRequestEvent>();
 
         // Editor messages
@@ -204,6 +209,7 @@ impl Plugin for MarbleHeadlessPlugin {
             app.add_systems(
                 Update,
                 (
+                    p2p_sync::pickup_message_keys,
                     p2p_sync::pickup_pending_p2p,
                     p2p_sync::handle_p2p_disconnect,
                     p2p_sync::poll_p2p_socket,
@@ -227,9 +233,19 @@ impl Plugin for MarbleHeadlessPlugin {
                     p2p_sync::handle_sync_request,
                     p2p_sync::apply_sync_snapshot,
                     p2p_sync::broadcast_game_start,
+                    p2p_sync::broadcast_map_change,
                 )
                     .run_if(in_state(AppMode::Game)),
             );
+
+            // Synchronized race start: hold during the countdown, then catch up
+            app.add_systems(
+                Update,
+                p2p_sync::pace_race_start
+                    .after(p2p_sync::apply_sync_snapshot)
+                    .after(p2p_sync::broadcast_game_start)
+                    .run_if(in_state(AppMode::Game)),
+            );
         }
 
         // Core state sync (always active)
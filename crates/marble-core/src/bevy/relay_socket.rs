@@ -0,0 +1,97 @@
+//! Server relay WebSocket for P2P gossip.
+//!
+//! Players whose WebRTC links keep failing are moved to the server relay, and bridges
+//! connect to it as well (both get `PeerTopology.relay_url`). The relay is one more
+//! gossip neighbour: its binary frames are encoded `P2pMessage`s, received and sent
+//! through the same path as WebRTC messages under the reserved [`RELAY_PEER_ID`].
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use matchbox_socket::PeerId;
+use wasm_bindgen::JsCast;
+use wasm_bindgen::closure::Closure;
+use web_sys::{BinaryType, MessageEvent, WebSocket};
+
+/// Peer id standing for the server relay in gossip peer lists.
+pub const RELAY_PEER_ID: PeerId = PeerId(uuid::Uuid::nil());
+
+/// Delay before reopening a relay connection that closed (ms).
+pub const RELAY_RETRY_MS: f64 = 2000.0;
+
+/// An open (or opening) relay connection.
+pub struct RelaySocket {
+    url: String,
+    ws: WebSocket,
+    inbound: Rc<RefCell<VecDeque<Vec<u8>>>>,
+    opened_at_ms: f64,
+    _on_message: Closure<dyn FnMut(MessageEvent)>,
+}
+
+// SAFETY: WASM is single-threaded; no data races possible.
+unsafe impl Send for RelaySocket {}
+unsafe impl Sync for RelaySocket {}
+
+impl RelaySocket {
+    /// Open a relay connection; `url` already carries the `?token=`.
+    pub fn connect(url: &str) -> Option<Self> {
+        let ws = match WebSocket::new(url) {
+            Ok(ws) => ws,
+            Err(e) => {
+                tracing::warn!("[relay] Failed to open relay socket: {:?}", e);
+                return None;
+            }
+        };
+        ws.set_binary_type(BinaryType::Arraybuffer);
+
+        let inbound: Rc<RefCell<VecDeque<Vec<u8>>>> = Rc::default();
+        let queue = Rc::clone(&inbound);
+        let on_message = Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+            if let Ok(buffer) = event.data().dyn_into::<js_sys::ArrayBuffer>() {
+                queue
+                    .borrow_mut()
+                    .push_back(js_sys::Uint8Array::new(&buffer).to_vec());
+            }
+        });
+        ws.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+
+        Some(Self {
+            url: url.to_string(),
+            ws,
+            inbound,
+            opened_at_ms: js_sys::Date::now(),
+            _on_message: on_message,
+        })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Whether the connection closed and is due to be reopened.
+    pub fn needs_reconnect(&self, now_ms: f64) -> bool {
+        self.ws.ready_state() == WebSocket::CLOSED && now_ms - self.opened_at_ms >= RELAY_RETRY_MS
+    }
+
+    /// Take the frames received since the last call.
+    pub fn receive(&mut self) -> Vec<Vec<u8>> {
+        self.inbound.borrow_mut().drain(..).collect()
+    }
+
+    /// Send a frame; dropped while the connection is not open (gossip recovers via sync).
+    pub fn send(&self, data: &[u8]) {
+        if self.ws.ready_state() == WebSocket::OPEN
+            && let Err(e) = self.ws.send_with_u8_array(data)
+        {
+            tracing::debug!("[relay] Send failed: {:?}", e);
+        }
+    }
+}
+
+impl Drop for RelaySocket {
+    fn drop(&mut self) {
+        self.ws.set_onmessage(None);
+        let _ = self.ws.close();
+    }
+}
@@ -11,6 +11,7 @@ use parking_lot::Mutex;
 use rand::SeedableRng;
 use rand_chacha::ChaCha8Rng;
 
+use crate::bevy::clock_sync::RaceStart;
 use crate::dsl::GameContext;
 use crate::game::Player;
 use crate::keyframe::KeyframeExecutor;
@@ -290,6 +291,8 @@ pub struct SyncState {
     pub pending_hashes: Vec<(u64, u64)>,
     /// Session version (incremented on each game start).
     pub session_version: u64,
+    /// Scheduled synchronized start, until the simulation has caught up to it.
+    pub race_start: Option<RaceStart>,
 }
 
 /// Local player ID for camera following.
@@ -407,8 +410,15 @@ pub enum GameCommand {
     SetSyncHost { is_host: bool },
     /// Set the game rule.
     SetGamerule { gamerule: String },
-    /// Tell Bevy to broadcast a GameStart message to all peers.
-    BroadcastGameStart,
+    /// Tell Bevy to broadcast a GameStart message to all peers, for the server's
+    /// round and seed.
+    BroadcastGameStart { round: u64, seed: u64 },
+    /// Load a new map and tell peers to load it too (host, after `UpdateRoom`).
+    BroadcastMapChange {
+        map_id: String,
+        map_revision: u32,
+        config: RouletteConfig,
+    },
     /// Spawn marbles at specific positions (peer: uses host-provided coordinates).
     SpawnMarblesAt { positions: Vec<[f32; 2]> },
 
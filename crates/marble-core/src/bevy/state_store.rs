@@ -93,6 +93,8 @@ pub struct GameStateSummary {
     pub frame: u64,
     pub gamerule: String,
     pub map_name: String,
+    /// Local wall-clock instant (ms) of a pending synchronized start.
+    pub race_start_at_ms: Option<f64>,
 }
 
 // ============================================================================
@@ -9,11 +9,11 @@ use crate::bevy::systems::editor::{
     EditorStateRes, SelectObjectEvent, SnapConfig, UpdateObjectEvent,
 };
 use crate::bevy::{
-    AddObjectEvent, AddPlayerEvent, BroadcastGameStartEvent, ClearMarblesEvent, CommandQueue,
-    DeleteObjectEvent, DeterministicRng, GameCamera, GameCommand, GameContextRes, LoadMapEvent,
-    LocalPlayerId, MainCamera, MapConfig, MarbleGameState, PreviewSequenceEvent, RemovePlayerEvent,
-    ResetSimulationEvent, SpawnMarblesAtEvent, SpawnMarblesEvent, StartSimulationEvent,
-    StopSimulationEvent, SyncState,
+    AddObjectEvent, AddPlayerEvent, BroadcastGameStartEvent, BroadcastMapChangeEvent,
+    ClearMarblesEvent, CommandQueue, DeleteObjectEvent, DeterministicRng, GameCamera, GameCommand,
+    GameContextRes, LoadMapEvent, LocalPlayerId, MainCamera, MapConfig, MarbleGameState,
+    PreviewSequenceEvent, RemovePlayerEvent, ResetSimulationEvent, SpawnMarblesAtEvent,
+    SpawnMarblesEvent, StartSimulationEvent, StopSimulationEvent, SyncState,
 };
 use crate::game::Player;
 
@@ -38,6 +38,7 @@ pub fn process_commands(
     mut game_context: ResMut<GameContextRes>,
     mut sync_state: ResMut<SyncState>,
     mut broadcast_events: MessageWriter<BroadcastGameStartEvent>,
+    mut map_change_events: MessageWriter<BroadcastMapChangeEvent>,
 ) {
     // Use drain_until_yield() to process game commands until Yield or empty.
     // This allows frame-separated command processing.
@@ -130,9 +131,24 @@ pub fn process_commands(
                 tracing::info!("[command] SetGamerule: {}", gamerule);
                 game_state.selected_gamerule = gamerule;
             }
-            GameCommand::BroadcastGameStart => {
-                tracing::info!("[command] BroadcastGameStart");
-                broadcast_events.write(BroadcastGameStartEvent);
+            GameCommand::BroadcastGameStart { round, seed } => {
+                tracing::info!("[command] BroadcastGameStart: round={}", round);
+                broadcast_events.write(BroadcastGameStartEvent { round, seed });
+            }
+            GameCommand::BroadcastMapChange {
+                map_id,
+                map_revision,
+                config,
+            } => {
+                tracing::info!("[command] BroadcastMapChange: {}@{}", map_id, map_revision);
+                load_map_events.write(LoadMapEvent {
+                    config: config.clone(),
+                });
+                map_change_events.write(BroadcastMapChangeEvent {
+                    map_id,
+                    map_revision,
+                    config,
+                });
             }
             // Yield is consumed by drain_until_yield(), should not reach here
             GameCommand::Yield => {}
@@ -2,14 +2,18 @@
 //!
 //! These systems handle the complete P2P game sync lifecycle:
 //! - Socket lifecycle (pickup from WASM, disconnect)
-//! - Message polling, dispatch, and gossip relay
+//! - Message polling, origin verification, dispatch, and gossip relay
 //! - Frame hash broadcasting (host → peers)
 //! - Desync detection (peer)
 //! - Sync snapshot request/response
 //! - Game start broadcasting (host → peers)
+//! - Clock sync and synchronized race start pacing
+//! - Map change broadcasting (host → peers)
 
+use std::collections::VecDeque;
 use std::hash::{Hash, Hasher};
 
+use bevy::app::FixedMain;
 use bevy::prelude::*;
 use matchbox_socket::PeerId;
 use prost::Message as ProstMessage;
@@ -18,17 +22,26 @@ use rapier2d::prelude::*;
 use marble_proto::play::p2p_message::Payload;
 use marble_proto::play::{FrameHash, P2pMessage, Ping, Pong};
 
-use crate::bevy::gossip::GossipHandler;
+use crate::bevy::clock_sync::{ClockSample, ClockSync, RACE_COUNTDOWN_MS, RaceStart};
+use crate::bevy::gossip::{
+    GossipHandler, MessageClass, RELIABLE_CHANNEL, SNAPSHOT_CHANNEL, UNRELIABLE_CHANNEL,
+};
+use crate::bevy::message_auth::{AuthError, MessageAuth};
 use crate::bevy::p2p_socket::P2pSocketRes;
+use crate::bevy::relay_socket::{RELAY_PEER_ID, RelaySocket};
 use crate::bevy::rapier_plugin::{
     PhysicsBody, PhysicsExternalForce, PhysicsWorldRes, USER_DATA_MARBLE, encode_user_data,
 };
 use crate::bevy::sync_snapshot::{BevySyncSnapshot, MapObjectTransformSnapshot, MarbleSnapshot};
-use crate::bevy::wasm_entry::{take_p2p_disconnect, take_pending_p2p, take_pending_peer_updates};
+use crate::bevy::wasm_entry::{
+    map_limits, mark_member_keys_stale, relay_url_since, take_p2p_disconnect,
+    take_pending_member_keys, take_pending_p2p, take_pending_peer_updates,
+    take_pending_signing_key,
+};
 use crate::bevy::{
-    BroadcastGameStartEvent, CommandQueue, DeterministicRng, GameCommand, GameContextRes,
-    KeyframeExecutors, KeyframeTarget, Marble, MarbleGameState, MarbleVisual, StateStores,
-    SyncSnapshotRequestEvent, SyncState,
+    BroadcastGameStartEvent, BroadcastMapChangeEvent, CommandQueue, DeterministicRng, GameCommand,
+    GameContextRes, KeyframeExecutors, KeyframeTarget, Marble, MarbleGameState, MarbleVisual,
+    StateStores, SyncSnapshotRequestEvent, SyncState,
 };
 
 /// Hash broadcast interval in frames (0.5 seconds at 60 FPS).
@@ -37,12 +50,50 @@ const HASH_BROADCAST_INTERVAL: u64 = 30;
 /// Sync cooldown in frames (3 seconds at 60 FPS).
 const SYNC_COOLDOWN: u64 = 180;
 
+/// Messages relayed per poll before sheddable classes stop being relayed.
+const RELAY_BUDGET: usize = 64;
+
+/// Messages from unknown origins held until the next member key update.
+const MAX_UNVERIFIED_MESSAGES: usize = 64;
+
+/// Maximum extra fixed updates stepped per frame while catching up to the race start.
+const MAX_CATCH_UP_STEPS: u64 = 8;
+
+/// How long past the start instant a peer waits for the start snapshot (ms).
+const START_SNAPSHOT_TIMEOUT_MS: f64 = 5000.0;
+
+/// TTL of pings addressed to a user and their pongs, as far as broadcasts reach.
+const ADDRESSED_PROBE_TTL: u32 = 3;
+
 // ============================================================================
 // Socket Lifecycle Systems
 // ============================================================================
 
+/// Installs a pending signing key and member keys from the WASM global slots.
+pub fn pickup_message_keys(
+    mut auth: ResMut<MessageAuth>,
+    mut gossip: Option<ResMut<GossipHandler>>,
+) {
+    if let Some(signing_key) = take_pending_signing_key() {
+        if !auth.set_signing_key(&signing_key) {
+            tracing::warn!("[p2p] Invalid signing key; own messages go out unsigned");
+        }
+        if let Some(gossip) = gossip.as_mut() {
+            gossip.set_signing_key(auth.signing_key().cloned());
+        }
+    }
+    if let Some(topology) = take_pending_member_keys() {
+        auth.set_topology(&topology);
+        tracing::info!(
+            "[p2p] Member keys updated: {} members, host={}",
+            topology.member_keys.len(),
+            auth.host_user_id()
+        );
+    }
+}
+
 /// Picks up a pending P2P socket from the WASM global slot and inserts it as a Resource.
-pub fn pickup_pending_p2p(mut commands: Commands) {
+pub fn pickup_pending_p2p(mut commands: Commands, auth: Res<MessageAuth>) {
     if let Some(pending) = take_pending_p2p() {
         tracing::info!(
             "[p2p] Picking up pending P2P socket: player={}, host={}",
@@ -50,7 +101,8 @@ pub fn pickup_pending_p2p(mut commands: Commands) {
             pending.is_host
         );
 
-        let gossip = GossipHandler::new(pending.mesh_group, pending.is_bridge);
+        let mut gossip = GossipHandler::new(pending.mesh_group, pending.is_bridge);
+        gossip.set_signing_key(auth.signing_key().cloned());
 
         commands.insert_resource(P2pSocketRes {
             socket: crate::bevy::p2p_socket::P2pSocketWrapper(pending.socket),
@@ -59,17 +111,29 @@ pub fn pickup_pending_p2p(mut commands: Commands) {
             host_peer_id: None,
             connected_peers: Vec::new(),
             peer_player_map: std::collections::HashMap::new(),
+            relay: None,
+            relay_version: 0,
         });
         commands.insert_resource(gossip);
     }
 }
 
 /// Handles P2P disconnect requests by removing the socket Resource.
-pub fn handle_p2p_disconnect(mut commands: Commands) {
+pub fn handle_p2p_disconnect(
+    mut commands: Commands,
+    mut sync_state: ResMut<SyncState>,
+    mut clock_sync: ResMut<ClockSync>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+) {
     if take_p2p_disconnect() {
         tracing::info!("[p2p] Disconnecting P2P socket");
         commands.remove_resource::<P2pSocketRes>();
         commands.remove_resource::<GossipHandler>();
+
+        // Never leave the simulation held by a start that will not happen
+        sync_state.race_start = None;
+        *clock_sync = ClockSync::default();
+        virtual_time.unpause();
     }
 }
 
@@ -77,6 +141,14 @@ pub fn handle_p2p_disconnect(mut commands: Commands) {
 // Message Polling System
 // ============================================================================
 
+/// Local state holding messages whose origin had no key yet, most likely a member
+/// who joined after our last topology. Re-verified once the member keys change.
+#[derive(Default)]
+pub struct UnverifiedMessages {
+    messages: VecDeque<(PeerId, P2pMessage)>,
+    key_generation: u64,
+}
+
 /// Polls the P2P socket for incoming messages and peer updates.
 ///
 /// This is the main message loop, replacing `run_message_loop` from marble-client.
@@ -85,9 +157,12 @@ pub fn poll_p2p_socket(
     mut socket_res: Option<ResMut<P2pSocketRes>>,
     mut gossip: Option<ResMut<GossipHandler>>,
     mut sync_state: ResMut<SyncState>,
+    mut clock_sync: ResMut<ClockSync>,
+    auth: Res<MessageAuth>,
     command_queue: Res<CommandQueue>,
     state_stores: Res<StateStores>,
     mut sync_request_events: MessageWriter<SyncSnapshotRequestEvent>,
+    mut unverified: Local<UnverifiedMessages>,
 ) {
     let Some(socket_res) = socket_res.as_mut() else {
         return;
@@ -145,11 +220,37 @@ pub fn poll_p2p_socket(
         }
     }
 
+    // (Re)connect the server relay when the topology's relay URL changes or it closed
+    let relay_url = match relay_url_since(socket_res.relay_version) {
+        Some((version, url)) => {
+            socket_res.relay_version = version;
+            (socket_res.relay.as_ref().map(RelaySocket::url) != Some(url.as_str())).then_some(url)
+        }
+        None => socket_res
+            .relay
+            .as_ref()
+            .filter(|relay| relay.needs_reconnect(js_sys::Date::now()))
+            .map(|relay| relay.url().to_string()),
+    };
+    if let Some(url) = relay_url {
+        let had_relay = socket_res.relay.is_some();
+        socket_res.relay = None;
+        if !url.is_empty() {
+            tracing::info!("[p2p] Connecting to the server relay");
+            socket_res.relay = RelaySocket::connect(&url);
+        }
+        peers_changed |= had_relay != socket_res.relay.is_some();
+    }
+
     if peers_changed {
         // Update gossip handler with current peers
-        gossip.set_peers(socket_res.connected_peers.clone(), vec![]);
+        gossip.set_peers(socket_res.gossip_peers(), vec![]);
     }
 
+    // The host's link comes from the server-resolved peer mapping, never from message
+    // fields a relaying peer could rewrite
+    socket_res.host_peer_id = socket_res.connected_peer_of(auth.host_user_id());
+
     // Update StateStore peers when peers changed OR player_id mappings were updated
     if peers_changed || had_updates {
         let peer_infos: Vec<crate::bevy::state_store::PeerInfo> = socket_res
@@ -164,13 +265,53 @@ pub fn poll_p2p_socket(
         state_stores.peers.set_peers(peer_infos);
     }
 
-    // 2. Receive messages
-    let received = socket_res.socket.0.channel_mut(0).receive();
+    // 2. Receive messages from all channels, highest priority class first
+    let mut received: Vec<(PeerId, P2pMessage)> =
+        [RELIABLE_CHANNEL, UNRELIABLE_CHANNEL, SNAPSHOT_CHANNEL]
+            .into_iter()
+            .flat_map(|channel| socket_res.socket.0.channel_mut(channel).receive())
+            .filter_map(|(peer_id, data)| Some((peer_id, P2pMessage::decode(&*data).ok()?)))
+            .collect();
+    if let Some(relay) = socket_res.relay.as_mut() {
+        received.extend(
+            relay
+                .receive()
+                .into_iter()
+                .filter_map(|data| Some((RELAY_PEER_ID, P2pMessage::decode(&*data).ok()?))),
+        );
+    }
+    // Retry held messages once new member keys are installed
+    if unverified.key_generation != auth.key_generation() {
+        unverified.key_generation = auth.key_generation();
+        received.extend(unverified.messages.drain(..));
+    }
+    received.sort_by_key(|(_, msg)| MessageClass::of_message(msg));
 
-    for (peer_id, data) in received {
-        let Ok(msg) = P2pMessage::decode(&*data) else {
+    let mut relayed = 0;
+    for (peer_id, msg) in received {
+        // Verify before dedup, so a forged copy can not shadow the genuine message,
+        // and before processing or relaying, so forgeries go nowhere
+        if gossip.is_seen(&msg.message_id) {
             continue;
-        };
+        }
+        if let Err(e) = auth.verify(&msg) {
+            if matches!(e, AuthError::UnknownOrigin(_)) {
+                // Likely a member who joined after our last topology: hold the message
+                // for the refreshed keys, dropping the oldest when full
+                mark_member_keys_stale();
+                tracing::debug!("[p2p] Held message relayed by {}: {}", peer_id, e);
+                let held = &mut unverified.messages;
+                if !held.iter().any(|(_, m)| m.message_id == msg.message_id) {
+                    if held.len() == MAX_UNVERIFIED_MESSAGES {
+                        held.pop_front();
+                    }
+                    held.push_back((peer_id, msg));
+                }
+            } else {
+                tracing::warn!("[p2p] Dropped message relayed by {}: {}", peer_id, e);
+            }
+            continue;
+        }
 
         let (should_process, relay_targets) = gossip.handle_incoming(&msg, peer_id);
 
@@ -180,6 +321,7 @@ pub fn poll_p2p_socket(
                     socket_res.as_mut(),
                     gossip.as_mut(),
                     &mut sync_state,
+                    &mut clock_sync,
                     &command_queue,
                     &state_stores,
                     &mut sync_request_events,
@@ -190,42 +332,48 @@ pub fn poll_p2p_socket(
             }
         }
 
-        // Relay if needed
-        if !relay_targets.is_empty() {
-            let relay_msg = gossip.prepare_for_relay(&msg);
-            let relay_data = relay_msg.encode_to_vec();
-            for target in relay_targets {
-                socket_res
-                    .socket
-                    .0
-                    .channel_mut(0)
-                    .send(relay_data.clone().into_boxed_slice(), target);
-            }
+        // Relay if needed; under load, chat and probes are not passed on
+        if relay_targets.is_empty()
+            || (relayed >= RELAY_BUDGET && MessageClass::of_message(&msg).is_sheddable())
+        {
+            continue;
         }
+        relayed += 1;
+        socket_res.send_message(&gossip.prepare_for_relay(&msg), relay_targets);
+    }
+
+    // 3. Peers keep sampling the host's clock for the synchronized start. The host may
+    //    not be a direct peer, so the ping is addressed to it and gossiped until it arrives.
+    let host_user_id = auth.host_user_id();
+    if !sync_state.is_host
+        && !host_user_id.is_empty()
+        && clock_sync.due_for_ping(host_user_id, js_sys::Date::now())
+    {
+        let msg = gossip.create_message_with_ttl(
+            &socket_res.player_id,
+            Payload::Ping(Ping {
+                timestamp: js_sys::Date::now(),
+                to_user: host_user_id.to_string(),
+            }),
+            ADDRESSED_PROBE_TTL,
+        );
+        socket_res.send_message(&msg, gossip.get_all_peers());
     }
 
-    // 3. Process outgoing P2P commands (chat, reaction, ping)
+    // 4. Process outgoing P2P commands (chat, reaction, ping)
     for cmd in command_queue.drain_p2p_send() {
         match cmd {
             GameCommand::SendChat { content } => {
                 let timestamp_ms = js_sys::Date::now() as u64;
                 let msg = gossip.create_message(
                     &socket_res.player_id,
-                    3,
                     Payload::ChatMessage(marble_proto::play::ChatMessage {
                         user_id: socket_res.player_id.clone(),
                         content: content.clone(),
                         timestamp_ms,
                     }),
                 );
-                let data = msg.encode_to_vec();
-                for peer in gossip.get_all_peers() {
-                    socket_res
-                        .socket
-                        .0
-                        .channel_mut(0)
-                        .send(data.clone().into_boxed_slice(), peer);
-                }
+                socket_res.send_message(&msg, gossip.get_all_peers());
                 // Also add to local chat store
                 state_stores.chat.add_message(
                     socket_res.player_id.clone(),
@@ -237,21 +385,13 @@ pub fn poll_p2p_socket(
                 let timestamp_ms = js_sys::Date::now() as u64;
                 let msg = gossip.create_message(
                     &socket_res.player_id,
-                    3,
                     Payload::Reaction(marble_proto::play::Reaction {
                         user_id: socket_res.player_id.clone(),
                         emoji: emoji.clone(),
                         timestamp_ms,
                     }),
                 );
-                let data = msg.encode_to_vec();
-                for peer in gossip.get_all_peers() {
-                    socket_res
-                        .socket
-                        .0
-                        .channel_mut(0)
-                        .send(data.clone().into_boxed_slice(), peer);
-                }
+                socket_res.send_message(&msg, gossip.get_all_peers());
                 // Also add to local reaction store
                 state_stores.reactions.add_reaction(
                     socket_res.player_id.clone(),
@@ -262,36 +402,24 @@ pub fn poll_p2p_socket(
             GameCommand::SendPing => {
                 let msg = gossip.create_message(
                     &socket_res.player_id,
-                    1,
                     Payload::Ping(Ping {
                         timestamp: js_sys::Date::now(),
+                        to_user: String::new(),
                     }),
                 );
-                let data = msg.encode_to_vec();
-                for peer in gossip.get_all_peers() {
-                    socket_res
-                        .socket
-                        .0
-                        .channel_mut(0)
-                        .send(data.clone().into_boxed_slice(), peer);
-                }
+                socket_res.send_message(&msg, gossip.get_all_peers());
             }
             GameCommand::SendPingTo { peer_id } => {
                 if let Ok(uuid) = uuid::Uuid::parse_str(&peer_id) {
                     let target = PeerId::from(uuid);
                     let msg = gossip.create_message(
                         &socket_res.player_id,
-                        1,
                         Payload::Ping(Ping {
                             timestamp: js_sys::Date::now(),
+                            to_user: String::new(),
                         }),
                     );
-                    let data = msg.encode_to_vec();
-                    socket_res
-                        .socket
-                        .0
-                        .channel_mut(0)
-                        .send(data.into_boxed_slice(), target);
+                    socket_res.send_message(&msg, [target]);
                     tracing::debug!("[p2p] Sent targeted ping to {}", peer_id);
                 }
             }
@@ -306,6 +434,7 @@ fn process_p2p_payload(
     socket_res: &mut P2pSocketRes,
     gossip: &mut GossipHandler,
     sync_state: &mut SyncState,
+    clock_sync: &mut ClockSync,
     command_queue: &CommandQueue,
     state_stores: &StateStores,
     sync_request_events: &mut MessageWriter<SyncSnapshotRequestEvent>,
@@ -381,21 +510,34 @@ fn process_p2p_payload(
                 // Instead, send SyncRequest to host to get the full snapshot.
             }
 
-            // Set host peer id
-            socket_res.host_peer_id = Some(peer_id);
+            // Schedule the start on our clock; the host's clock was sampled via Ping/Pong.
+            // The origin is the verified host, whichever peer relayed the message.
+            if game_start.start_at_ms > 0.0 {
+                let start_at_ms = match clock_sync.offset_ms(&msg.origin_user) {
+                    Some(offset_ms) => game_start.start_at_ms - offset_ms,
+                    None => {
+                        tracing::warn!(
+                            "[p2p] No clock samples from host {}, assuming its clock matches ours",
+                            msg.origin_user
+                        );
+                        game_start.start_at_ms
+                    }
+                };
+                sync_state.race_start =
+                    Some(RaceStart::new(start_at_ms, game_start.start_frame, false));
+                tracing::info!(
+                    "[p2p] Race starts at frame {} in {:.0}ms",
+                    game_start.start_frame,
+                    start_at_ms - js_sys::Date::now()
+                );
+            }
 
             // Send SyncRequest to host for full state snapshot (including marbles)
             let sync_msg = gossip.create_message(
                 &socket_res.player_id,
-                1,
                 Payload::SyncRequest(marble_proto::play::SyncRequest { from_frame: 0 }),
             );
-            let sync_data = sync_msg.encode_to_vec();
-            socket_res
-                .socket
-                .0
-                .channel_mut(0)
-                .send(sync_data.into_boxed_slice(), peer_id);
+            socket_res.send_message(&sync_msg, [socket_res.host_peer_id.unwrap_or(peer_id)]);
 
             tracing::info!("[p2p] Sent SyncRequest to host after GameStart");
         }
@@ -434,12 +576,6 @@ fn process_p2p_payload(
                 return;
             }
 
-            // Set host peer ID if not yet known (e.g., from auto-sync before GameStart)
-            if socket_res.host_peer_id.is_none() {
-                socket_res.host_peer_id = Some(peer_id);
-                tracing::info!("[p2p] Set host_peer_id from SyncState: {}", peer_id);
-            }
-
             tracing::info!("[p2p] Received SyncState at frame {}", sync_state_msg.frame);
 
             // Store pending snapshot for apply_sync_snapshot system
@@ -465,31 +601,93 @@ fn process_p2p_payload(
             );
         }
 
+        Payload::MapChange(change) => {
+            // Only peers reload (host loaded the map before broadcasting)
+            if sync_state.is_host {
+                return;
+            }
+
+            match serde_json::from_slice::<crate::map::RouletteConfig>(&change.config) {
+                Ok(config) => {
+                    // Untrusted map data: never load what the server would not store
+                    if let Some(violation) = config.validate(&map_limits()).first() {
+                        tracing::warn!(
+                            "[p2p] Rejected MapChange {}@{} from {}: {}: {}",
+                            change.map_id,
+                            change.map_revision,
+                            msg.origin_user,
+                            violation.field,
+                            violation.description
+                        );
+                        return;
+                    }
+                    tracing::info!(
+                        "[p2p] Received MapChange: {}@{}",
+                        change.map_id,
+                        change.map_revision
+                    );
+                    command_queue.push(GameCommand::ClearMarbles);
+                    command_queue.push(GameCommand::LoadMap { config });
+                }
+                Err(e) => {
+                    tracing::warn!("[p2p] Invalid MapChange config from {}: {}", peer_id, e);
+                }
+            }
+        }
+
         Payload::Ping(ping) => {
-            // Reply with pong
-            let pong = gossip.create_message(
+            // Addressed pings are answered by their addressee only; the relay passes them on
+            if !ping.to_user.is_empty() && ping.to_user != socket_res.player_id {
+                return;
+            }
+
+            // Reply with pong, back the way an addressed ping came
+            let (to_user, ttl, targets) = if ping.to_user.is_empty() {
+                (
+                    String::new(),
+                    MessageClass::Probe.default_ttl(),
+                    vec![peer_id],
+                )
+            } else {
+                (
+                    msg.origin_user.clone(),
+                    ADDRESSED_PROBE_TTL,
+                    gossip.get_all_peers(),
+                )
+            };
+            let pong = gossip.create_message_with_ttl(
                 &socket_res.player_id,
-                1,
                 Payload::Pong(Pong {
                     timestamp: ping.timestamp,
+                    reply_timestamp: js_sys::Date::now(),
+                    to_user,
                 }),
+                ttl,
             );
-            let pong_data = pong.encode_to_vec();
-            socket_res
-                .socket
-                .0
-                .channel_mut(0)
-                .send(pong_data.into_boxed_slice(), peer_id);
+            socket_res.send_message(&pong, targets);
         }
 
         Payload::Pong(pong) => {
+            if !pong.to_user.is_empty() && pong.to_user != socket_res.player_id {
+                return;
+            }
+
             let now = js_sys::Date::now();
             let rtt = (now - pong.timestamp) as u32;
-            tracing::debug!("[p2p] RTT to {}: {}ms", peer_id, rtt);
-            // Record pong in PongStore for Yew to consume
-            state_stores
-                .pongs
-                .record_pong(peer_id.to_string(), pong.timestamp);
+            tracing::debug!("[p2p] RTT to {}: {}ms", msg.origin_user, rtt);
+            // Older clients leave reply_timestamp unset
+            if pong.reply_timestamp > 0.0
+                && let Some(sample) =
+                    ClockSample::from_round_trip(pong.timestamp, pong.reply_timestamp, now)
+            {
+                clock_sync.record(&msg.origin_user, sample);
+            }
+            // Record direct pongs in PongStore for Yew to consume
+            if pong.to_user.is_empty() {
+                state_stores
+                    .pongs
+                    .record_pong(peer_id.to_string(), pong.timestamp);
+            }
         }
 
         _ => {
@@ -581,21 +779,13 @@ pub fn broadcast_frame_hash(
 
     let msg = gossip.create_message(
         &socket_res.player_id,
-        3,
         Payload::FrameHash(FrameHash {
             frame: game_state.frame,
             hash,
         }),
     );
 
-    let data = msg.encode_to_vec();
-    for peer in gossip.get_all_peers() {
-        socket_res
-            .socket
-            .0
-            .channel_mut(0)
-            .send(data.clone().into_boxed_slice(), peer);
-    }
+    socket_res.send_message(&msg, gossip.get_all_peers());
 }
 
 // ============================================================================
@@ -678,17 +868,11 @@ pub fn check_desync(
     if let Some(host_peer) = socket_res.host_peer_id {
         let msg = gossip.create_message(
             &socket_res.player_id,
-            1,
             Payload::SyncRequest(marble_proto::play::SyncRequest {
                 from_frame: current_frame,
             }),
         );
-        let data = msg.encode_to_vec();
-        socket_res
-            .socket
-            .0
-            .channel_mut(0)
-            .send(data.into_boxed_slice(), host_peer);
+        socket_res.send_message(&msg, [host_peer]);
 
         sync_state.last_sync_frame = current_frame;
         tracing::info!(
@@ -808,18 +992,12 @@ pub fn handle_sync_request(
 
                     let msg = gossip.create_message(
                         &socket_res.player_id,
-                        1,
                         Payload::SyncState(marble_proto::play::SyncState {
                             frame: game_state.frame,
                             state: state_bytes,
                         }),
                     );
-                    let data = msg.encode_to_vec();
-                    socket_res
-                        .socket
-                        .0
-                        .channel_mut(0)
-                        .send(data.into_boxed_slice(), target_peer);
+                    socket_res.send_message(&msg, [target_peer]);
 
                     tracing::info!(
                         "[p2p] Sent sync snapshot to peer {} at frame {} (physics_world: {} bytes)",
@@ -942,6 +1120,13 @@ pub fn apply_sync_snapshot(
     game_state.rng_seed = snapshot.rng_seed;
     game_state.selected_gamerule = snapshot.selected_gamerule;
 
+    // A snapshot taken at or after the start frame is the race start state
+    if let Some(race_start) = sync_state.race_start.as_mut()
+        && snapshot.frame >= race_start.start_frame
+    {
+        race_start.ready = true;
+    }
+
     // 4. Restore RNG and GameContext with full internal state
     if let Some(det_rng) = snapshot.det_rng {
         rng.rng = det_rng;
@@ -1120,9 +1305,13 @@ pub fn broadcast_game_start(
         return;
     };
 
-    for _ in events.read() {
-        // Increment session version
-        sync_state.session_version += 1;
+    for event in events.read() {
+        // The session is the server's round, so peers drop GameStarts of earlier rounds
+        sync_state.session_version = event.round;
+
+        // Hold the simulation at the current frame until the countdown ends
+        let start_at_ms = js_sys::Date::now() + RACE_COUNTDOWN_MS;
+        sync_state.race_start = Some(RaceStart::new(start_at_ms, game_state.frame, true));
 
         // Build player list as JSON for initial_state
         let player_names: Vec<&str> = game_state.players.iter().map(|p| p.name.as_str()).collect();
@@ -1153,29 +1342,119 @@ pub fn broadcast_game_start(
 
         let msg = gossip.create_message(
             &socket_res.player_id,
-            3,
             Payload::GameStart(marble_proto::play::GameStart {
-                seed: game_state.rng_seed,
+                seed: event.seed,
                 initial_state,
                 gamerule: game_state.selected_gamerule.clone(),
                 session_version: sync_state.session_version,
+                start_at_ms,
+                start_frame: game_state.frame,
             }),
         );
 
-        let data = msg.encode_to_vec();
-        for peer in gossip.get_all_peers() {
-            socket_res
-                .socket
-                .0
-                .channel_mut(0)
-                .send(data.clone().into_boxed_slice(), peer);
-        }
+        socket_res.send_message(&msg, gossip.get_all_peers());
 
         tracing::info!(
             "[p2p] Broadcast GameStart: seed={}, {} players, session={}",
-            game_state.rng_seed,
+            event.seed,
             game_state.players.len(),
             sync_state.session_version,
         );
     }
 }
+
+// ============================================================================
+// Race Start Pacing (Game mode, Update)
+// ============================================================================
+
+/// Holds the simulation until the scheduled race start, then catches up.
+///
+/// Virtual time is paused during the countdown, and on peers until the start
+/// snapshot has been applied, so no fixed updates run. Once started, a client
+/// behind the wall-clock target frame steps extra fixed updates (bounded per
+/// frame) until it has caught up.
+pub fn pace_race_start(world: &mut World) {
+    let Some(race_start) = world.resource::<SyncState>().race_start else {
+        return;
+    };
+    let now = js_sys::Date::now();
+
+    let Some(target) = race_start.target_frame(now) else {
+        world.resource_mut::<Time<Virtual>>().pause();
+        return;
+    };
+
+    if !race_start.ready {
+        if now - race_start.start_at_ms < START_SNAPSHOT_TIMEOUT_MS {
+            world.resource_mut::<Time<Virtual>>().pause();
+            return;
+        }
+        tracing::warn!("[p2p] Start snapshot did not arrive, starting unsynchronized");
+        world.resource_mut::<SyncState>().race_start = None;
+        world.resource_mut::<Time<Virtual>>().unpause();
+        return;
+    }
+
+    world.resource_mut::<Time<Virtual>>().unpause();
+
+    let behind = target.saturating_sub(world.resource::<MarbleGameState>().frame);
+    for _ in 0..behind.min(MAX_CATCH_UP_STEPS) {
+        world.run_schedule(FixedMain);
+    }
+
+    if behind <= MAX_CATCH_UP_STEPS {
+        world.resource_mut::<SyncState>().race_start = None;
+        tracing::info!(
+            "[p2p] Race started at frame {} ({} frames caught up)",
+            race_start.start_frame,
+            behind
+        );
+    }
+}
+
+// ============================================================================
+// Map Change Broadcasting (Host only, Update)
+// ============================================================================
+
+/// Broadcasts a MapChange message to all peers when triggered.
+pub fn broadcast_map_change(
+    mut events: MessageReader<BroadcastMapChangeEvent>,
+    mut socket_res: Option<ResMut<P2pSocketRes>>,
+    mut gossip: Option<ResMut<GossipHandler>>,
+) {
+    let Some(socket_res) = socket_res.as_mut() else {
+        for _ in events.read() {}
+        return;
+    };
+    let Some(gossip) = gossip.as_mut() else {
+        for _ in events.read() {}
+        return;
+    };
+
+    for event in events.read() {
+        let config = match serde_json::to_vec(&event.config) {
+            Ok(config) => config,
+            Err(e) => {
+                tracing::warn!("[p2p] Failed to serialize map config: {}", e);
+                continue;
+            }
+        };
+
+        let msg = gossip.create_message(
+            &socket_res.player_id,
+            Payload::MapChange(marble_proto::play::MapChange {
+                map_id: event.map_id.clone(),
+                map_revision: event.map_revision,
+                config,
+            }),
+        );
+
+        socket_res.send_message(&msg, gossip.get_all_peers());
+
+        tracing::info!(
+            "[p2p] Broadcast MapChange: {}@{}",
+            event.map_id,
+            event.map_revision
+        );
+    }
+}
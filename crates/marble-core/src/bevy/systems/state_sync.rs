@@ -7,7 +7,7 @@ use bevy::prelude::*;
 use crate::bevy::systems::editor::{EditorStateRes, SnapConfig};
 use crate::bevy::{
     EditorStateSummary, GameStateSummary, KeyframeExecutors, MapConfig, MapLoadedEvent, Marble,
-    MarbleGameState, PlayerInfo, SnapConfigSummary, StateStores,
+    MarbleGameState, PlayerInfo, SnapConfigSummary, StateStores, SyncState,
 };
 
 /// Resource to store calculated live rankings.
@@ -26,6 +26,7 @@ pub fn sync_game_state_to_stores(
     map_config: Option<Res<MapConfig>>,
     state_stores: Res<StateStores>,
     live_rankings: Option<Res<LiveRankings>>,
+    sync_state: Res<SyncState>,
 ) {
     // Sync game state summary
     let summary = GameStateSummary {
@@ -37,6 +38,7 @@ pub fn sync_game_state_to_stores(
             .as_ref()
             .map(|c| c.0.meta.name.clone())
             .unwrap_or_default(),
+        race_start_at_ms: sync_state.race_start.map(|start| start.start_at_ms),
     };
     state_stores.game.update(summary);
 
@@ -7,9 +7,12 @@ use std::sync::atomic::{AtomicBool, Ordering};
 
 use bevy::prelude::*;
 use bevy::winit::{UpdateMode, WinitSettings};
+use marble_proto::room::PeerTopology;
 use matchbox_socket::WebRtcSocket;
+use prost::Message as _;
 use wasm_bindgen::prelude::*;
 
+use crate::MapLimits;
 use crate::bevy::{CameraMode, CommandQueue, GameCommand, MarbleUnifiedPlugin, StateStores};
 use crate::map::RouletteConfig;
 use crate::marble::Color;
@@ -74,6 +77,22 @@ static P2P_DISCONNECT: AtomicBool = AtomicBool::new(false);
 static PENDING_PEER_UPDATES: parking_lot::Mutex<Vec<(String, String)>> =
     parking_lot::Mutex::new(Vec::new());
 
+/// Pending message signing key (from JoinRoom).
+static PENDING_SIGNING_KEY: parking_lot::Mutex<Option<Vec<u8>>> = parking_lot::Mutex::new(None);
+
+/// Pending topology carrying the room's member keys (from JoinRoom / GetTopology).
+static PENDING_MEMBER_KEYS: parking_lot::Mutex<Option<PeerTopology>> =
+    parking_lot::Mutex::new(None);
+
+/// Set when a message arrived from an origin without a known key.
+static MEMBER_KEYS_STALE: AtomicBool = AtomicBool::new(false);
+
+/// Map limits of the current room's server (from `RoomInfo.map_limits`).
+static MAP_LIMITS: parking_lot::Mutex<Option<MapLimits>> = parking_lot::Mutex::new(None);
+
+/// Relay URL of the latest topology (empty = no relay) and its version.
+static RELAY_URL: parking_lot::Mutex<(u64, String)> = parking_lot::Mutex::new((0, String::new()));
+
 fn ensure_global_state() {
     let mut guard = GLOBAL_STATE.lock().unwrap();
     if guard.is_none() {
@@ -516,7 +535,26 @@ pub fn send_command(command_json: &str) -> Result<(), JsValue> {
                 .to_string();
             GameCommand::SetGamerule { gamerule }
         }
-        "broadcast_game_start" => GameCommand::BroadcastGameStart,
+        "broadcast_game_start" => {
+            let round = value["round"]
+                .as_u64()
+                .ok_or_else(|| JsValue::from_str("Missing 'round' field"))?;
+            let seed = value["seed"]
+                .as_u64()
+                .ok_or_else(|| JsValue::from_str("Missing 'seed' field"))?;
+            GameCommand::BroadcastGameStart { round, seed }
+        }
+        "broadcast_map_change" => {
+            let map_id = value["map_id"].as_str().unwrap_or_default().to_string();
+            let map_revision = value["map_revision"].as_u64().unwrap_or(0) as u32;
+            let config: RouletteConfig = serde_json::from_value(value["config"].clone())
+                .map_err(|e| JsValue::from_str(&format!("Invalid map config: {}", e)))?;
+            GameCommand::BroadcastMapChange {
+                map_id,
+                map_revision,
+                config,
+            }
+        }
 
         // P2P chat/reaction commands
         "send_chat" => {
@@ -789,7 +827,12 @@ pub fn init_p2p_socket(
         is_host
     );
 
-    let (socket, loop_fut) = WebRtcSocket::new_reliable(signaling_url);
+    // Channel order must match the indices in `gossip`
+    let (socket, loop_fut) = WebRtcSocket::builder(signaling_url)
+        .add_reliable_channel() // RELIABLE_CHANNEL
+        .add_unreliable_channel() // UNRELIABLE_CHANNEL
+        .add_reliable_channel() // SNAPSHOT_CHANNEL
+        .build();
     wasm_bindgen_futures::spawn_local(async move {
         if let Err(e) = loop_fut.await {
             tracing::error!("[marble] P2P signaling error: {:?}", e);
@@ -824,6 +867,59 @@ pub fn update_peer_player_id(peer_id_str: &str, player_id: &str) {
         .push((peer_id_str.to_string(), player_id.to_string()));
 }
 
+/// Set the P2P message signing key issued by `JoinRoom`.
+///
+/// The Bevy `pickup_message_keys` system installs it next frame.
+#[wasm_bindgen]
+pub fn set_signing_key(signing_key: &[u8]) {
+    PENDING_SIGNING_KEY.lock().replace(signing_key.to_vec());
+}
+
+/// Update the room's member keys from an encoded `PeerTopology`.
+///
+/// The Bevy `pickup_message_keys` system installs them next frame.
+#[wasm_bindgen]
+pub fn update_member_keys(topology: &[u8]) {
+    match PeerTopology::decode(topology) {
+        Ok(topology) => {
+            PENDING_MEMBER_KEYS.lock().replace(topology);
+        }
+        Err(e) => tracing::warn!("[marble] update_member_keys: invalid topology: {}", e),
+    }
+}
+
+/// Check and reset whether member keys need a refresh (a message came from an
+/// origin without a known key). Yew answers with `GetTopology`.
+#[wasm_bindgen]
+pub fn take_member_keys_stale() -> bool {
+    MEMBER_KEYS_STALE.swap(false, Ordering::SeqCst)
+}
+
+/// Set the server relay URL of the latest topology, with the `?token=` attached.
+///
+/// Empty closes the relay. The Bevy `poll_p2p_socket` system (re)connects on change,
+/// also for P2P sockets created later.
+#[wasm_bindgen]
+pub fn set_relay_url(relay_url: &str) {
+    let mut current = RELAY_URL.lock();
+    if current.1 != relay_url {
+        current.0 += 1;
+        current.1 = relay_url.to_string();
+    }
+}
+
+/// Set the map limits of the room's server; `None` falls back to the defaults.
+///
+/// Peers check `MapChange` maps against them, so maps the server accepted are loaded.
+pub fn set_map_limits(limits: Option<MapLimits>) {
+    *MAP_LIMITS.lock() = limits;
+}
+
+/// Map limits of the room's server, or the defaults before any room was joined.
+pub fn map_limits() -> MapLimits {
+    MAP_LIMITS.lock().clone().unwrap_or_default()
+}
+
 // --- Internal accessors for Bevy systems ---
 
 /// Take the pending P2P init data (called by `pickup_pending_p2p` system).
@@ -840,3 +936,24 @@ pub fn take_p2p_disconnect() -> bool {
 pub fn take_pending_peer_updates() -> Vec<(String, String)> {
     std::mem::take(&mut *PENDING_PEER_UPDATES.lock())
 }
+
+/// Take the pending signing key.
+pub fn take_pending_signing_key() -> Option<Vec<u8>> {
+    PENDING_SIGNING_KEY.lock().take()
+}
+
+/// Take the pending member-key topology.
+pub fn take_pending_member_keys() -> Option<PeerTopology> {
+    PENDING_MEMBER_KEYS.lock().take()
+}
+
+/// The relay URL if its version differs from `seen_version`, with the current version.
+pub fn relay_url_since(seen_version: u64) -> Option<(u64, String)> {
+    let current = RELAY_URL.lock();
+    (current.0 != seen_version).then(|| current.clone())
+}
+
+/// Ask Yew to refresh the member keys.
+pub fn mark_member_keys_stale() {
+    MEMBER_KEYS_STALE.store(true, Ordering::SeqCst);
+}
@@ -25,6 +25,7 @@ pub mod marble;
 pub mod physics;
 pub mod sync;
 pub mod util;
+pub mod validation;
 
 // Bevy integration
 pub mod bevy;
@@ -40,3 +41,4 @@ pub use map::{
 pub use marble::{Color, DEFAULT_MARBLE_RADIUS, Marble, MarbleId, MarbleManager, PlayerId};
 pub use physics::{PHYSICS_DT, PhysicsWorld, default_gravity};
 pub use sync::SyncSnapshot;
+pub use validation::{MapLimits, MapViolation};
@@ -0,0 +1,466 @@
+//! Structural validation and resource limits for untrusted map data.
+//!
+//! Maps uploaded by users are parsed into [`RouletteConfig`] and checked here
+//! before they are stored, so that peers never receive a map that fails to
+//! load or is expensive enough to stall the simulation.
+
+use std::collections::HashSet;
+
+use cel::Program;
+use serde::Deserialize;
+
+use crate::dsl::{BoolOrExpr, NumberOrExpr, Vec2OrExpr};
+use crate::map::{Keyframe, LiveRankingConfig, MapObject, ObjectRole, RouletteConfig, Shape};
+
+/// Resource limits applied to a map.
+///
+/// Deserializable so servers can load it from configuration; missing fields keep their defaults
+/// and unknown fields are rejected.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MapLimits {
+    /// Maximum number of objects.
+    pub max_objects: usize,
+    /// Maximum number of keyframe sequences.
+    pub max_keyframe_sequences: usize,
+    /// Maximum number of keyframes summed over all sequences.
+    pub max_keyframes: usize,
+    /// Maximum length of a single CEL expression in bytes.
+    pub max_expr_len: usize,
+    /// Maximum absolute value of any static coordinate or extent (meters).
+    pub max_coordinate: f32,
+    /// Maximum bezier approximation segments.
+    pub max_bezier_segments: u32,
+    /// Maximum length of object ids and sequence names.
+    pub max_id_len: usize,
+}
+
+impl Default for MapLimits {
+    fn default() -> Self {
+        Self {
+            max_objects: 512,
+            max_keyframe_sequences: 64,
+            max_keyframes: 1024,
+            max_expr_len: 256,
+            max_coordinate: 1000.0,
+            max_bezier_segments: 128,
+            max_id_len: 64,
+        }
+    }
+}
+
+impl MapLimits {
+    /// Wire form sent to clients in `RoomInfo.map_limits`.
+    pub fn to_proto(&self) -> marble_proto::room::MapLimits {
+        let saturate = |value: usize| u32::try_from(value).unwrap_or(u32::MAX);
+        marble_proto::room::MapLimits {
+            max_objects: saturate(self.max_objects),
+            max_keyframe_sequences: saturate(self.max_keyframe_sequences),
+            max_keyframes: saturate(self.max_keyframes),
+            max_expr_len: saturate(self.max_expr_len),
+            max_coordinate: self.max_coordinate,
+            max_bezier_segments: self.max_bezier_segments,
+            max_id_len: saturate(self.max_id_len),
+        }
+    }
+
+    pub fn from_proto(limits: &marble_proto::room::MapLimits) -> Self {
+        Self {
+            max_objects: limits.max_objects as usize,
+            max_keyframe_sequences: limits.max_keyframe_sequences as usize,
+            max_keyframes: limits.max_keyframes as usize,
+            max_expr_len: limits.max_expr_len as usize,
+            max_coordinate: limits.max_coordinate,
+            max_bezier_segments: limits.max_bezier_segments,
+            max_id_len: limits.max_id_len as usize,
+        }
+    }
+}
+
+/// A single validation failure.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapViolation {
+    /// JSON path of the offending field, e.g. `objects[3].shape.radius`.
+    pub field: String,
+    /// Human-readable reason.
+    pub description: String,
+}
+
+impl RouletteConfig {
+    /// Checks structure and limits, returning every violation found.
+    ///
+    /// An empty result means the map is safe to store and load.
+    pub fn validate(&self, limits: &MapLimits) -> Vec<MapViolation> {
+        let mut v = Validator {
+            limits,
+            violations: Vec::new(),
+        };
+        v.check_config(self);
+        v.violations
+    }
+}
+
+struct Validator<'a> {
+    limits: &'a MapLimits,
+    violations: Vec<MapViolation>,
+}
+
+impl Validator<'_> {
+    fn push(&mut self, field: impl Into<String>, description: impl Into<String>) {
+        self.violations.push(MapViolation {
+            field: field.into(),
+            description: description.into(),
+        });
+    }
+
+    fn check_config(&mut self, config: &RouletteConfig) {
+        if config.objects.is_empty() {
+            self.push("objects", "map must contain at least one object");
+        }
+        if config.objects.len() > self.limits.max_objects {
+            self.push(
+                "objects",
+                format!("at most {} objects allowed", self.limits.max_objects),
+            );
+            // Skip per-object checks; the map is rejected regardless.
+            return;
+        }
+        if !config.objects.iter().any(|o| o.role == ObjectRole::Spawner) {
+            self.push("objects", "map must contain a spawner");
+        }
+        if !config.objects.iter().any(|o| o.role == ObjectRole::Trigger) {
+            self.push("objects", "map must contain a trigger");
+        }
+
+        let mut ids = HashSet::new();
+        for (i, obj) in config.objects.iter().enumerate() {
+            let path = format!("objects[{i}]");
+            if let Some(id) = &obj.id {
+                self.check_id(&format!("{path}.id"), id);
+                if !ids.insert(id.as_str()) {
+                    self.push(format!("{path}.id"), format!("duplicate object id '{id}'"));
+                }
+            }
+            self.check_shape(&format!("{path}.shape"), &obj.shape);
+            self.check_properties(&format!("{path}.properties"), obj);
+        }
+
+        if let LiveRankingConfig::Distance { target_id } = &config.meta.live_ranking
+            && !ids.contains(target_id.as_str())
+        {
+            self.push(
+                "meta.live_ranking.target_id",
+                format!("unknown object id '{target_id}'"),
+            );
+        }
+
+        self.check_keyframes(config, &ids);
+    }
+
+    fn check_properties(&mut self, path: &str, obj: &MapObject) {
+        let props = &obj.properties;
+        if let Some(bumper) = &props.bumper {
+            self.check_number(&format!("{path}.bumper.force"), &bumper.force, None);
+        }
+        if let Some(roll) = &props.roll {
+            self.check_finite(&format!("{path}.roll.speed"), roll.speed);
+        }
+        if let Some(field) = &props.vector_field {
+            let path = format!("{path}.vector_field");
+            self.check_vec2(&format!("{path}.direction"), &field.direction);
+            self.check_number(&format!("{path}.magnitude"), &field.magnitude, None);
+            if let BoolOrExpr::Expr(expr) = &field.enabled {
+                self.check_expr(&format!("{path}.enabled"), expr);
+            }
+        }
+        if obj.role == ObjectRole::Trigger && props.trigger.is_none() {
+            self.push(
+                format!("{path}.trigger"),
+                "trigger objects require an action",
+            );
+        }
+    }
+
+    fn check_keyframes(&mut self, config: &RouletteConfig, ids: &HashSet<&str>) {
+        if config.keyframes.len() > self.limits.max_keyframe_sequences {
+            self.push(
+                "keyframes",
+                format!(
+                    "at most {} keyframe sequences allowed",
+                    self.limits.max_keyframe_sequences
+                ),
+            );
+            return;
+        }
+        let total: usize = config.keyframes.iter().map(|s| s.keyframes.len()).sum();
+        if total > self.limits.max_keyframes {
+            self.push(
+                "keyframes",
+                format!("at most {} keyframes allowed", self.limits.max_keyframes),
+            );
+            return;
+        }
+
+        for (i, seq) in config.keyframes.iter().enumerate() {
+            let path = format!("keyframes[{i}]");
+            self.check_id(&format!("{path}.name"), &seq.name);
+            for (j, target) in seq.target_ids.iter().enumerate() {
+                if !ids.contains(target.as_str()) {
+                    self.push(
+                        format!("{path}.target_ids[{j}]"),
+                        format!("unknown object id '{target}'"),
+                    );
+                }
+            }
+
+            let mut depth = 0usize;
+            for (j, keyframe) in seq.keyframes.iter().enumerate() {
+                let path = format!("{path}.keyframes[{j}]");
+                match keyframe {
+                    Keyframe::LoopStart { .. } => depth += 1,
+                    Keyframe::LoopEnd => {
+                        if depth == 0 {
+                            self.push(path, "loop_end without matching loop_start");
+                        } else {
+                            depth -= 1;
+                        }
+                    }
+                    Keyframe::Delay { duration } => {
+                        self.check_number(&format!("{path}.duration"), duration, Some(0.0));
+                    }
+                    Keyframe::Apply {
+                        translation,
+                        rotation,
+                        duration,
+                        ..
+                    } => {
+                        if let Some(t) = translation {
+                            self.check_coords(&format!("{path}.translation"), *t);
+                        }
+                        if let Some(r) = rotation {
+                            self.check_finite(&format!("{path}.rotation"), *r);
+                        }
+                        self.check_duration(&format!("{path}.duration"), *duration);
+                    }
+                    Keyframe::PivotRotate {
+                        pivot,
+                        angle,
+                        duration,
+                        ..
+                    } => {
+                        self.check_coords(&format!("{path}.pivot"), *pivot);
+                        self.check_finite(&format!("{path}.angle"), *angle);
+                        self.check_duration(&format!("{path}.duration"), *duration);
+                    }
+                    Keyframe::ContinuousRotate { speed, .. } => {
+                        self.check_finite(&format!("{path}.speed"), *speed);
+                    }
+                }
+            }
+            if depth > 0 {
+                self.push(
+                    format!("{path}.keyframes"),
+                    "loop_start without matching loop_end",
+                );
+            }
+        }
+    }
+
+    fn check_shape(&mut self, path: &str, shape: &Shape) {
+        match shape {
+            Shape::Line { start, end } => {
+                self.check_vec2(&format!("{path}.start"), start);
+                self.check_vec2(&format!("{path}.end"), end);
+            }
+            Shape::Circle { center, radius } => {
+                self.check_vec2(&format!("{path}.center"), center);
+                self.check_number(&format!("{path}.radius"), radius, Some(0.0));
+            }
+            Shape::Rect {
+                center,
+                size,
+                rotation,
+            } => {
+                self.check_vec2(&format!("{path}.center"), center);
+                self.check_vec2(&format!("{path}.size"), size);
+                if let Vec2OrExpr::Static(size) = size
+                    && (size[0] < 0.0 || size[1] < 0.0)
+                {
+                    self.push(format!("{path}.size"), "must not be negative");
+                }
+                self.check_number(&format!("{path}.rotation"), rotation, None);
+            }
+            Shape::Bezier {
+                start,
+                control1,
+                control2,
+                end,
+                segments,
+            } => {
+                self.check_vec2(&format!("{path}.start"), start);
+                self.check_vec2(&format!("{path}.control1"), control1);
+                self.check_vec2(&format!("{path}.control2"), control2);
+                self.check_vec2(&format!("{path}.end"), end);
+                if *segments == 0 || *segments > self.limits.max_bezier_segments {
+                    self.push(
+                        format!("{path}.segments"),
+                        format!("must be 1-{}", self.limits.max_bezier_segments),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_id(&mut self, path: &str, id: &str) {
+        if id.len() > self.limits.max_id_len {
+            self.push(
+                path,
+                format!("must be at most {} characters", self.limits.max_id_len),
+            );
+        }
+    }
+
+    fn check_vec2(&mut self, path: &str, value: &Vec2OrExpr) {
+        match value {
+            Vec2OrExpr::Static(v) => self.check_coords(path, *v),
+            Vec2OrExpr::Expr(expr) => self.check_expr(path, expr),
+            Vec2OrExpr::Dynamic([x, y]) => {
+                self.check_number(&format!("{path}[0]"), x, None);
+                self.check_number(&format!("{path}[1]"), y, None);
+            }
+        }
+    }
+
+    /// Checks a number against the coordinate range, optionally with a lower bound.
+    fn check_number(&mut self, path: &str, value: &NumberOrExpr, min: Option<f32>) {
+        match value {
+            NumberOrExpr::Number(n) => {
+                if !n.is_finite() || n.abs() > self.limits.max_coordinate {
+                    self.push(
+                        path,
+                        format!("must be within ±{}", self.limits.max_coordinate),
+                    );
+                } else if let Some(min) = min
+                    && *n < min
+                {
+                    self.push(path, format!("must be at least {min}"));
+                }
+            }
+            NumberOrExpr::Expr(expr) => self.check_expr(path, expr),
+        }
+    }
+
+    fn check_coords(&mut self, path: &str, v: [f32; 2]) {
+        if v.iter()
+            .any(|c| !c.is_finite() || c.abs() > self.limits.max_coordinate)
+        {
+            self.push(
+                path,
+                format!("coordinates must be within ±{}", self.limits.max_coordinate),
+            );
+        }
+    }
+
+    fn check_finite(&mut self, path: &str, value: f32) {
+        if !value.is_finite() {
+            self.push(path, "must be a finite number");
+        }
+    }
+
+    fn check_duration(&mut self, path: &str, value: f32) {
+        if !value.is_finite() || value < 0.0 {
+            self.push(path, "must be a non-negative number");
+        }
+    }
+
+    fn check_expr(&mut self, path: &str, expr: &str) {
+        if expr.len() > self.limits.max_expr_len {
+            self.push(
+                path,
+                format!(
+                    "expression must be at most {} characters",
+                    self.limits.max_expr_len
+                ),
+            );
+            // Don't hand oversized input to the CEL parser.
+            return;
+        }
+        if let Err(e) = Program::compile(expr) {
+            self.push(path, format!("invalid expression: {e:?}"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(config: &RouletteConfig) -> Vec<String> {
+        config
+            .validate(&MapLimits::default())
+            .into_iter()
+            .map(|v| v.field)
+            .collect()
+    }
+
+    #[test]
+    fn test_default_map_is_valid() {
+        let config = RouletteConfig::default_classic();
+        assert!(config.validate(&MapLimits::default()).is_empty());
+    }
+
+    #[test]
+    fn test_unknown_keyframe_target() {
+        let mut config = RouletteConfig::default_classic();
+        config.keyframes[0].target_ids = vec!["missing".to_string()];
+        assert_eq!(fields(&config), vec!["keyframes[0].target_ids[0]"]);
+    }
+
+    #[test]
+    fn test_unbalanced_loop() {
+        let mut config = RouletteConfig::default_classic();
+        config.keyframes[0].keyframes.pop();
+        assert_eq!(fields(&config), vec!["keyframes[0].keyframes"]);
+    }
+
+    #[test]
+    fn test_limits() {
+        let mut config = RouletteConfig::default_classic();
+        config.objects[1].shape = Shape::Circle {
+            center: Vec2OrExpr::Static([5000.0, 0.0]),
+            radius: NumberOrExpr::Expr("1.0 + ".repeat(100)),
+        };
+        assert_eq!(
+            fields(&config),
+            vec!["objects[1].shape.center", "objects[1].shape.radius"]
+        );
+
+        let limits = MapLimits {
+            max_objects: 2,
+            ..MapLimits::default()
+        };
+        let violations = RouletteConfig::default_classic().validate(&limits);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].field, "objects");
+    }
+
+    #[test]
+    fn test_invalid_expression() {
+        let mut config = RouletteConfig::default_classic();
+        config.objects[1].shape = Shape::Circle {
+            center: Vec2OrExpr::Static([0.0, 0.0]),
+            radius: NumberOrExpr::Expr("((".to_string()),
+        };
+        assert_eq!(fields(&config), vec!["objects[1].shape.radius"]);
+    }
+
+    #[test]
+    fn test_limits_proto_round_trip() {
+        let limits = MapLimits {
+            max_objects: 2048,
+            max_coordinate: 5000.0,
+            ..MapLimits::default()
+        };
+        assert_eq!(MapLimits::from_proto(&limits.to_proto()), limits);
+    }
+}
@@ -16,6 +16,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 "../../proto/room.proto",
                 "../../proto/play.proto",
                 "../../proto/avatar.proto",
+                "../../proto/stats.proto",
+                "../../proto/admin.proto",
             ],
             &["../../proto"],
         )?;
@@ -52,3 +52,21 @@ pub mod avatar {
     #[cfg(not(feature = "server"))]
     include!(concat!(env!("OUT_DIR"), "/marble.avatar.rs"));
 }
+
+#[allow(clippy::pedantic)]
+pub mod stats {
+    #[cfg(feature = "server")]
+    tonic::include_proto!("marble.stats");
+
+    #[cfg(not(feature = "server"))]
+    include!(concat!(env!("OUT_DIR"), "/marble.stats.rs"));
+}
+
+#[allow(clippy::pedantic)]
+pub mod admin {
+    #[cfg(feature = "server")]
+    tonic::include_proto!("marble.admin");
+
+    #[cfg(not(feature = "server"))]
+    include!(concat!(env!("OUT_DIR"), "/marble.admin.rs"));
+}
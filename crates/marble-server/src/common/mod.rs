@@ -1,2 +1,3 @@
+pub mod invite;
 pub mod player;
 pub mod room;
@@ -1,5 +1,7 @@
 use chrono::{DateTime, Utc};
-use marble_proto::room::RoomRole;
+use ed25519_dalek::SigningKey;
+use marble_proto::room::{Readiness, RoomRole, RoomUser};
+use rand::Rng;
 
 /// A user in a room (identified by `user_id` from JWT)
 #[derive(Debug, Clone)]
@@ -8,6 +10,9 @@ pub struct RoomMember {
     pub is_host: bool,
     pub role: RoomRole,
     pub joined_at: DateTime<Utc>,
+    pub readiness: Readiness,
+    /// Signs this member's P2P messages; issued on join and bound to `user_id`
+    pub signing_key: SigningKey,
 }
 
 impl RoomMember {
@@ -17,6 +22,8 @@ impl RoomMember {
             is_host: true,
             role: RoomRole::Participant,
             joined_at: Utc::now(),
+            readiness: Readiness::default(),
+            signing_key: generate_signing_key(),
         }
     }
 
@@ -26,6 +33,8 @@ impl RoomMember {
             is_host: false,
             role: RoomRole::Participant,
             joined_at: Utc::now(),
+            readiness: Readiness::default(),
+            signing_key: generate_signing_key(),
         }
     }
 
@@ -35,6 +44,33 @@ impl RoomMember {
             is_host: false,
             role: RoomRole::Spectator,
             joined_at: Utc::now(),
+            readiness: Readiness::default(),
+            signing_key: generate_signing_key(),
         }
     }
+
+    /// Map loaded, connected to the P2P mesh and confirmed ready
+    pub fn is_ready(&self) -> bool {
+        self.readiness.map_loaded && self.readiness.p2p_connected && self.readiness.ready
+    }
+
+    /// Public half of the signing key, distributed in every member's topology
+    pub fn public_key(&self) -> Vec<u8> {
+        self.signing_key.verifying_key().to_bytes().to_vec()
+    }
+
+    pub fn to_room_user(&self) -> RoomUser {
+        RoomUser {
+            user_id: self.user_id.clone(),
+            is_host: self.is_host,
+            role: self.role.into(),
+            joined_at: self.joined_at.to_rfc3339(),
+            readiness: Some(self.readiness),
+        }
+    }
+}
+
+/// Fresh Ed25519 key from the thread RNG
+fn generate_signing_key() -> SigningKey {
+    SigningKey::from_bytes(&rand::rng().random::<[u8; 32]>())
 }
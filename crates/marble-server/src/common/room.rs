@@ -1,24 +1,48 @@
 use chrono::{DateTime, Utc};
+use marble_core::MapLimits;
 use marble_proto::room::{
-    GameState as ProtoGameState, NetworkConfig, PeerConnectionStatus, PeerTopology, PlayerResult,
-    RoomInfo, RoomRole, RoomState, RoomSummary, RoomUser,
+    GameResultsChanged, GameState as ProtoGameState, MemberJoined, MemberLeft, NetworkConfig,
+    PeerConnectionStatus, PeerTopology, PlayerResult, Readiness, ReadinessChanged, ReadyCheck,
+    RoleChanged, RoomEvent, RoomInfo, RoomRole, RoomState, RoomSummary, RoomUpdated, RoomUser,
+    RoundResult, StateChanged, TopologyChanged, room_event,
 };
 use rand::Rng;
+use tokio::sync::broadcast;
 
+use crate::common::invite::{InviteToken, JoinCredentials, PasswordHash, generate_invite_code};
 use crate::common::player::RoomMember;
 use crate::topology::{TopologyManager, TopologyManagerConfig};
 
+/// Buffered events per room before slow watchers start lagging
+const ROOM_EVENT_CAPACITY: usize = 64;
+/// Usable invite tokens a room may hold at once
+const MAX_INVITE_TOKENS: usize = 32;
+
 #[derive(Debug, Clone)]
 pub struct Room {
     id: uuid::Uuid,
     name: String,
     map_id: String,
+    map_revision: u32,
     max_players: u32,
     is_public: bool,
     host_user_id: String,
     members: Vec<RoomMember>,
+
+    // Join credentials
+    password: Option<PasswordHash>,
+    invite_code: String,
+    invite_tokens: Vec<InviteToken>,
+    /// Users the host kicked with `ban`, refused by `add_user`
+    banned_user_ids: Vec<String>,
+    /// StartGame rule on member readiness
+    ready_check: ReadyCheck,
+    /// Server map limits, passed to members so peers validate `MapChange` alike
+    map_limits: MapLimits,
+
     created_at: DateTime<Utc>,
     started_at: Option<DateTime<Utc>>,
+    ended_at: Option<DateTime<Utc>>,
     topology_manager: TopologyManager,
     topology_version: u64,
     signaling_base_url: String,
@@ -27,6 +51,13 @@ pub struct Room {
     rng_seed: u64,
     game_start_frame: Option<u64>,
     game_results: Vec<GameResult>,
+
+    // Multi-round state
+    round: u64,
+    round_history: Vec<RoundRecord>,
+
+    // Live event fan-out for WatchRoom (clones share the channel)
+    events: broadcast::Sender<RoomEvent>,
 }
 
 #[derive(Debug, Clone)]
@@ -36,6 +67,33 @@ pub struct GameResult {
     pub arrival_frame: u64,
 }
 
+/// A finished round archived by `Room::reset`.
+#[derive(Debug, Clone)]
+pub struct RoundRecord {
+    pub round: u64,
+    pub map_id: String,
+    pub map_revision: u32,
+    pub rng_seed: u64,
+    pub start_frame: u64,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    /// Sorted by rank
+    pub results: Vec<GameResult>,
+}
+
+/// Settings changed by `Room::update`. `None` keeps the current value.
+#[derive(Debug, Clone, Default)]
+pub struct RoomUpdate {
+    /// `(map_id, revision)` to pin
+    pub map: Option<(String, u32)>,
+    pub name: Option<String>,
+    pub max_players: Option<u32>,
+    pub is_public: Option<bool>,
+    /// Overrides; fields left at 0 keep their current value
+    pub network_config: Option<NetworkConfig>,
+    pub ready_check: Option<ReadyCheck>,
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum RoomError {
     #[error("Room is full")]
@@ -52,6 +110,48 @@ pub enum RoomError {
 
     #[error("Game not started yet")]
     GameNotStarted,
+
+    #[error("Game has not ended yet")]
+    GameNotEnded,
+
+    #[error("Room is private: an invite code or invite token is required")]
+    InviteRequired,
+
+    #[error("Wrong room password")]
+    WrongPassword,
+
+    #[error("Invite token is invalid, expired, revoked or used up")]
+    InvalidInvite,
+
+    #[error("Invite token not found")]
+    InviteNotFound,
+
+    #[error("Too many active invite tokens")]
+    TooManyInvites,
+
+    #[error("User is banned from this room")]
+    Banned,
+
+    #[error("User is not banned from this room")]
+    NotBanned,
+
+    #[error("Roles can only change while the room is waiting")]
+    RoleChangeNotAllowed,
+
+    #[error("Only allowed while the room is waiting")]
+    NotWaiting,
+
+    #[error("Role must be participant or spectator")]
+    InvalidRole,
+
+    #[error("max_players can not be below the {0} current participants")]
+    MaxPlayersBelowParticipants(u32),
+
+    #[error("Ready check quorum can not exceed max_players ({0})")]
+    QuorumAboveMaxPlayers(u32),
+
+    #[error("Only {ready} of the {required} required participants are ready")]
+    NotReady { ready: u32, required: u32 },
 }
 
 impl RoomError {
@@ -61,7 +161,17 @@ impl RoomError {
             Self::UserNotFound => tonic::Code::NotFound,
             Self::HostCanNotKick => tonic::Code::InvalidArgument,
             Self::RoomHostOnly(_) => tonic::Code::PermissionDenied,
-            Self::GameNotStarted => tonic::Code::FailedPrecondition,
+            Self::GameNotStarted | Self::GameNotEnded => tonic::Code::FailedPrecondition,
+            Self::InviteRequired | Self::WrongPassword | Self::InvalidInvite | Self::Banned => {
+                tonic::Code::PermissionDenied
+            }
+            Self::InviteNotFound | Self::NotBanned => tonic::Code::NotFound,
+            Self::TooManyInvites => tonic::Code::ResourceExhausted,
+            Self::RoleChangeNotAllowed
+            | Self::NotWaiting
+            | Self::MaxPlayersBelowParticipants(_)
+            | Self::NotReady { .. } => tonic::Code::FailedPrecondition,
+            Self::InvalidRole | Self::QuorumAboveMaxPlayers(_) => tonic::Code::InvalidArgument,
         }
     }
 }
@@ -74,6 +184,7 @@ impl From<RoomError> for tonic::Status {
 
 #[allow(dead_code)]
 impl Room {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         id: uuid::Uuid,
         room_name: String,
@@ -82,16 +193,9 @@ impl Room {
         is_public: bool,
         host_user_id: String,
         signaling_base_url: String,
+        topology_config: TopologyManagerConfig,
     ) -> Self {
-        let config = TopologyManagerConfig {
-            mesh_group_size: (max_players / 3).clamp(10, 40),
-            peer_connections: 5,
-            bridges_per_group: 2,
-            gossip_ttl: 10,
-            lockstep_delay_frames: 6,
-        };
-
-        let mut topology_manager = TopologyManager::new(config);
+        let mut topology_manager = TopologyManager::new(topology_config);
         topology_manager.add_player(&host_user_id, &format!("pending_{host_user_id}"));
 
         let host = RoomMember::new_host(host_user_id.clone());
@@ -101,18 +205,29 @@ impl Room {
             id,
             name: room_name,
             map_id,
+            map_revision: 0,
             max_players,
             is_public,
             host_user_id,
             members: vec![host],
+            password: None,
+            invite_code: generate_invite_code(),
+            invite_tokens: Vec::new(),
+            banned_user_ids: Vec::new(),
+            ready_check: ReadyCheck::default(),
+            map_limits: MapLimits::default(),
             created_at: Utc::now(),
             started_at: None,
+            ended_at: None,
             topology_manager,
             topology_version: 1,
             signaling_base_url,
             rng_seed,
             game_start_frame: None,
             game_results: Vec::new(),
+            round: 1,
+            round_history: Vec::new(),
+            events: broadcast::channel(ROOM_EVENT_CAPACITY).0,
         }
     }
 
@@ -122,6 +237,43 @@ impl Room {
         &self.id
     }
 
+    pub fn map_id(&self) -> &str {
+        &self.map_id
+    }
+
+    pub fn map_revision(&self) -> u32 {
+        self.map_revision
+    }
+
+    /// Pin the map revision every peer loads (0 = no stored map).
+    pub fn pin_map_revision(&mut self, revision: u32) {
+        self.map_revision = revision;
+    }
+
+    /// Require `password` from new members that don't hold an invite token.
+    pub fn set_password(&mut self, password: Option<String>) {
+        self.password = password.as_deref().map(PasswordHash::new);
+    }
+
+    /// Map limits the server stores maps under, sent to members in `RoomInfo`.
+    pub fn set_map_limits(&mut self, map_limits: MapLimits) {
+        self.map_limits = map_limits;
+    }
+
+    /// Rule `start_game` applies to member readiness.
+    pub fn set_ready_check(&mut self, ready_check: ReadyCheck) {
+        self.ready_check = ready_check;
+    }
+
+    pub fn invite_code(&self) -> &str {
+        &self.invite_code
+    }
+
+    /// Replace the invite code (used when a generated code collides with another room).
+    pub fn set_invite_code(&mut self, code: String) {
+        self.invite_code = code;
+    }
+
     pub fn max_players(&self) -> u32 {
         self.max_players
     }
@@ -152,6 +304,10 @@ impl Room {
         self.started_at
     }
 
+    pub fn topology_manager(&self) -> &TopologyManager {
+        &self.topology_manager
+    }
+
     pub fn topology_config(&self) -> &TopologyManagerConfig {
         &self.topology_manager.config
     }
@@ -168,10 +324,79 @@ impl Room {
         self.topology_version
     }
 
+    pub fn round(&self) -> u64 {
+        self.round
+    }
+
+    pub fn round_history(&self) -> &[RoundRecord] {
+        &self.round_history
+    }
+
     fn signaling_url(&self) -> String {
         format!("{}/{}", self.signaling_base_url, self.id)
     }
 
+    /// Relay endpoint served next to signaling (`…/signaling` -> `…/relay/{room_id}`)
+    fn relay_url(&self) -> String {
+        let base = self.signaling_base_url.trim_end_matches('/');
+        let base = base.strip_suffix("/signaling").unwrap_or(base);
+        format!("{base}/relay/{}", self.id)
+    }
+
+    /// Fill in the room-level URLs and member keys a topology from the manager leaves empty
+    fn with_room_fields(&self, mut topology: PeerTopology) -> PeerTopology {
+        topology.signaling_url = self.signaling_url();
+        if topology.relayed || (topology.is_bridge && self.topology_manager.has_relayed_players()) {
+            topology.relay_url = self.relay_url();
+        }
+        topology.member_keys = self
+            .members
+            .iter()
+            .map(|m| (m.user_id.clone(), m.public_key()))
+            .collect();
+        topology.host_user_id.clone_from(&self.host_user_id);
+        topology
+    }
+
+    // === Events ===
+
+    /// Subscribe to live room events.
+    pub fn subscribe(&self) -> broadcast::Receiver<RoomEvent> {
+        self.events.subscribe()
+    }
+
+    fn emit(&self, event: room_event::Event) {
+        // No receivers is not an error: nobody is watching the room
+        let _ = self.events.send(RoomEvent { event: Some(event) });
+    }
+
+    fn emit_state_changed(&self) {
+        self.emit(room_event::Event::StateChanged(StateChanged {
+            state: self.state().into(),
+            round: self.round,
+            start_frame: self.game_start_frame.unwrap_or(0),
+        }));
+    }
+
+    /// Tell watchers the room is over for good. Called right before the room is dropped.
+    pub fn close(&self) {
+        self.emit(room_event::Event::StateChanged(StateChanged {
+            state: RoomState::Ended.into(),
+            round: self.round,
+            start_frame: self.game_start_frame.unwrap_or(0),
+        }));
+    }
+
+    fn bump_topology_version(&mut self) {
+        self.topology_version += 1;
+        // Each watcher fills in its own topology and delta
+        self.emit(room_event::Event::TopologyChanged(TopologyChanged {
+            topology_version: self.topology_version,
+            topology: None,
+            delta: None,
+        }));
+    }
+
     // === State ===
 
     pub fn state(&self) -> RoomState {
@@ -202,19 +427,63 @@ impl Room {
         self.members.iter().any(|m| m.user_id == user_id)
     }
 
+    /// A member's P2P message signing key (secret bytes), stable across rejoins.
+    pub fn signing_key(&self, user_id: &str) -> Option<Vec<u8>> {
+        self.members
+            .iter()
+            .find(|m| m.user_id == user_id)
+            .map(|m| m.signing_key.to_bytes().to_vec())
+    }
+
+    pub fn is_banned(&self, user_id: &str) -> bool {
+        self.banned_user_ids.iter().any(|id| id == user_id)
+    }
+
     // === Room management ===
 
-    /// Add a user to the room. Idempotent.
+    /// Check join credentials of a new member.
+    ///
+    /// A valid invite token admits on its own. Otherwise private rooms require the invite
+    /// code, and rooms with a password require it. Returns the index of the token to consume.
+    fn check_credentials(&self, credentials: &JoinCredentials) -> Result<Option<usize>, RoomError> {
+        if let Some(token) = credentials.invite_token {
+            let now = Utc::now();
+            return self
+                .invite_tokens
+                .iter()
+                .position(|t| t.token == token && t.is_usable(now))
+                .map(Some)
+                .ok_or(RoomError::InvalidInvite);
+        }
+        if !self.is_public && !credentials.via_invite_code {
+            return Err(RoomError::InviteRequired);
+        }
+        if let Some(password) = &self.password
+            && !credentials.password.is_some_and(|p| password.verify(p))
+        {
+            return Err(RoomError::WrongPassword);
+        }
+        Ok(None)
+    }
+
+    /// Add a user to the room. Idempotent; credentials are only checked for new members.
     pub fn add_user(
         &mut self,
         user_id: String,
         role: Option<RoomRole>,
+        credentials: JoinCredentials,
     ) -> Result<PeerTopology, RoomError> {
         // Idempotent: if user already exists, return their topology
         if let Some(topology) = self.get_topology(&user_id) {
             return Ok(topology);
         }
 
+        // Bans override every credential, including invite tokens
+        if self.is_banned(&user_id) {
+            return Err(RoomError::Banned);
+        }
+        let invite_index = self.check_credentials(&credentials)?;
+
         let actual_role = match role {
             Some(RoomRole::Spectator) => RoomRole::Spectator,
             Some(RoomRole::Participant | RoomRole::Unspecified) | None => {
@@ -229,59 +498,338 @@ impl Room {
         if actual_role == RoomRole::Participant && self.participant_count() >= self.max_players {
             return Err(RoomError::RoomFull);
         }
+        if let Some(index) = invite_index {
+            self.invite_tokens[index].uses += 1;
+        }
 
         let peer_id = format!("pending_{user_id}");
-        let mut topology = self.topology_manager.add_player(&user_id, &peer_id);
-        topology.signaling_url = self.signaling_url();
+        let topology = self.topology_manager.add_player(&user_id, &peer_id);
 
         let member = match actual_role {
             RoomRole::Spectator => RoomMember::new_spectator(user_id),
             _ => RoomMember::new_participant(user_id),
         };
+        self.emit(room_event::Event::MemberJoined(MemberJoined {
+            user: Some(member.to_room_user()),
+        }));
         self.members.push(member);
+        self.bump_topology_version();
 
-        Ok(topology)
+        // After the push, so the new member's own key is among the member keys
+        Ok(self.with_room_fields(topology))
     }
 
-    pub fn kick_user(&mut self, target_user_id: &str) -> Result<(), RoomError> {
+    /// Remove a member. With `ban`, the user can not rejoin until unbanned.
+    pub fn kick_user(&mut self, target_user_id: &str, ban: bool) -> Result<(), RoomError> {
         if self.host_user_id == target_user_id {
             return Err(RoomError::HostCanNotKick);
         }
+        let was_ended = self.state() == RoomState::Ended;
         let initial_len = self.members.len();
         self.members.retain(|m| m.user_id != target_user_id);
         if self.members.len() == initial_len {
             return Err(RoomError::UserNotFound);
         }
+        if ban && !self.is_banned(target_user_id) {
+            self.banned_user_ids.push(target_user_id.to_string());
+        }
         self.topology_manager.remove_player(target_user_id);
-        self.topology_version += 1;
+        // Regroup only in the lobby: moving racers would drop their live links. Covered by
+        // the single version bump below
+        if self.state() == RoomState::Waiting {
+            self.topology_manager.rebalance();
+        }
+        self.emit(room_event::Event::MemberLeft(MemberLeft {
+            user_id: target_user_id.to_string(),
+            kicked: true,
+        }));
+        // Kicking the last player still racing finishes the round
+        if !was_ended && self.state() == RoomState::Ended {
+            self.ended_at = Some(Utc::now());
+            self.emit_state_changed();
+        }
+        self.bump_topology_version();
+        Ok(())
+    }
+
+    /// Lift a ban. Host only.
+    pub fn unban_user(&mut self, user_id: &str, target_user_id: &str) -> Result<(), RoomError> {
+        self.assert_host(user_id, "unban_user")?;
+        let initial_len = self.banned_user_ids.len();
+        self.banned_user_ids.retain(|id| id != target_user_id);
+        if self.banned_user_ids.len() == initial_len {
+            return Err(RoomError::NotBanned);
+        }
         Ok(())
     }
 
+    /// Switch a member between participant and spectator. WAITING only.
+    ///
+    /// Becoming a participant needs a free slot. Returns the member's topology; the
+    /// topology version is bumped so peers refresh their view of the room.
+    pub fn change_role(
+        &mut self,
+        user_id: &str,
+        role: RoomRole,
+    ) -> Result<PeerTopology, RoomError> {
+        if role == RoomRole::Unspecified {
+            return Err(RoomError::InvalidRole);
+        }
+        if self.state() != RoomState::Waiting {
+            return Err(RoomError::RoleChangeNotAllowed);
+        }
+        let participant_count = self.participant_count();
+        let max_players = self.max_players;
+        let member = self
+            .members
+            .iter_mut()
+            .find(|m| m.user_id == user_id)
+            .ok_or(RoomError::UserNotFound)?;
+
+        if member.role != role {
+            if role == RoomRole::Participant && participant_count >= max_players {
+                return Err(RoomError::RoomFull);
+            }
+            member.role = role;
+            let event = RoleChanged {
+                user_id: member.user_id.clone(),
+                role: role.into(),
+                is_host: member.is_host,
+            };
+            self.emit(room_event::Event::RoleChanged(event));
+            self.bump_topology_version();
+        }
+
+        self.get_topology(user_id).ok_or(RoomError::UserNotFound)
+    }
+
+    // === Invites ===
+
+    /// Issue an invite token. Host only.
+    pub fn create_invite(
+        &mut self,
+        user_id: &str,
+        ttl: Option<chrono::Duration>,
+        max_uses: u32,
+    ) -> Result<InviteToken, RoomError> {
+        self.assert_host(user_id, "create_invite")?;
+
+        let now = Utc::now();
+        self.invite_tokens.retain(|t| t.is_usable(now));
+        if self.invite_tokens.len() >= MAX_INVITE_TOKENS {
+            return Err(RoomError::TooManyInvites);
+        }
+
+        let invite = InviteToken::new(ttl, max_uses);
+        self.invite_tokens.push(invite.clone());
+        Ok(invite)
+    }
+
+    /// Invite tokens still held by the room, oldest first. Host only.
+    pub fn invites(&self, user_id: &str) -> Result<Vec<InviteToken>, RoomError> {
+        self.assert_host(user_id, "list_invites")?;
+        Ok(self.invite_tokens.clone())
+    }
+
+    /// Revoke an invite token. Host only; members who already joined stay.
+    pub fn revoke_invite(&mut self, user_id: &str, token: &str) -> Result<InviteToken, RoomError> {
+        self.assert_host(user_id, "revoke_invite")?;
+        let invite = self
+            .invite_tokens
+            .iter_mut()
+            .find(|t| t.token == token)
+            .ok_or(RoomError::InviteNotFound)?;
+        invite.revoked = true;
+        Ok(invite.clone())
+    }
+
+    /// Change room settings. Host only, WAITING only.
+    ///
+    /// Members are told through a `RoomUpdated` event. A new mesh group size rebalances
+    /// the mesh groups.
+    pub fn update(&mut self, user_id: &str, update: RoomUpdate) -> Result<(), RoomError> {
+        self.assert_host(user_id, "update_room")?;
+        if self.state() != RoomState::Waiting {
+            return Err(RoomError::NotWaiting);
+        }
+        if let Some(max_players) = update.max_players
+            && max_players < self.participant_count()
+        {
+            return Err(RoomError::MaxPlayersBelowParticipants(
+                self.participant_count(),
+            ));
+        }
+        // Against the new player limit if it changes too
+        let max_players = update.max_players.unwrap_or(self.max_players);
+        if update
+            .ready_check
+            .is_some_and(|check| check.quorum > max_players)
+        {
+            return Err(RoomError::QuorumAboveMaxPlayers(max_players));
+        }
+
+        let map_changed = update.map.as_ref().is_some_and(|(map_id, revision)| {
+            *map_id != self.map_id || *revision != self.map_revision
+        });
+        if let Some((map_id, revision)) = update.map {
+            self.map_id = map_id;
+            self.map_revision = revision;
+        }
+        if let Some(name) = update.name {
+            self.name = name;
+        }
+        if let Some(max_players) = update.max_players {
+            self.max_players = max_players;
+        }
+        if let Some(is_public) = update.is_public {
+            self.is_public = is_public;
+        }
+        if let Some(network) = update.network_config {
+            self.apply_network_config(&network);
+        }
+        if let Some(ready_check) = update.ready_check {
+            self.ready_check = ready_check;
+        }
+        if map_changed {
+            self.clear_readiness();
+        }
+
+        self.emit(room_event::Event::RoomUpdated(RoomUpdated {
+            room: Some(self.to_room_info()),
+            map_changed,
+        }));
+        Ok(())
+    }
+
+    fn apply_network_config(&mut self, network: &NetworkConfig) {
+        let mut config = self.topology_manager.config.clone();
+        let override_with = |current: &mut u32, value: u32| {
+            if value != 0 {
+                *current = value;
+            }
+        };
+        override_with(
+            &mut config.lockstep_delay_frames,
+            network.lockstep_delay_frames,
+        );
+        override_with(&mut config.gossip_ttl, network.gossip_ttl);
+        override_with(&mut config.mesh_group_size, network.mesh_group_size);
+        override_with(&mut config.peer_connections, network.peer_connections);
+
+        let current = &self.topology_manager.config;
+        let regroup = config.mesh_group_size != current.mesh_group_size;
+        let reconnect = regroup || config.peer_connections != current.peer_connections;
+
+        self.topology_manager.set_config(config);
+        if regroup {
+            self.topology_manager.rebalance();
+        }
+        if reconnect {
+            self.bump_topology_version();
+        }
+    }
+
     pub fn get_room_users(&self) -> Vec<RoomUser> {
+        self.members.iter().map(RoomMember::to_room_user).collect()
+    }
+
+    // === Readiness ===
+
+    /// Replace a member's readiness. WAITING only.
+    pub fn set_readiness(
+        &mut self,
+        user_id: &str,
+        readiness: Readiness,
+    ) -> Result<RoomUser, RoomError> {
+        if self.state() != RoomState::Waiting {
+            return Err(RoomError::NotWaiting);
+        }
+        let member = self
+            .members
+            .iter_mut()
+            .find(|m| m.user_id == user_id)
+            .ok_or(RoomError::UserNotFound)?;
+
+        if member.readiness != readiness {
+            member.readiness = readiness;
+            let event = ReadinessChanged {
+                user_id: member.user_id.clone(),
+                readiness: Some(readiness),
+            };
+            self.emit(room_event::Event::ReadinessChanged(event));
+        }
+
         self.members
             .iter()
-            .map(|m| RoomUser {
-                user_id: m.user_id.clone(),
-                is_host: m.is_host,
-                role: m.role.into(),
-                joined_at: m.joined_at.to_rfc3339(),
-            })
-            .collect()
+            .find(|m| m.user_id == user_id)
+            .map(RoomMember::to_room_user)
+            .ok_or(RoomError::UserNotFound)
+    }
+
+    /// Drop map and ready confirmations for a new map or round; P2P links stay up.
+    fn clear_readiness(&mut self) {
+        let mut changed = Vec::new();
+        for member in &mut self.members {
+            let readiness = Readiness {
+                p2p_connected: member.readiness.p2p_connected,
+                ..Readiness::default()
+            };
+            if member.readiness != readiness {
+                member.readiness = readiness;
+                changed.push(ReadinessChanged {
+                    user_id: member.user_id.clone(),
+                    readiness: Some(readiness),
+                });
+            }
+        }
+        for event in changed {
+            self.emit(room_event::Event::ReadinessChanged(event));
+        }
+    }
+
+    /// Fail unless enough participants are ready, when the room requires it.
+    fn check_ready(&self) -> Result<(), RoomError> {
+        if !self.ready_check.required {
+            return Ok(());
+        }
+        let participants = self.participant_count();
+        let required = match self.ready_check.quorum {
+            0 => participants,
+            quorum => quorum.min(participants),
+        };
+        let ready = u32::try_from(
+            self.members
+                .iter()
+                .filter(|m| m.role == RoomRole::Participant && m.is_ready())
+                .count(),
+        )
+        .unwrap_or(u32::MAX);
+
+        if ready < required {
+            return Err(RoomError::NotReady { ready, required });
+        }
+        Ok(())
     }
 
     // === Game lifecycle ===
 
-    /// Start the game. Can only be called once. Also marks room as started.
+    /// Start the game. Can only be called once per round. Also marks room as started.
+    ///
+    /// Applies the room's ready check unless the host forces the start.
     pub fn start_game(
         &mut self,
         user_id: &str,
         start_frame: u64,
+        force: bool,
     ) -> Result<bool, RoomError> {
         self.assert_host(user_id, "start_game")?;
 
         if self.game_start_frame.is_some() {
             return Ok(false); // Idempotent
         }
+        if !force {
+            self.check_ready()?;
+        }
 
         if self.started_at.is_none() {
             self.started_at = Some(Utc::now());
@@ -289,6 +837,7 @@ impl Room {
 
         self.game_start_frame = Some(start_frame);
         self.game_results.clear();
+        self.emit_state_changed();
 
         Ok(true)
     }
@@ -318,40 +867,111 @@ impl Room {
                 rank,
                 arrival_frame,
             });
+            self.emit(room_event::Event::GameResultsChanged(GameResultsChanged {
+                results: self.game_results.iter().map(GameResult::to_proto).collect(),
+            }));
+            if self.state() == RoomState::Ended {
+                self.ended_at = Some(Utc::now());
+                self.emit_state_changed();
+            }
         }
 
         let participant_count = self.participant_count() as usize;
         Ok(self.game_results.len() >= participant_count)
     }
 
+    /// Archive the finished round and return to WAITING with the same members. Host only.
+    ///
+    /// Issues a fresh `rng_seed` and bumps the round number. Optionally switches the map,
+    /// given as `(map_id, revision)` to pin. Members are told through `StateChanged` and a
+    /// `RoomUpdated` carrying the new seed and map.
+    pub fn reset(&mut self, user_id: &str, map: Option<(String, u32)>) -> Result<(), RoomError> {
+        self.assert_host(user_id, "reset_room")?;
+
+        if self.state() != RoomState::Ended {
+            return Err(RoomError::GameNotEnded);
+        }
+
+        let record = self.current_round_record();
+        self.round_history.push(record);
+        self.game_results.clear();
+
+        let mut map_changed = false;
+        if let Some((map_id, revision)) = map {
+            map_changed = map_id != self.map_id || revision != self.map_revision;
+            self.map_id = map_id;
+            self.map_revision = revision;
+        }
+        self.round += 1;
+        self.rng_seed = rand::rng().random::<u64>();
+        self.started_at = None;
+        self.ended_at = None;
+        self.game_start_frame = None;
+        self.clear_readiness();
+        self.emit_state_changed();
+        self.emit(room_event::Event::RoomUpdated(RoomUpdated {
+            room: Some(self.to_room_info()),
+            map_changed,
+        }));
+
+        Ok(())
+    }
+
+    /// Record of the current round with results sorted by rank.
+    pub fn current_round_record(&self) -> RoundRecord {
+        let mut results = self.game_results.clone();
+        results.sort_by_key(|r| r.rank);
+
+        RoundRecord {
+            round: self.round,
+            map_id: self.map_id.clone(),
+            map_revision: self.map_revision,
+            rng_seed: self.rng_seed,
+            start_frame: self.game_start_frame.unwrap_or(0),
+            started_at: self.started_at.unwrap_or(self.created_at),
+            ended_at: self.ended_at.unwrap_or_else(Utc::now),
+            results,
+        }
+    }
+
+    /// Participant `user_id`s in the order they joined (host first).
+    pub fn participants_in_join_order(&self) -> Vec<String> {
+        self.members
+            .iter()
+            .filter(|m| m.role == RoomRole::Participant)
+            .map(|m| m.user_id.clone())
+            .collect()
+    }
+
     // === Topology ===
 
     pub fn get_topology(&self, user_id: &str) -> Option<PeerTopology> {
-        self.topology_manager.get_topology(user_id).map(|mut t| {
-            t.signaling_url = self.signaling_url();
-            t
-        })
+        self.topology_manager
+            .get_topology(user_id)
+            .map(|t| self.with_room_fields(t))
     }
 
+    /// Feed a connection report to the topology. Players only move between mesh groups
+    /// while the room is waiting, never mid-race.
     pub fn update_connection_status(
         &mut self,
         user_id: &str,
         statuses: &[PeerConnectionStatus],
     ) -> Option<PeerTopology> {
+        let regroup = self.state() == RoomState::Waiting;
         let result = self
             .topology_manager
-            .update_connection_status(user_id, statuses);
+            .update_connection_status(user_id, statuses, regroup);
         if result.is_some() {
-            self.topology_version += 1;
+            self.bump_topology_version();
         }
-        result.map(|mut t| {
-            t.signaling_url = self.signaling_url();
-            t
-        })
+        result.map(|t| self.with_room_fields(t))
     }
 
     pub fn update_peer_id(&mut self, user_id: &str, peer_id: &str) -> Option<PeerTopology> {
         if self.topology_manager.update_peer_id(user_id, peer_id) {
+            // Other members' connect_to lists now carry the new peer_id
+            self.bump_topology_version();
             self.get_topology(user_id)
         } else {
             None
@@ -379,15 +999,8 @@ impl Room {
 
     pub fn to_room_info(&self) -> RoomInfo {
         let config = self.topology_config();
-        let results: Vec<PlayerResult> = self
-            .game_results
-            .iter()
-            .map(|r| PlayerResult {
-                user_id: r.user_id.clone(),
-                rank: r.rank,
-                arrival_frame: r.arrival_frame,
-            })
-            .collect();
+        let results: Vec<PlayerResult> =
+            self.game_results.iter().map(GameResult::to_proto).collect();
 
         RoomInfo {
             room_id: self.id.to_string(),
@@ -415,6 +1028,18 @@ impl Room {
                 results,
             }),
             topology_version: self.topology_version,
+            round: self.round,
+            map_revision: self.map_revision,
+            round_history: self
+                .round_history
+                .iter()
+                .map(RoundRecord::to_proto)
+                .collect(),
+            invite_code: self.invite_code.clone(),
+            has_password: self.password.is_some(),
+            banned_user_ids: self.banned_user_ids.clone(),
+            ready_check: Some(self.ready_check),
+            map_limits: Some(self.map_limits.to_proto()),
         }
     }
 
@@ -428,6 +1053,32 @@ impl Room {
             current_players: self.participant_count(),
             state: self.state().into(),
             created_at: self.created_at.to_rfc3339(),
+            has_password: self.password.is_some(),
+        }
+    }
+}
+
+impl GameResult {
+    fn to_proto(&self) -> PlayerResult {
+        PlayerResult {
+            user_id: self.user_id.clone(),
+            rank: self.rank,
+            arrival_frame: self.arrival_frame,
+        }
+    }
+}
+
+impl RoundRecord {
+    fn to_proto(&self) -> RoundResult {
+        RoundResult {
+            round: self.round,
+            map_id: self.map_id.clone(),
+            map_revision: self.map_revision,
+            rng_seed: self.rng_seed,
+            start_frame: self.start_frame,
+            started_at: self.started_at.to_rfc3339(),
+            ended_at: self.ended_at.to_rfc3339(),
+            results: self.results.iter().map(GameResult::to_proto).collect(),
         }
     }
 }
@@ -435,6 +1086,7 @@ impl Room {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::TopologySettings;
 
     fn create_test_room() -> Room {
         Room::new(
@@ -445,6 +1097,7 @@ mod tests {
             true,
             "host_user".to_string(),
             "ws://localhost:3000/signaling".to_string(),
+            TopologySettings::default().for_room(10),
         )
     }
 
@@ -452,8 +1105,12 @@ mod tests {
     fn test_add_user_idempotent() {
         let mut room = create_test_room();
 
-        let topology1 = room.add_user("user1".to_string(), None).unwrap();
-        let topology2 = room.add_user("user1".to_string(), None).unwrap();
+        let topology1 = room
+            .add_user("user1".to_string(), None, JoinCredentials::default())
+            .unwrap();
+        let topology2 = room
+            .add_user("user1".to_string(), None, JoinCredentials::default())
+            .unwrap();
 
         assert_eq!(topology1.mesh_group, topology2.mesh_group);
         assert_eq!(topology1.is_bridge, topology2.is_bridge);
@@ -463,7 +1120,9 @@ mod tests {
     #[test]
     fn test_host_join_idempotent() {
         let mut room = create_test_room();
-        let _topology = room.add_user("host_user".to_string(), None).unwrap();
+        let _topology = room
+            .add_user("host_user".to_string(), None, JoinCredentials::default())
+            .unwrap();
         assert_eq!(room.member_count(), 1);
     }
 
@@ -477,10 +1136,16 @@ mod tests {
             true,
             "host".to_string(),
             "ws://localhost:3000/signaling".to_string(),
+            TopologySettings::default().for_room(2),
         );
 
-        room.add_user("user1".to_string(), None).unwrap();
-        let result = room.add_user("user2".to_string(), Some(RoomRole::Participant));
+        room.add_user("user1".to_string(), None, JoinCredentials::default())
+            .unwrap();
+        let result = room.add_user(
+            "user2".to_string(),
+            Some(RoomRole::Participant),
+            JoinCredentials::default(),
+        );
         assert!(matches!(result, Err(RoomError::RoomFull)));
     }
 
@@ -494,11 +1159,15 @@ mod tests {
             true,
             "host".to_string(),
             "ws://localhost:3000/signaling".to_string(),
+            TopologySettings::default().for_room(2),
         );
 
-        room.add_user("user1".to_string(), None).unwrap();
+        room.add_user("user1".to_string(), None, JoinCredentials::default())
+            .unwrap();
         // Room full for participants, but unspecified role -> auto spectator
-        let _topology = room.add_user("user2".to_string(), None).unwrap();
+        let _topology = room
+            .add_user("user2".to_string(), None, JoinCredentials::default())
+            .unwrap();
         assert_eq!(room.participant_count(), 2);
     }
 
@@ -518,4 +1187,498 @@ mod tests {
         assert!(info.game_state.is_some());
         assert_ne!(info.game_state.as_ref().unwrap().rng_seed, 0);
     }
+
+    #[test]
+    fn test_reset_requires_ended() {
+        let mut room = create_test_room();
+        room.add_user("user1".to_string(), None, JoinCredentials::default())
+            .unwrap();
+        room.start_game("host_user", 100, false).unwrap();
+
+        let result = room.reset("host_user", None);
+        assert!(matches!(result, Err(RoomError::GameNotEnded)));
+    }
+
+    #[test]
+    fn test_reset_starts_next_round() {
+        let mut room = create_test_room();
+        room.add_user("user1".to_string(), None, JoinCredentials::default())
+            .unwrap();
+        let first_seed = room.rng_seed();
+
+        room.start_game("host_user", 100, false).unwrap();
+        room.report_arrival("host_user", "user1", 500, 1).unwrap();
+        room.report_arrival("host_user", "host_user", 600, 2).unwrap();
+        assert_eq!(room.state(), RoomState::Ended);
+
+        let mut rx = room.subscribe();
+        assert!(matches!(
+            room.reset("user1", None),
+            Err(RoomError::RoomHostOnly(_))
+        ));
+        room.reset("host_user", Some(("map_456".to_string(), 3)))
+            .unwrap();
+
+        assert_eq!(room.state(), RoomState::Waiting);
+        assert_eq!(room.round(), 2);
+        assert_eq!(room.member_count(), 2);
+        assert_ne!(room.rng_seed(), first_seed);
+
+        // Watchers learn the new map and seed
+        let mut updated = None;
+        while let Ok(event) = rx.try_recv() {
+            if let Some(room_event::Event::RoomUpdated(event)) = event.event {
+                updated = Some(event);
+            }
+        }
+        let updated = updated.expect("reset emits RoomUpdated");
+        assert!(updated.map_changed);
+        let room_info = updated.room.unwrap();
+        assert_eq!(room_info.map_id, "map_456");
+        assert_eq!(room_info.game_state.unwrap().rng_seed, room.rng_seed());
+
+        let info = room.to_room_info();
+        assert_eq!(info.map_id, "map_456");
+        assert_eq!(info.map_revision, 3);
+        assert!(info.game_state.unwrap().results.is_empty());
+        assert_eq!(info.round_history.len(), 1);
+        let archived = &info.round_history[0];
+        assert_eq!(archived.round, 1);
+        assert_eq!(archived.map_id, "map_123");
+        assert_eq!(archived.rng_seed, first_seed);
+        assert_eq!(archived.results[0].user_id, "user1");
+
+        // The next round can be played like the first one
+        assert!(room.start_game("host_user", 50, false).unwrap());
+        assert_eq!(room.state(), RoomState::Playing);
+    }
+
+    #[test]
+    fn test_events_broadcast_to_watchers() {
+        let mut room = create_test_room();
+        let mut rx = room.subscribe();
+
+        room.add_user("user1".to_string(), None, JoinCredentials::default())
+            .unwrap();
+        room.kick_user("user1", false).unwrap();
+
+        let mut events = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            events.push(event.event.unwrap());
+        }
+
+        assert!(matches!(
+            &events[0],
+            room_event::Event::MemberJoined(MemberJoined { user: Some(u) }) if u.user_id == "user1"
+        ));
+        assert!(matches!(&events[1], room_event::Event::TopologyChanged(_)));
+        assert!(matches!(
+            &events[2],
+            room_event::Event::MemberLeft(MemberLeft { user_id, kicked: true }) if user_id == "user1"
+        ));
+        assert!(matches!(
+            &events[3],
+            room_event::Event::TopologyChanged(t) if t.topology_version == room.topology_version()
+        ));
+    }
+
+    #[test]
+    fn test_private_room_credentials() {
+        let mut room = Room::new(
+            uuid::Uuid::new_v4(),
+            "Private".to_string(),
+            "map_123".to_string(),
+            8,
+            false,
+            "host".to_string(),
+            "ws://localhost:3000/signaling".to_string(),
+            TopologySettings::default().for_room(8),
+        );
+        room.set_password(Some("hunter22".to_string()));
+
+        // Knowing the room_id is not enough
+        let result = room.add_user("user1".to_string(), None, JoinCredentials::default());
+        assert!(matches!(result, Err(RoomError::InviteRequired)));
+
+        // Invite code still needs the password
+        let via_code = JoinCredentials {
+            via_invite_code: true,
+            ..JoinCredentials::default()
+        };
+        let result = room.add_user("user1".to_string(), None, via_code);
+        assert!(matches!(result, Err(RoomError::WrongPassword)));
+        let with_password = JoinCredentials {
+            password: Some("hunter22"),
+            ..via_code
+        };
+        room.add_user("user1".to_string(), None, with_password)
+            .unwrap();
+
+        // A single-use token admits without the password, once
+        let invite = room.create_invite("host", None, 1).unwrap();
+        let with_token = JoinCredentials {
+            invite_token: Some(&invite.token),
+            ..JoinCredentials::default()
+        };
+        room.add_user("user2".to_string(), None, with_token)
+            .unwrap();
+        let result = room.add_user("user3".to_string(), None, with_token);
+        assert!(matches!(result, Err(RoomError::InvalidInvite)));
+
+        // Revoked tokens are refused
+        let invite = room.create_invite("host", None, 0).unwrap();
+        assert!(matches!(
+            room.revoke_invite("user1", &invite.token),
+            Err(RoomError::RoomHostOnly(_))
+        ));
+        room.revoke_invite("host", &invite.token).unwrap();
+        let with_token = JoinCredentials {
+            invite_token: Some(&invite.token),
+            ..JoinCredentials::default()
+        };
+        let result = room.add_user("user3".to_string(), None, with_token);
+        assert!(matches!(result, Err(RoomError::InvalidInvite)));
+        assert_eq!(room.member_count(), 3);
+    }
+
+    #[test]
+    fn test_kick_with_ban() {
+        let mut room = create_test_room();
+        room.add_user("user1".to_string(), None, JoinCredentials::default())
+            .unwrap();
+        room.kick_user("user1", true).unwrap();
+
+        let result = room.add_user("user1".to_string(), None, JoinCredentials::default());
+        assert!(matches!(result, Err(RoomError::Banned)));
+
+        // Not even an invite token gets a banned user back in
+        let invite = room.create_invite("host_user", None, 0).unwrap();
+        let with_token = JoinCredentials {
+            invite_token: Some(&invite.token),
+            ..JoinCredentials::default()
+        };
+        let result = room.add_user("user1".to_string(), None, with_token);
+        assert!(matches!(result, Err(RoomError::Banned)));
+        assert_eq!(room.to_room_info().banned_user_ids, vec!["user1"]);
+
+        assert!(matches!(
+            room.unban_user("user2", "user1"),
+            Err(RoomError::RoomHostOnly(_))
+        ));
+        room.unban_user("host_user", "user1").unwrap();
+        assert!(matches!(
+            room.unban_user("host_user", "user1"),
+            Err(RoomError::NotBanned)
+        ));
+        room.add_user("user1".to_string(), None, JoinCredentials::default())
+            .unwrap();
+        assert_eq!(room.member_count(), 2);
+    }
+
+    #[test]
+    fn test_kick_ends_running_game() {
+        let mut room = create_test_room();
+        room.add_user("user1".to_string(), None, JoinCredentials::default())
+            .unwrap();
+        room.start_game("host_user", 100, false).unwrap();
+        room.report_arrival("host_user", "host_user", 500, 1)
+            .unwrap();
+        let mut rx = room.subscribe();
+
+        room.kick_user("user1", false).unwrap();
+        assert_eq!(room.state(), RoomState::Ended);
+        assert!(matches!(
+            rx.try_recv().unwrap().event,
+            Some(room_event::Event::MemberLeft(MemberLeft {
+                kicked: true,
+                ..
+            }))
+        ));
+        assert!(matches!(
+            rx.try_recv().unwrap().event,
+            Some(room_event::Event::StateChanged(_))
+        ));
+    }
+
+    #[test]
+    fn test_member_signing_keys() {
+        let mut room = create_test_room();
+        let topology = room
+            .add_user("user1".to_string(), None, JoinCredentials::default())
+            .unwrap();
+
+        // Every member's public key, including the joiner's own, is in the topology
+        let key = room.signing_key("user1").unwrap();
+        let public_key = ed25519_dalek::SigningKey::from_bytes(&key.clone().try_into().unwrap())
+            .verifying_key()
+            .to_bytes();
+        assert_eq!(topology.member_keys["user1"], public_key);
+        assert!(topology.member_keys.contains_key("host_user"));
+        assert_eq!(topology.host_user_id, "host_user");
+
+        // Rejoining keeps the key, and other members see it too
+        room.add_user("user1".to_string(), None, JoinCredentials::default())
+            .unwrap();
+        assert_eq!(room.signing_key("user1").unwrap(), key);
+        assert_ne!(room.signing_key("host_user").unwrap(), key);
+        let host_topology = room.get_topology("host_user").unwrap();
+        assert_eq!(host_topology.member_keys["user1"], public_key);
+
+        room.kick_user("user1", false).unwrap();
+        assert!(room.signing_key("user1").is_none());
+        let host_topology = room.get_topology("host_user").unwrap();
+        assert!(!host_topology.member_keys.contains_key("user1"));
+    }
+
+    #[test]
+    fn test_change_role() {
+        let mut room = Room::new(
+            uuid::Uuid::new_v4(),
+            "Small Room".to_string(),
+            "map_123".to_string(),
+            2,
+            true,
+            "host".to_string(),
+            "ws://localhost:3000/signaling".to_string(),
+            TopologySettings::default().for_room(2),
+        );
+        room.add_user("user1".to_string(), None, JoinCredentials::default())
+            .unwrap();
+        room.add_user(
+            "user2".to_string(),
+            Some(RoomRole::Spectator),
+            JoinCredentials::default(),
+        )
+        .unwrap();
+        let version = room.topology_version();
+        let mut rx = room.subscribe();
+
+        // No free slot while user1 plays
+        let result = room.change_role("user2", RoomRole::Participant);
+        assert!(matches!(result, Err(RoomError::RoomFull)));
+        assert!(matches!(
+            room.change_role("user2", RoomRole::Unspecified),
+            Err(RoomError::InvalidRole)
+        ));
+
+        room.change_role("user1", RoomRole::Spectator).unwrap();
+        room.change_role("user2", RoomRole::Participant).unwrap();
+        assert_eq!(room.participants_in_join_order(), vec!["host", "user2"]);
+        assert_eq!(room.topology_version(), version + 2);
+        assert!(matches!(
+            rx.try_recv().unwrap().event,
+            Some(room_event::Event::RoleChanged(RoleChanged { ref user_id, role, .. }))
+                if user_id == "user1" && role == i32::from(RoomRole::Spectator)
+        ));
+
+        // Same role is a no-op
+        room.change_role("user2", RoomRole::Participant).unwrap();
+        assert_eq!(room.topology_version(), version + 2);
+
+        room.start_game("host", 10, false).unwrap();
+        assert!(matches!(
+            room.change_role("user1", RoomRole::Participant),
+            Err(RoomError::RoleChangeNotAllowed)
+        ));
+    }
+
+    #[test]
+    fn test_update_room() {
+        let mut room = create_test_room();
+        room.add_user("user1".to_string(), None, JoinCredentials::default())
+            .unwrap();
+        room.add_user("user2".to_string(), None, JoinCredentials::default())
+            .unwrap();
+        let mut rx = room.subscribe();
+
+        let update = RoomUpdate {
+            name: Some("Renamed".to_string()),
+            ..RoomUpdate::default()
+        };
+        assert!(matches!(
+            room.update("user1", update.clone()),
+            Err(RoomError::RoomHostOnly(_))
+        ));
+        let too_small = RoomUpdate {
+            max_players: Some(2),
+            ..RoomUpdate::default()
+        };
+        assert!(matches!(
+            room.update("host_user", too_small),
+            Err(RoomError::MaxPlayersBelowParticipants(3))
+        ));
+        let quorum = |quorum, max_players| RoomUpdate {
+            max_players,
+            ready_check: Some(ReadyCheck {
+                required: true,
+                quorum,
+            }),
+            ..RoomUpdate::default()
+        };
+        let max_players = room.to_room_info().max_players;
+        assert!(matches!(
+            room.update("host_user", quorum(max_players + 1, None)),
+            Err(RoomError::QuorumAboveMaxPlayers(m)) if m == max_players
+        ));
+        assert!(matches!(
+            room.update("host_user", quorum(4, Some(3))),
+            Err(RoomError::QuorumAboveMaxPlayers(3))
+        ));
+
+        let version = room.topology_version();
+        let update = RoomUpdate {
+            map: Some(("map_456".to_string(), 2)),
+            max_players: Some(4),
+            is_public: Some(false),
+            network_config: Some(NetworkConfig {
+                mesh_group_size: 2,
+                gossip_ttl: 4,
+                ..NetworkConfig::default()
+            }),
+            ..update
+        };
+        room.update("host_user", update).unwrap();
+
+        let info = room.to_room_info();
+        assert_eq!(info.room_name, "Renamed");
+        assert_eq!(info.map_id, "map_456");
+        assert_eq!(info.map_revision, 2);
+        assert_eq!(info.max_players, 4);
+        assert!(!info.is_public);
+        let network = info.network_config.unwrap();
+        assert_eq!(network.mesh_group_size, 2);
+        assert_eq!(network.gossip_ttl, 4);
+        assert_eq!(network.lockstep_delay_frames, 6);
+        // The smaller mesh group size split the three members over two groups
+        assert_eq!(room.topology_manager().group_count(), 2);
+        assert_eq!(room.topology_version(), version + 1);
+
+        assert!(matches!(
+            rx.try_recv().unwrap().event,
+            Some(room_event::Event::TopologyChanged(_))
+        ));
+        assert!(matches!(
+            rx.try_recv().unwrap().event,
+            Some(room_event::Event::RoomUpdated(RoomUpdated {
+                map_changed: true,
+                ..
+            }))
+        ));
+
+        room.start_game("host_user", 10, false).unwrap();
+        assert!(matches!(
+            room.update("host_user", RoomUpdate::default()),
+            Err(RoomError::NotWaiting)
+        ));
+    }
+
+    #[test]
+    fn test_ready_check_gates_start() {
+        let mut room = create_test_room();
+        room.add_user("user1".to_string(), None, JoinCredentials::default())
+            .unwrap();
+        room.add_user(
+            "watcher".to_string(),
+            Some(RoomRole::Spectator),
+            JoinCredentials::default(),
+        )
+        .unwrap();
+        room.set_ready_check(ReadyCheck {
+            required: true,
+            quorum: 0,
+        });
+        let mut rx = room.subscribe();
+
+        let ready = Readiness {
+            map_loaded: true,
+            p2p_connected: true,
+            ready: true,
+        };
+        let user = room.set_readiness("user1", ready).unwrap();
+        assert_eq!(user.readiness, Some(ready));
+        assert!(matches!(
+            rx.try_recv().unwrap().event,
+            Some(room_event::Event::ReadinessChanged(ReadinessChanged { ref user_id, .. }))
+                if user_id == "user1"
+        ));
+        // Unchanged readiness is not re-announced
+        room.set_readiness("user1", ready).unwrap();
+        assert!(rx.try_recv().is_err());
+
+        // Spectators don't count towards the quorum
+        room.set_readiness("watcher", ready).unwrap();
+        assert!(matches!(
+            room.start_game("host_user", 10, false),
+            Err(RoomError::NotReady {
+                ready: 1,
+                required: 2
+            })
+        ));
+
+        room.set_ready_check(ReadyCheck {
+            required: true,
+            quorum: 1,
+        });
+        assert!(room.start_game("host_user", 10, false).unwrap());
+        assert!(matches!(
+            room.set_readiness("user1", Readiness::default()),
+            Err(RoomError::NotWaiting)
+        ));
+
+        // A new round drops ready confirmations but keeps P2P links
+        room.report_arrival("host_user", "host_user", 100, 1)
+            .unwrap();
+        room.report_arrival("host_user", "user1", 110, 2).unwrap();
+        room.reset("host_user", None).unwrap();
+        let user1 = room
+            .get_room_users()
+            .into_iter()
+            .find(|u| u.user_id == "user1")
+            .unwrap();
+        assert_eq!(
+            user1.readiness,
+            Some(Readiness {
+                p2p_connected: true,
+                ..Readiness::default()
+            })
+        );
+
+        // The host can always override
+        room.set_ready_check(ReadyCheck {
+            required: true,
+            quorum: 0,
+        });
+        assert!(room.start_game("host_user", 10, true).unwrap());
+    }
+
+    #[test]
+    fn test_relay_after_failed_links() {
+        let mut room = create_test_room();
+        room.add_user("user1".to_string(), None, JoinCredentials::default())
+            .unwrap();
+        let version = room.topology_version();
+
+        let all_down = [PeerConnectionStatus {
+            peer_id: "pending_host_user".to_string(),
+            connected: false,
+            ..Default::default()
+        }];
+        for _ in 0..3 {
+            room.update_connection_status("user1", &all_down);
+        }
+
+        let relayed = room.get_topology("user1").unwrap();
+        assert!(relayed.relayed);
+        assert_eq!(
+            relayed.relay_url,
+            format!("ws://localhost:3000/relay/{}", room.id())
+        );
+        assert!(room.topology_version() > version);
+
+        // The host bridges relayed traffic into the mesh
+        let host = room.get_topology("host_user").unwrap();
+        assert!(host.is_bridge);
+        assert!(!host.relayed);
+        assert_eq!(host.relay_url, relayed.relay_url);
+    }
 }
@@ -0,0 +1,728 @@
+//! Typed server configuration.
+//!
+//! Values are layered, later layers winning:
+//! 1. built-in defaults
+//! 2. TOML file given by `--config` / `MARBLE_CONFIG`
+//! 3. environment variables `MARBLE__<SECTION>__<KEY>` (e.g. `MARBLE__TOPOLOGY__GOSSIP_TTL=12`)
+//! 4. `--set <section>.<key>=<value>` flags
+//! 5. dedicated flags such as `--bind-addr` (each also readable from its own env var)
+//!
+//! The merged result is validated once at startup; an invalid configuration aborts the server.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand};
+use marble_core::MapLimits;
+use marble_core::bevy::gossip::MessageClass;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::handler::rate_limit::{Quota, RateLimitConfig, UserQuotas};
+use crate::topology::{SimulationConfig, TopologyManagerConfig};
+
+/// Prefix of environment variables that override config keys.
+const ENV_PREFIX: &str = "MARBLE__";
+
+/// Longest accepted token lifetime (one year); `chrono::Duration::hours` panics far above it.
+const MAX_JWT_EXPIRY_HOURS: i64 = 24 * 365;
+
+/// Slowest accepted rate-limit refill rate (one token per ~17 minutes).
+const MIN_RATE_PER_SECOND: f64 = 0.001;
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Marble-Live server")]
+pub struct Cli {
+    /// TOML configuration file
+    #[arg(long, short, env = "MARBLE_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    /// Override any key, e.g. `--set topology.gossip_ttl=12` (repeatable)
+    #[arg(long = "set", value_name = "KEY=VALUE")]
+    pub overrides: Vec<String>,
+
+    /// Listen address
+    #[arg(long, env = "MARBLE_BIND_ADDR")]
+    pub bind_addr: Option<SocketAddr>,
+
+    /// Public signaling WebSocket URL handed to clients
+    #[arg(long, env = "SIGNALING_URL")]
+    pub signaling_url: Option<String>,
+
+    /// Allowed CORS origin (repeatable; none = any origin)
+    #[arg(
+        long = "cors-origin",
+        env = "MARBLE_CORS_ORIGINS",
+        value_delimiter = ','
+    )]
+    pub cors_origins: Option<Vec<String>>,
+
+    /// Lifetime of issued tokens in hours
+    #[arg(long, env = "MARBLE_JWT_EXPIRY_HOURS")]
+    pub jwt_expiry_hours: Option<i64>,
+
+    /// User granted the admin role at login (repeatable)
+    #[arg(long = "admin-user-id", env = "ADMIN_USER_IDS", value_delimiter = ',')]
+    pub admin_user_ids: Option<Vec<String>>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Simulate gossip propagation over a room topology built from the loaded settings, then exit
+    SimulateGossip(SimulateGossipArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct SimulateGossipArgs {
+    /// Players in the simulated room (also its `max_players`)
+    #[arg(long, default_value_t = 100)]
+    pub players: u32,
+
+    /// Messages flooded per scenario
+    #[arg(long, default_value_t = 200)]
+    pub messages: usize,
+
+    /// Lower bound of the one-way link latency
+    #[arg(long, default_value_t = 20.0)]
+    pub min_latency_ms: f64,
+
+    /// Upper bound of the one-way link latency
+    #[arg(long, default_value_t = 120.0)]
+    pub max_latency_ms: f64,
+
+    /// Probability that a single transmission is lost (0.0 ~ 1.0)
+    #[arg(long, default_value_t = 0.01)]
+    pub loss: f64,
+
+    /// TTL of each flooded message; defaults to what clients give broadcasts
+    #[arg(long, default_value_t = MessageClass::Control.default_ttl())]
+    pub ttl: u32,
+
+    /// RNG seed
+    #[arg(long, default_value_t = 1)]
+    pub seed: u64,
+}
+
+impl SimulateGossipArgs {
+    /// Simulation parameters for a room of `players` under `topology`.
+    pub fn simulation(&self, topology: &TopologySettings) -> Result<SimulationConfig, ConfigError> {
+        let mut problems = Vec::new();
+        if self.players < 2 {
+            problems.push("--players must be at least 2".to_string());
+        }
+        if !(0.0..=self.max_latency_ms).contains(&self.min_latency_ms) {
+            problems.push("--min-latency-ms must be between 0 and --max-latency-ms".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.loss) {
+            problems.push("--loss must be between 0 and 1".to_string());
+        }
+        if !problems.is_empty() {
+            return Err(ConfigError::Invalid(problems));
+        }
+
+        Ok(SimulationConfig {
+            topology: topology.for_room(self.players),
+            players: self.players as usize,
+            messages: self.messages,
+            min_latency_ms: self.min_latency_ms,
+            max_latency_ms: self.max_latency_ms,
+            loss: self.loss,
+            ttl: self.ttl,
+            seed: self.seed,
+        })
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("failed to read {}: {source}", .path.display())]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("failed to parse {}: {source}", .path.display())]
+    Parse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+
+    #[error("invalid override '{0}': expected <section>.<key>=<value>")]
+    Override(String),
+
+    #[error("invalid configuration: {0}")]
+    Deserialize(#[from] toml::de::Error),
+
+    #[error("invalid configuration:\n  {}", .0.join("\n  "))]
+    Invalid(Vec<String>),
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub server: ServerSettings,
+    pub auth: AuthSettings,
+    pub topology: TopologySettings,
+    pub limits: LimitSettings,
+    pub rate_limit: RateLimitSettings,
+    pub storage: StorageSettings,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerSettings {
+    pub bind_addr: SocketAddr,
+    /// Signaling URL given to clients; derived from `bind_addr` when unset
+    pub signaling_url: Option<String>,
+    /// Allowed CORS origins; empty allows any origin
+    pub cors_origins: Vec<String>,
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        Self {
+            bind_addr: SocketAddr::from(([0, 0, 0, 0], 3000)),
+            signaling_url: None,
+            cors_origins: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthSettings {
+    pub jwt_expiry_hours: i64,
+    /// Token signing secret; random per process when unset (tokens die on restart)
+    pub jwt_secret: Option<String>,
+    /// Users granted the admin role at login
+    pub admin_user_ids: Vec<String>,
+}
+
+impl Default for AuthSettings {
+    fn default() -> Self {
+        Self {
+            jwt_expiry_hours: 24,
+            jwt_secret: None,
+            admin_user_ids: Vec::new(),
+        }
+    }
+}
+
+/// P2P topology numbers applied to every new room.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TopologySettings {
+    /// Mesh group size is `max_players / mesh_group_divisor`, clamped to the bounds below
+    pub mesh_group_divisor: u32,
+    pub min_mesh_group_size: u32,
+    pub max_mesh_group_size: u32,
+    pub peer_connections: u32,
+    pub bridges_per_group: usize,
+    pub gossip_ttl: u32,
+    pub lockstep_delay_frames: u32,
+    /// Groups filled below this share of the mesh group size are merged into others
+    pub min_group_fill_percent: u32,
+}
+
+impl Default for TopologySettings {
+    fn default() -> Self {
+        Self {
+            mesh_group_divisor: 3,
+            min_mesh_group_size: 10,
+            max_mesh_group_size: 40,
+            peer_connections: 5,
+            bridges_per_group: 2,
+            gossip_ttl: 10,
+            lockstep_delay_frames: 6,
+            min_group_fill_percent: 50,
+        }
+    }
+}
+
+impl TopologySettings {
+    /// Topology manager config for a room of `max_players`.
+    pub fn for_room(&self, max_players: u32) -> TopologyManagerConfig {
+        TopologyManagerConfig {
+            mesh_group_size: (max_players / self.mesh_group_divisor)
+                .clamp(self.min_mesh_group_size, self.max_mesh_group_size),
+            peer_connections: self.peer_connections,
+            bridges_per_group: self.bridges_per_group,
+            gossip_ttl: self.gossip_ttl,
+            lockstep_delay_frames: self.lockstep_delay_frames,
+            min_group_fill_percent: self.min_group_fill_percent,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitSettings {
+    /// Range `CreateRoom.max_players` is clamped to
+    pub min_players: u32,
+    pub max_players: u32,
+    pub max_open_rooms_per_user: usize,
+    pub max_maps_per_user: usize,
+    pub map: MapLimits,
+}
+
+impl Default for LimitSettings {
+    fn default() -> Self {
+        let quotas = UserQuotas::default();
+        Self {
+            min_players: 2,
+            max_players: 32,
+            max_open_rooms_per_user: quotas.max_open_rooms,
+            max_maps_per_user: quotas.max_stored_maps,
+            map: MapLimits::default(),
+        }
+    }
+}
+
+impl LimitSettings {
+    pub fn quotas(&self) -> UserQuotas {
+        UserQuotas {
+            max_open_rooms: self.max_open_rooms_per_user,
+            max_stored_maps: self.max_maps_per_user,
+        }
+    }
+}
+
+/// Token buckets in front of the gRPC routes.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitSettings {
+    /// Shared by all calls from one IP address
+    pub per_ip: Quota,
+    /// Methods without their own quota
+    pub default: Quota,
+    /// Quotas by gRPC path, added to or replacing the built-in per-method ones
+    pub methods: HashMap<String, Quota>,
+    /// Use the first `x-forwarded-for` entry as client IP (only behind a trusted proxy)
+    pub trust_forwarded_for: bool,
+}
+
+impl Default for RateLimitSettings {
+    fn default() -> Self {
+        let config = RateLimitConfig::default();
+        Self {
+            per_ip: config.per_ip,
+            default: config.default,
+            methods: HashMap::new(),
+            trust_forwarded_for: config.trust_forwarded_for,
+        }
+    }
+}
+
+impl RateLimitSettings {
+    pub fn config(&self) -> RateLimitConfig {
+        let mut config = RateLimitConfig {
+            per_ip: self.per_ip,
+            default: self.default,
+            trust_forwarded_for: self.trust_forwarded_for,
+            ..RateLimitConfig::default()
+        };
+        config.methods.extend(
+            self.methods
+                .iter()
+                .map(|(method, quota)| (method.clone(), *quota)),
+        );
+        config
+    }
+
+    fn quotas(&self) -> impl Iterator<Item = &Quota> {
+        [&self.per_ip, &self.default]
+            .into_iter()
+            .chain(self.methods.values())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageBackend {
+    /// Everything lives in process memory and is lost on restart
+    #[default]
+    Memory,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageSettings {
+    pub backend: StorageBackend,
+    /// Rendered map thumbnails kept before the cache is flushed
+    pub thumbnail_cache_size: usize,
+}
+
+impl Default for StorageSettings {
+    fn default() -> Self {
+        Self {
+            backend: StorageBackend::Memory,
+            thumbnail_cache_size: 1024,
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Load and validate the configuration from CLI flags, the process environment
+    /// and the config file they point to.
+    pub fn load(cli: &Cli) -> Result<Self, ConfigError> {
+        let mut table = match &cli.config {
+            Some(path) => {
+                let text = std::fs::read_to_string(path).map_err(|source| ConfigError::Read {
+                    path: path.clone(),
+                    source,
+                })?;
+                toml::from_str(&text).map_err(|source| ConfigError::Parse {
+                    path: path.clone(),
+                    source,
+                })?
+            }
+            None => toml::Table::new(),
+        };
+
+        for (key, value) in std::env::vars() {
+            if let Some(path) = key.strip_prefix(ENV_PREFIX) {
+                let path = path.to_lowercase().replace("__", ".");
+                set_path(&mut table, &path, &value)
+                    .ok_or_else(|| ConfigError::Override(key.clone()))?;
+            }
+        }
+        for assignment in &cli.overrides {
+            let (path, value) = assignment
+                .split_once('=')
+                .ok_or_else(|| ConfigError::Override(assignment.clone()))?;
+            set_path(&mut table, path.trim(), value.trim())
+                .ok_or_else(|| ConfigError::Override(assignment.clone()))?;
+        }
+
+        let mut config: Self = toml::Value::Table(table).try_into()?;
+        config.apply_cli(cli);
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn apply_cli(&mut self, cli: &Cli) {
+        if let Some(addr) = cli.bind_addr {
+            self.server.bind_addr = addr;
+        }
+        if let Some(url) = &cli.signaling_url {
+            self.server.signaling_url = Some(url.clone());
+        }
+        if let Some(origins) = &cli.cors_origins {
+            self.server.cors_origins.clone_from(origins);
+        }
+        if let Some(hours) = cli.jwt_expiry_hours {
+            self.auth.jwt_expiry_hours = hours;
+        }
+        if let Some(ids) = &cli.admin_user_ids {
+            self.auth.admin_user_ids.clone_from(ids);
+        }
+    }
+
+    /// Signaling URL handed to clients.
+    pub fn signaling_url(&self) -> String {
+        self.server
+            .signaling_url
+            .clone()
+            .unwrap_or_else(|| format!("ws://localhost:{}/signaling", self.server.bind_addr.port()))
+    }
+
+    /// Check cross-field constraints, reporting every problem at once.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems = Vec::new();
+        let mut check = |ok: bool, problem: &str| {
+            if !ok {
+                problems.push(problem.to_string());
+            }
+        };
+
+        if let Some(url) = &self.server.signaling_url {
+            check(
+                url.starts_with("ws://") || url.starts_with("wss://"),
+                "server.signaling_url must start with ws:// or wss://",
+            );
+        }
+        for origin in &self.server.cors_origins {
+            check(
+                (origin.starts_with("http://") || origin.starts_with("https://"))
+                    && http::HeaderValue::from_str(origin).is_ok(),
+                "server.cors_origins entries must be http(s) origins",
+            );
+        }
+
+        check(
+            (1..=MAX_JWT_EXPIRY_HOURS).contains(&self.auth.jwt_expiry_hours),
+            "auth.jwt_expiry_hours must be between 1 and 8760 (one year)",
+        );
+        if let Some(secret) = &self.auth.jwt_secret {
+            check(
+                secret.len() >= 16,
+                "auth.jwt_secret must be at least 16 characters",
+            );
+        }
+
+        let topology = &self.topology;
+        check(
+            topology.mesh_group_divisor > 0,
+            "topology.mesh_group_divisor must be positive",
+        );
+        check(
+            topology.min_mesh_group_size >= 2,
+            "topology.min_mesh_group_size must be at least 2",
+        );
+        check(
+            topology.min_mesh_group_size <= topology.max_mesh_group_size,
+            "topology.min_mesh_group_size must not exceed max_mesh_group_size",
+        );
+        check(
+            topology.peer_connections > 0,
+            "topology.peer_connections must be positive",
+        );
+        check(
+            topology.bridges_per_group > 0,
+            "topology.bridges_per_group must be positive",
+        );
+        check(
+            topology.gossip_ttl > 0,
+            "topology.gossip_ttl must be positive",
+        );
+        check(
+            topology.min_group_fill_percent <= 100,
+            "topology.min_group_fill_percent must be at most 100",
+        );
+
+        let limits = &self.limits;
+        check(
+            limits.min_players >= 2,
+            "limits.min_players must be at least 2",
+        );
+        check(
+            limits.min_players <= limits.max_players,
+            "limits.min_players must not exceed max_players",
+        );
+        check(
+            limits.max_open_rooms_per_user > 0,
+            "limits.max_open_rooms_per_user must be positive",
+        );
+        check(
+            limits.max_maps_per_user > 0,
+            "limits.max_maps_per_user must be positive",
+        );
+        check(
+            limits.map.max_objects > 0 && limits.map.max_coordinate > 0.0,
+            "limits.map.max_objects and max_coordinate must be positive",
+        );
+
+        let rate_limit = &self.rate_limit;
+        check(
+            rate_limit
+                .quotas()
+                .all(|q| q.burst > 0 && q.per_second >= MIN_RATE_PER_SECOND),
+            "rate_limit quotas must have a positive burst and a per_second of at least 0.001",
+        );
+        check(
+            rate_limit.methods.keys().all(|m| m.starts_with('/')),
+            "rate_limit.methods keys must be gRPC paths such as /marble.user.UserService/Login",
+        );
+
+        check(
+            self.storage.thumbnail_cache_size > 0,
+            "storage.thumbnail_cache_size must be positive",
+        );
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(problems))
+        }
+    }
+}
+
+/// Set the dotted `path` in `table` to `raw`, parsed as a TOML value when possible and
+/// kept as a string otherwise. Returns `None` if the path crosses a non-table value.
+fn set_path(table: &mut toml::Table, path: &str, raw: &str) -> Option<()> {
+    let value = toml::from_str::<toml::Table>(&format!("v = {raw}"))
+        .ok()
+        .and_then(|mut t| t.remove("v"))
+        .unwrap_or_else(|| toml::Value::String(raw.to_string()));
+
+    let mut keys = path.split('.').peekable();
+    let mut current = table;
+    while let Some(key) = keys.next() {
+        if key.is_empty() {
+            return None;
+        }
+        if keys.peek().is_none() {
+            current.insert(key.to_string(), value);
+            return Some(());
+        }
+        current = current
+            .entry(key)
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+            .as_table_mut()?;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load_str(text: &str, overrides: &[(&str, &str)]) -> Result<ServerConfig, ConfigError> {
+        let mut table: toml::Table = toml::from_str(text).unwrap();
+        for (path, value) in overrides {
+            set_path(&mut table, path, value).unwrap();
+        }
+        let config: ServerConfig = toml::Value::Table(table).try_into()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    #[test]
+    fn test_defaults_are_valid() {
+        let config = load_str("", &[]).unwrap();
+        assert_eq!(config.server.bind_addr.port(), 3000);
+        assert_eq!(config.auth.jwt_expiry_hours, 24);
+        assert_eq!(config.signaling_url(), "ws://localhost:3000/signaling");
+    }
+
+    #[test]
+    fn test_example_file_is_valid() {
+        let config = load_str(include_str!("../marble-server.example.toml"), &[]).unwrap();
+        assert_eq!(config.limits.map, MapLimits::default());
+        assert_eq!(config.storage.backend, StorageBackend::Memory);
+        let rate_limit = config.rate_limit.config();
+        let default = RateLimitConfig::default();
+        assert_eq!(rate_limit.per_ip, default.per_ip);
+        assert_eq!(rate_limit.methods, default.methods);
+    }
+
+    #[test]
+    fn test_rate_limit_section() {
+        let config = load_str(
+            r#"
+            [rate_limit]
+            per_ip = { per_second = 10.0, burst = 20 }
+
+            [rate_limit.methods]
+            "/marble.room.RoomService/JoinRoom" = { per_second = 1.0, burst = 3 }
+            "/marble.user.UserService/Login" = { per_second = 0.5, burst = 2 }
+            "#,
+            &[("rate_limit.trust_forwarded_for", "true")],
+        )
+        .unwrap();
+
+        let rate_limit = config.rate_limit.config();
+        assert_eq!(rate_limit.per_ip, Quota::per_second(10, 20));
+        assert_eq!(rate_limit.default, RateLimitConfig::default().default);
+        assert!(rate_limit.trust_forwarded_for);
+        assert_eq!(
+            rate_limit.methods["/marble.room.RoomService/JoinRoom"],
+            Quota::per_second(1, 3)
+        );
+        // Configured methods replace built-in quotas, the others stay
+        assert_eq!(
+            rate_limit.methods["/marble.user.UserService/Login"].burst,
+            2
+        );
+        assert_eq!(
+            rate_limit.methods["/marble.map.MapService/CreateMap"],
+            Quota::per_minute(10)
+        );
+
+        assert!(matches!(
+            load_str("[rate_limit.per_ip]\nper_second = 1.0\nbrust = 2\n", &[]),
+            Err(ConfigError::Deserialize(_))
+        ));
+        assert!(matches!(
+            load_str(
+                "[rate_limit]\ndefault = { per_second = 0.0, burst = 1 }\n",
+                &[]
+            ),
+            Err(ConfigError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn test_file_and_overrides() {
+        let config = load_str(
+            r#"
+            [server]
+            bind_addr = "127.0.0.1:8080"
+            cors_origins = ["https://marble.example"]
+
+            [topology]
+            gossip_ttl = 8
+            "#,
+            &[
+                ("topology.gossip_ttl", "12"),
+                ("limits.map.max_objects", "64"),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(config.server.bind_addr.port(), 8080);
+        assert_eq!(config.signaling_url(), "ws://localhost:8080/signaling");
+        assert_eq!(config.topology.gossip_ttl, 12);
+        assert_eq!(config.limits.map.max_objects, 64);
+        // Untouched keys keep their defaults
+        assert_eq!(config.limits.map.max_keyframes, 1024);
+    }
+
+    #[test]
+    fn test_unknown_key_is_rejected() {
+        let result = load_str("[topology]\ngosip_ttl = 3\n", &[]);
+        assert!(matches!(result, Err(ConfigError::Deserialize(_))));
+        let result = load_str("[limits.map]\nmax_object = 10\n", &[]);
+        assert!(matches!(result, Err(ConfigError::Deserialize(_))));
+    }
+
+    #[test]
+    fn test_validation_reports_every_problem() {
+        let Err(ConfigError::Invalid(problems)) = load_str(
+            "[auth]\njwt_expiry_hours = 0\n[topology]\nmin_mesh_group_size = 50\n",
+            &[],
+        ) else {
+            panic!("expected validation failure");
+        };
+        assert_eq!(problems.len(), 2);
+    }
+
+    #[test]
+    fn test_validation_rejects_extreme_values() {
+        let Err(ConfigError::Invalid(problems)) = load_str(
+            "[auth]\njwt_expiry_hours = 9223372036854775807\n\
+             [rate_limit]\nper_ip = { per_second = 1e-30, burst = 1 }\n",
+            &[],
+        ) else {
+            panic!("expected validation failure");
+        };
+        assert_eq!(problems.len(), 2);
+    }
+
+    #[test]
+    fn test_mesh_group_size_clamp() {
+        let topology = TopologySettings::default();
+        assert_eq!(topology.for_room(4).mesh_group_size, 10);
+        assert_eq!(topology.for_room(60).mesh_group_size, 20);
+        assert_eq!(topology.for_room(300).mesh_group_size, 40);
+    }
+
+    #[test]
+    fn test_override_value_types() {
+        let mut table = toml::Table::new();
+        set_path(&mut table, "server.signaling_url", "wss://signal.example").unwrap();
+        set_path(&mut table, "auth.jwt_expiry_hours", "6").unwrap();
+        assert_eq!(
+            table["server"]["signaling_url"].as_str(),
+            Some("wss://signal.example")
+        );
+        assert_eq!(table["auth"]["jwt_expiry_hours"].as_integer(), Some(6));
+
+        // A scalar can't be descended into
+        assert!(set_path(&mut table, "auth.jwt_expiry_hours.x", "1").is_none());
+    }
+}
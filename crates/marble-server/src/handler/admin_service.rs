@@ -0,0 +1,224 @@
+use marble_proto::admin::{
+    AuditEntry, BanUserRequest, BanUserResponse, DeleteAnyMapRequest, DeleteAnyMapResponse,
+    ForceCloseRoomRequest, ForceCloseRoomResponse, HideMapRequest, HideMapResponse,
+    ListAllRoomsRequest, ListAllRoomsResponse, ListAuditLogRequest, ListAuditLogResponse,
+    ResetAvatarRequest, ResetAvatarResponse, ResetDisplayNameRequest, ResetDisplayNameResponse,
+    UnbanUserRequest, UnbanUserResponse,
+};
+use marble_proto::room::RoomState;
+use tonic::{Request, Response, Status};
+
+use crate::common::room::Room;
+use crate::service::database::{Database, DatabaseError, StoredAuditEntry};
+use crate::util;
+
+use super::jwt::AuthenticatedUser;
+
+pub struct AdminServiceImpl {
+    database: Database,
+}
+
+impl AdminServiceImpl {
+    pub fn new(database: Database) -> Self {
+        Self { database }
+    }
+
+    /// `user_id` of the caller if its token carries the admin role.
+    fn require_admin(request_extensions: &http::Extensions) -> Result<String, Status> {
+        let user = request_extensions
+            .get::<AuthenticatedUser>()
+            .ok_or_else(|| Status::unauthenticated("Authentication required"))?;
+        if !user.is_admin {
+            return Err(Status::permission_denied("Admin role required"));
+        }
+        Ok(user.user_id.clone())
+    }
+
+    fn audit(&self, admin_id: &str, action: &str, target_id: &str, reason: &str) {
+        tracing::info!(admin_id, action, target_id, reason, "Admin action");
+        self.database
+            .record_audit(admin_id, action, target_id, reason);
+    }
+}
+
+fn stored_to_audit_entry(e: StoredAuditEntry) -> AuditEntry {
+    AuditEntry {
+        entry_id: e.entry_id,
+        admin_id: e.admin_id,
+        action: e.action,
+        target_id: e.target_id,
+        reason: e.reason,
+        created_at: e.created_at.to_rfc3339(),
+    }
+}
+
+#[tonic::async_trait]
+impl marble_proto::admin::admin_service_server::AdminService for AdminServiceImpl {
+    async fn list_all_rooms(
+        &self,
+        request: Request<ListAllRoomsRequest>,
+    ) -> Result<Response<ListAllRoomsResponse>, Status> {
+        let admin_id = Self::require_admin(request.extensions())?;
+        let req = request.into_inner();
+
+        let states: Vec<RoomState> = req
+            .states
+            .iter()
+            .filter_map(|&s| RoomState::try_from(s).ok())
+            .collect();
+        let page_size = if req.page_size == 0 {
+            20
+        } else {
+            req.page_size
+        };
+
+        let (rooms, next_page_token, total_count) =
+            self.database
+                .list_rooms(page_size, &req.page_token, &states, None, None, false, true);
+        self.audit(&admin_id, "ListAllRooms", "", "");
+
+        Ok(Response::new(ListAllRoomsResponse {
+            rooms: rooms.iter().map(Room::to_room_info).collect(),
+            next_page_token,
+            total_count,
+        }))
+    }
+
+    async fn force_close_room(
+        &self,
+        request: Request<ForceCloseRoomRequest>,
+    ) -> Result<Response<ForceCloseRoomResponse>, Status> {
+        let admin_id = Self::require_admin(request.extensions())?;
+        let req = request.into_inner();
+        let room_id = util::tonic_uuid!(&req.room_id)?;
+
+        self.database
+            .force_close_room(&room_id)
+            .map_err(tonic::Status::from)?;
+        self.audit(&admin_id, "ForceCloseRoom", &req.room_id, &req.reason);
+
+        Ok(Response::new(ForceCloseRoomResponse {}))
+    }
+
+    async fn delete_any_map(
+        &self,
+        request: Request<DeleteAnyMapRequest>,
+    ) -> Result<Response<DeleteAnyMapResponse>, Status> {
+        let admin_id = Self::require_admin(request.extensions())?;
+        let req = request.into_inner();
+
+        self.database
+            .admin_delete_map(&req.map_id)
+            .map_err(tonic::Status::from)?;
+        self.audit(&admin_id, "DeleteAnyMap", &req.map_id, &req.reason);
+
+        Ok(Response::new(DeleteAnyMapResponse {}))
+    }
+
+    async fn hide_map(
+        &self,
+        request: Request<HideMapRequest>,
+    ) -> Result<Response<HideMapResponse>, Status> {
+        let admin_id = Self::require_admin(request.extensions())?;
+        let req = request.into_inner();
+
+        self.database
+            .set_map_hidden(&req.map_id, req.hidden)
+            .map_err(tonic::Status::from)?;
+        let action = if req.hidden { "HideMap" } else { "UnhideMap" };
+        self.audit(&admin_id, action, &req.map_id, &req.reason);
+
+        Ok(Response::new(HideMapResponse {}))
+    }
+
+    async fn ban_user(
+        &self,
+        request: Request<BanUserRequest>,
+    ) -> Result<Response<BanUserResponse>, Status> {
+        let admin_id = Self::require_admin(request.extensions())?;
+        let req = request.into_inner();
+
+        if req.user_id == admin_id {
+            return Err(Status::invalid_argument("Cannot ban yourself"));
+        }
+        self.database
+            .ban_user(&req.user_id)
+            .map_err(tonic::Status::from)?;
+        self.audit(&admin_id, "BanUser", &req.user_id, &req.reason);
+
+        Ok(Response::new(BanUserResponse {}))
+    }
+
+    async fn unban_user(
+        &self,
+        request: Request<UnbanUserRequest>,
+    ) -> Result<Response<UnbanUserResponse>, Status> {
+        let admin_id = Self::require_admin(request.extensions())?;
+        let req = request.into_inner();
+
+        if !self.database.unban_user(&req.user_id) {
+            return Err(Status::not_found("User is not banned"));
+        }
+        self.audit(&admin_id, "UnbanUser", &req.user_id, &req.reason);
+
+        Ok(Response::new(UnbanUserResponse {}))
+    }
+
+    async fn reset_display_name(
+        &self,
+        request: Request<ResetDisplayNameRequest>,
+    ) -> Result<Response<ResetDisplayNameResponse>, Status> {
+        let admin_id = Self::require_admin(request.extensions())?;
+        let req = request.into_inner();
+
+        let user = self
+            .database
+            .reset_display_name(&req.user_id)
+            .map_err(tonic::Status::from)?;
+        self.audit(&admin_id, "ResetDisplayName", &req.user_id, &req.reason);
+
+        Ok(Response::new(ResetDisplayNameResponse {
+            display_name: user.display_name,
+        }))
+    }
+
+    async fn reset_avatar(
+        &self,
+        request: Request<ResetAvatarRequest>,
+    ) -> Result<Response<ResetAvatarResponse>, Status> {
+        let admin_id = Self::require_admin(request.extensions())?;
+        let req = request.into_inner();
+
+        if self.database.get_user(&req.user_id).is_none() {
+            return Err(DatabaseError::UserNotFound.into());
+        }
+        self.database.reset_avatar(&req.user_id);
+        self.audit(&admin_id, "ResetAvatar", &req.user_id, &req.reason);
+
+        Ok(Response::new(ResetAvatarResponse {}))
+    }
+
+    async fn list_audit_log(
+        &self,
+        request: Request<ListAuditLogRequest>,
+    ) -> Result<Response<ListAuditLogResponse>, Status> {
+        let admin_id = Self::require_admin(request.extensions())?;
+        let req = request.into_inner();
+
+        let page_size = if req.page_size == 0 {
+            20
+        } else {
+            req.page_size
+        };
+        // Recorded after the read, so the page doesn't list its own lookup
+        let (entries, next_page_token, total_count) =
+            self.database.list_audit_log(page_size, &req.page_token);
+        self.audit(&admin_id, "ListAuditLog", "", "");
+
+        Ok(Response::new(ListAuditLogResponse {
+            entries: entries.into_iter().map(stored_to_audit_entry).collect(),
+            next_page_token,
+            total_count,
+        }))
+    }
+}
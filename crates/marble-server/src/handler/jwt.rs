@@ -1,11 +1,35 @@
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
 use chrono::{DateTime, Duration, Utc};
+use marble_proto::room::RoomEvent;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use tokio::sync::broadcast;
+
+use crate::service::database::{Database, DatabaseError};
+
+/// Role claim granting access to `AdminService`.
+pub const ADMIN_ROLE: &str = "admin";
 
 /// Authenticated user info stored in tonic Request extensions.
 #[derive(Debug, Clone)]
 pub struct AuthenticatedUser {
     pub user_id: String,
+    /// Token carries the admin role claim
+    pub is_admin: bool,
+}
+
+/// Validated token contents.
+#[derive(Debug, Clone)]
+pub struct Claims {
+    pub user_id: String,
+    pub roles: Vec<String>,
+}
+
+impl Claims {
+    pub fn is_admin(&self) -> bool {
+        self.roles.iter().any(|r| r == ADMIN_ROLE)
+    }
 }
 
 /// Simple JWT-like token manager using HMAC.
@@ -22,6 +46,8 @@ struct TokenPayload {
     sub: String,          // user_id
     exp: i64,             // expiry unix timestamp
     iat: i64,             // issued at
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    roles: Vec<String>,   // role claims, e.g. "admin"
 }
 
 impl JwtManager {
@@ -32,8 +58,8 @@ impl JwtManager {
         }
     }
 
-    /// Generate a token for a `user_id`. Returns (token, `expires_at`).
-    pub fn generate_token(&self, user_id: &str) -> (String, DateTime<Utc>) {
+    /// Generate a token for a `user_id` with role claims. Returns (token, `expires_at`).
+    pub fn generate_token(&self, user_id: &str, roles: &[String]) -> (String, DateTime<Utc>) {
         let now = Utc::now();
         let expires_at = now + Duration::hours(self.expiry_hours);
 
@@ -41,6 +67,7 @@ impl JwtManager {
             sub: user_id.to_string(),
             exp: expires_at.timestamp(),
             iat: now.timestamp(),
+            roles: roles.to_vec(),
         };
 
         let payload_json = serde_json::to_string(&payload).unwrap();
@@ -53,6 +80,11 @@ impl JwtManager {
 
     /// Validate a token and return the `user_id`.
     pub fn validate_token(&self, token: &str) -> Option<String> {
+        self.validate_claims(token).map(|claims| claims.user_id)
+    }
+
+    /// Validate a token and return its claims.
+    pub fn validate_claims(&self, token: &str) -> Option<Claims> {
         let parts: Vec<&str> = token.split('.').collect();
         if parts.len() != 2 {
             return None;
@@ -77,7 +109,10 @@ impl JwtManager {
             return None;
         }
 
-        Some(payload.sub)
+        Some(Claims {
+            user_id: payload.sub,
+            roles: payload.roles,
+        })
     }
 
     fn sign(&self, data: &str) -> String {
@@ -195,10 +230,11 @@ impl Base64Reader {
 }
 
 /// Create a tonic interceptor that validates JWT tokens.
-/// Login RPC bypasses validation.
+/// Login RPC bypasses validation. Tokens of banned users are refused.
 #[allow(dead_code)]
 pub fn jwt_interceptor(
     jwt_manager: JwtManager,
+    database: Database,
 ) -> impl Fn(tonic::Request<()>) -> Result<tonic::Request<()>, tonic::Status> + Clone {
     move |mut req: tonic::Request<()>| {
         // Check the gRPC method path to skip auth for Login
@@ -222,12 +258,18 @@ pub fn jwt_interceptor(
                         tonic::Status::unauthenticated("Invalid authorization format")
                     })?;
 
-                let user_id = jwt_manager.validate_token(token).ok_or_else(|| {
+                let claims = jwt_manager.validate_claims(token).ok_or_else(|| {
                     tonic::Status::unauthenticated("Invalid or expired token")
                 })?;
 
-                req.extensions_mut()
-                    .insert(AuthenticatedUser { user_id });
+                if database.is_banned(&claims.user_id) {
+                    return Err(tonic::Status::permission_denied("User is banned"));
+                }
+
+                req.extensions_mut().insert(AuthenticatedUser {
+                    is_admin: claims.is_admin(),
+                    user_id: claims.user_id,
+                });
 
                 Ok(req)
             }
@@ -238,3 +280,42 @@ pub fn jwt_interceptor(
         }
     }
 }
+
+/// Query string of the room WebSockets (signaling, relay)
+#[derive(Debug, Deserialize)]
+pub struct SocketQuery {
+    /// Same token as the gRPC `authorization` header
+    pub token: String,
+}
+
+/// Authenticate a WebSocket upgrade to a room (`?token=<jwt>`, since browsers can not set
+/// headers on WebSockets) and check membership.
+///
+/// Returns the parsed room id, the user and a subscription to the room's events, or the
+/// HTTP error to answer with.
+pub fn authorize_room_socket(
+    jwt_manager: &JwtManager,
+    database: &Database,
+    room_id: &str,
+    token: &str,
+) -> Result<(uuid::Uuid, String, broadcast::Receiver<RoomEvent>), Response> {
+    let room_id = uuid::Uuid::parse_str(room_id)
+        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid room id").into_response())?;
+    let claims = jwt_manager
+        .validate_claims(token)
+        .ok_or_else(|| (StatusCode::UNAUTHORIZED, "Invalid or expired token").into_response())?;
+    if database.is_banned(&claims.user_id) {
+        return Err((StatusCode::FORBIDDEN, "User is banned").into_response());
+    }
+
+    // Checks membership and subscribes to the room in one step
+    let (_, events) = database
+        .watch_room(&room_id, &claims.user_id)
+        .map_err(|err| match err {
+            DatabaseError::NotRoomMember => {
+                (StatusCode::FORBIDDEN, "Not a room member").into_response()
+            }
+            _ => (StatusCode::NOT_FOUND, "Room not found").into_response(),
+        })?;
+    Ok((room_id, claims.user_id, events))
+}
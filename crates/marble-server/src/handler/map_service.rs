@@ -1,24 +1,71 @@
+use marble_core::MapLimits;
 use marble_proto::map::{
-    CreateMapRequest, CreateMapResponse, DeleteMapRequest, DeleteMapResponse, GetMapRequest,
-    GetMapResponse, ListMapsRequest, ListMapsResponse, MapDetail, MapInfo, UpdateMapRequest,
+    CreateMapRequest, CreateMapResponse, DeleteMapRequest, DeleteMapResponse, ForkMapRequest,
+    ForkMapResponse, GetMapRequest, GetMapResponse, LikeMapRequest, LikeMapResponse,
+    ListMapRevisionsRequest, ListMapRevisionsResponse, ListMapsRequest, ListMapsResponse,
+    MapAncestor, MapDetail, MapInfo, MapRevisionInfo, RestoreMapRevisionRequest,
+    RestoreMapRevisionResponse, UnlikeMapRequest, UnlikeMapResponse, UpdateMapRequest,
     UpdateMapResponse,
 };
 use tonic::{Request, Response, Status};
 
+use crate::config::ServerConfig;
 use crate::service::database::Database;
+use crate::service::map_validation::validate_map_data;
+use crate::service::thumbnail::thumbnail_url;
 
 use super::jwt::AuthenticatedUser;
+use super::rate_limit::UserQuotas;
 
 pub struct MapServiceImpl {
     database: Database,
+    limits: MapLimits,
+    quotas: UserQuotas,
 }
 
 impl MapServiceImpl {
-    pub fn new(database: Database) -> Self {
-        Self { database }
+    pub fn new(database: Database, config: &ServerConfig) -> Self {
+        Self {
+            database,
+            limits: config.limits.map.clone(),
+            quotas: config.limits.quotas(),
+        }
+    }
+
+    /// `NotFound` for a hidden map unless `viewer` owns it or is an admin, as if the map
+    /// did not exist. Unknown maps pass; the database call that follows reports them.
+    fn check_map_visible(
+        &self,
+        map_id: &str,
+        viewer: Option<&AuthenticatedUser>,
+    ) -> Result<(), Status> {
+        match self.database.get_map(map_id) {
+            Some(map) if map.hidden && !can_see_hidden(&map, viewer) => {
+                Err(Status::not_found("Map not found"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn check_map_quota(&self, user_id: &str) -> Result<(), Status> {
+        if self.database.count_maps_by(user_id) >= self.quotas.max_stored_maps {
+            return Err(Status::resource_exhausted(format!(
+                "at most {} maps per user",
+                self.quotas.max_stored_maps
+            )));
+        }
+        Ok(())
     }
 }
 
+/// Whether `viewer` may see a hidden map: its owner and admins can.
+fn can_see_hidden(
+    map: &crate::service::database::StoredMap,
+    viewer: Option<&AuthenticatedUser>,
+) -> bool {
+    viewer.is_some_and(|v| v.is_admin || v.user_id == map.creator_id)
+}
+
 fn stored_to_map_detail(m: &crate::service::database::StoredMap) -> MapDetail {
     MapDetail {
         map_id: m.map_id.clone(),
@@ -29,6 +76,11 @@ fn stored_to_map_detail(m: &crate::service::database::StoredMap) -> MapDetail {
         created_at: m.created_at.to_rfc3339(),
         updated_at: m.updated_at.to_rfc3339(),
         data: m.data.clone(),
+        like_count: m.like_count(),
+        play_count: m.play_count,
+        revision: m.revision,
+        parent_map_id: m.parent_map_id.clone().unwrap_or_default(),
+        parent_revision: m.parent_revision,
     }
 }
 
@@ -41,6 +93,30 @@ fn stored_to_map_info(m: &crate::service::database::StoredMap) -> MapInfo {
         tags: m.tags.clone(),
         created_at: m.created_at.to_rfc3339(),
         updated_at: m.updated_at.to_rfc3339(),
+        like_count: m.like_count(),
+        play_count: m.play_count,
+        revision: m.revision,
+        thumbnail_url: thumbnail_url(&m.map_id, m.revision),
+        parent_map_id: m.parent_map_id.clone().unwrap_or_default(),
+        parent_revision: m.parent_revision,
+    }
+}
+
+fn stored_to_map_ancestor(a: crate::service::database::MapAncestor) -> MapAncestor {
+    MapAncestor {
+        map_id: a.map_id,
+        revision: a.revision,
+        name: a.name,
+        creator_id: a.creator_id,
+    }
+}
+
+fn stored_to_revision_info(r: &crate::service::database::StoredMapRevision) -> MapRevisionInfo {
+    MapRevisionInfo {
+        revision: r.revision,
+        author_id: r.author_id.clone(),
+        created_at: r.created_at.to_rfc3339(),
+        restored_from: r.restored_from,
     }
 }
 
@@ -62,6 +138,8 @@ impl marble_proto::map::map_service_server::MapService for MapServiceImpl {
         if req.name.is_empty() || req.name.len() > 64 {
             return Err(Status::invalid_argument("name must be 1-64 characters"));
         }
+        validate_map_data(&req.data, &self.limits)?;
+        self.check_map_quota(&user_id)?;
 
         let map = self
             .database
@@ -78,15 +156,35 @@ impl marble_proto::map::map_service_server::MapService for MapServiceImpl {
         &self,
         request: Request<GetMapRequest>,
     ) -> Result<Response<GetMapResponse>, Status> {
+        let viewer = request.extensions().get::<AuthenticatedUser>().cloned();
         let req = request.into_inner();
 
-        let map = self
+        let map = match req.revision {
+            Some(revision) => self
+                .database
+                .get_map_at_revision(&req.map_id, revision)
+                .map_err(tonic::Status::from)?,
+            None => self
+                .database
+                .get_map(&req.map_id)
+                .ok_or_else(|| Status::not_found("Map not found"))?,
+        };
+
+        // Hidden maps stay visible to their owner and to admins
+        if map.hidden && !can_see_hidden(&map, viewer.as_ref()) {
+            return Err(Status::not_found("Map not found"));
+        }
+
+        let lineage = self
             .database
-            .get_map(&req.map_id)
-            .ok_or_else(|| Status::not_found("Map not found"))?;
+            .map_lineage(&map)
+            .into_iter()
+            .map(stored_to_map_ancestor)
+            .collect();
 
         Ok(Response::new(GetMapResponse {
             map: Some(stored_to_map_detail(&map)),
+            lineage,
         }))
     }
 
@@ -116,6 +214,7 @@ impl marble_proto::map::map_service_server::MapService for MapServiceImpl {
         let data = if req.data.is_empty() {
             None
         } else {
+            validate_map_data(&req.data, &self.limits)?;
             Some(req.data.as_str())
         };
         let tags = if req.update_tags {
@@ -179,6 +278,12 @@ impl marble_proto::map::map_service_server::MapService for MapServiceImpl {
         };
 
         let page_size = if req.page_size == 0 { 20 } else { req.page_size };
+        let forked_from = if req.forked_from.is_empty() {
+            None
+        } else {
+            Some(req.forked_from.as_str())
+        };
+        let sort = req.sort();
 
         let (maps, next_page_token, total_count) = self.database.list_maps(
             page_size,
@@ -186,6 +291,8 @@ impl marble_proto::map::map_service_server::MapService for MapServiceImpl {
             creator_id,
             name_query,
             &req.tags,
+            forked_from,
+            sort,
         );
 
         let map_infos: Vec<MapInfo> = maps.iter().map(stored_to_map_info).collect();
@@ -196,4 +303,146 @@ impl marble_proto::map::map_service_server::MapService for MapServiceImpl {
             total_count,
         }))
     }
+
+    async fn like_map(
+        &self,
+        request: Request<LikeMapRequest>,
+    ) -> Result<Response<LikeMapResponse>, Status> {
+        let user = request
+            .extensions()
+            .get::<AuthenticatedUser>()
+            .cloned()
+            .ok_or_else(|| Status::unauthenticated("Authentication required"))?;
+
+        let req = request.into_inner();
+        self.check_map_visible(&req.map_id, Some(&user))?;
+
+        let map = self
+            .database
+            .set_map_liked(&req.map_id, &user.user_id, true)
+            .map_err(tonic::Status::from)?;
+
+        Ok(Response::new(LikeMapResponse {
+            map: Some(stored_to_map_info(&map)),
+        }))
+    }
+
+    async fn unlike_map(
+        &self,
+        request: Request<UnlikeMapRequest>,
+    ) -> Result<Response<UnlikeMapResponse>, Status> {
+        let user = request
+            .extensions()
+            .get::<AuthenticatedUser>()
+            .cloned()
+            .ok_or_else(|| Status::unauthenticated("Authentication required"))?;
+
+        let req = request.into_inner();
+        self.check_map_visible(&req.map_id, Some(&user))?;
+
+        let map = self
+            .database
+            .set_map_liked(&req.map_id, &user.user_id, false)
+            .map_err(tonic::Status::from)?;
+
+        Ok(Response::new(UnlikeMapResponse {
+            map: Some(stored_to_map_info(&map)),
+        }))
+    }
+
+    async fn list_map_revisions(
+        &self,
+        request: Request<ListMapRevisionsRequest>,
+    ) -> Result<Response<ListMapRevisionsResponse>, Status> {
+        let viewer = request.extensions().get::<AuthenticatedUser>().cloned();
+        let req = request.into_inner();
+        self.check_map_visible(&req.map_id, viewer.as_ref())?;
+
+        let page_size = if req.page_size == 0 {
+            20
+        } else {
+            req.page_size
+        };
+
+        let (revisions, next_page_token, total_count) = self
+            .database
+            .list_map_revisions(&req.map_id, page_size, &req.page_token)
+            .map_err(tonic::Status::from)?;
+
+        Ok(Response::new(ListMapRevisionsResponse {
+            revisions: revisions.iter().map(stored_to_revision_info).collect(),
+            next_page_token,
+            total_count,
+        }))
+    }
+
+    async fn restore_map_revision(
+        &self,
+        request: Request<RestoreMapRevisionRequest>,
+    ) -> Result<Response<RestoreMapRevisionResponse>, Status> {
+        let user_id = request
+            .extensions()
+            .get::<AuthenticatedUser>()
+            .ok_or_else(|| Status::unauthenticated("Authentication required"))?
+            .user_id
+            .clone();
+
+        let req = request.into_inner();
+
+        let map = self
+            .database
+            .restore_map_revision(&req.map_id, &user_id, req.revision)
+            .map_err(tonic::Status::from)?;
+
+        tracing::info!(
+            map_id = %req.map_id,
+            restored_from = req.revision,
+            revision = map.revision,
+            "Map revision restored"
+        );
+
+        Ok(Response::new(RestoreMapRevisionResponse {
+            map: Some(stored_to_map_detail(&map)),
+        }))
+    }
+
+    async fn fork_map(
+        &self,
+        request: Request<ForkMapRequest>,
+    ) -> Result<Response<ForkMapResponse>, Status> {
+        let user_id = request
+            .extensions()
+            .get::<AuthenticatedUser>()
+            .ok_or_else(|| Status::unauthenticated("Authentication required"))?
+            .user_id
+            .clone();
+
+        let req = request.into_inner();
+
+        let name = if req.name.is_empty() {
+            None
+        } else if req.name.len() > 64 {
+            return Err(Status::invalid_argument("name must be 1-64 characters"));
+        } else {
+            Some(req.name.as_str())
+        };
+        self.check_map_quota(&user_id)?;
+
+        let map = self
+            .database
+            .fork_map(&req.map_id, req.revision, &user_id, name)
+            .map_err(tonic::Status::from)?;
+
+        tracing::info!(
+            map_id = %map.map_id,
+            parent_map_id = %req.map_id,
+            parent_revision = map.parent_revision,
+            creator = %user_id,
+            "Map forked"
+        );
+
+        Ok(Response::new(ForkMapResponse {
+            map: Some(stored_to_map_detail(&map)),
+        }))
+    }
 }
@@ -1,5 +1,10 @@
+pub mod admin_service;
 pub mod avatar_service;
 pub mod jwt;
 pub mod map_service;
+pub mod rate_limit;
+pub mod relay;
 pub mod room_service;
+pub mod signaling;
+pub mod stats_service;
 pub mod user_service;
@@ -0,0 +1,419 @@
+//! Token-bucket rate limiting for the gRPC routes.
+//!
+//! Every call is charged against a per-IP bucket shared by all methods and a
+//! per-method bucket keyed by the authenticated user (or the client IP for
+//! unauthenticated calls such as `Login`). Throttled calls are answered with
+//! `RESOURCE_EXHAUSTED`, a `retry-after` metadata entry in whole seconds, and
+//! a `google.rpc.RetryInfo` detail.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use axum::extract::ConnectInfo;
+use parking_lot::Mutex;
+use serde::Deserialize;
+use tonic_types::{ErrorDetails, StatusExt};
+use tower::{Layer, Service};
+
+use super::jwt::JwtManager;
+
+/// Buckets tracked before idle (full) ones are dropped.
+const MAX_TRACKED_BUCKETS: usize = 10_000;
+
+/// Bucket capacity and refill rate.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Quota {
+    /// Calls allowed back to back.
+    pub burst: u32,
+    /// Tokens added per second.
+    pub per_second: f64,
+}
+
+impl Quota {
+    pub fn per_second(rate: u32, burst: u32) -> Self {
+        Self {
+            burst,
+            per_second: f64::from(rate),
+        }
+    }
+
+    pub fn per_minute(rate: u32) -> Self {
+        Self {
+            burst: rate,
+            per_second: f64::from(rate) / 60.0,
+        }
+    }
+}
+
+/// Rate limiter settings.
+#[derive(Debug, Clone)]
+pub struct RateLimitConfig {
+    /// Quota shared by all calls from one IP address.
+    pub per_ip: Quota,
+    /// Quota for methods without an entry in `methods`.
+    pub default: Quota,
+    /// Per-method quotas keyed by gRPC path, e.g. `/marble.user.UserService/Login`.
+    pub methods: HashMap<String, Quota>,
+    /// Use the first `x-forwarded-for` entry as client IP (only behind a trusted proxy).
+    pub trust_forwarded_for: bool,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        let methods = [
+            ("/marble.user.UserService/Login", Quota::per_minute(5)),
+            (
+                "/marble.user.UserService/GetUsers",
+                Quota::per_second(2, 10),
+            ),
+            ("/marble.room.RoomService/CreateRoom", Quota::per_minute(6)),
+            ("/marble.map.MapService/CreateMap", Quota::per_minute(10)),
+            ("/marble.map.MapService/ForkMap", Quota::per_minute(10)),
+            (
+                "/marble.avatar.AvatarService/SetAvatar",
+                Quota::per_minute(10),
+            ),
+            (
+                "/marble.avatar.AvatarService/GetAvatars",
+                Quota::per_second(2, 10),
+            ),
+        ]
+        .into_iter()
+        .map(|(method, quota)| (method.to_string(), quota))
+        .collect();
+
+        Self {
+            per_ip: Quota::per_second(50, 100),
+            default: Quota::per_second(20, 40),
+            methods,
+            trust_forwarded_for: false,
+        }
+    }
+}
+
+/// Per-user caps on stored resources, checked by the services that create them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UserQuotas {
+    /// Rooms a user may host that have not ended.
+    pub max_open_rooms: usize,
+    /// Maps a user may own.
+    pub max_stored_maps: usize,
+}
+
+impl Default for UserQuotas {
+    fn default() -> Self {
+        Self {
+            max_open_rooms: 3,
+            max_stored_maps: 100,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn full(quota: Quota, now: Instant) -> Self {
+        Self {
+            tokens: f64::from(quota.burst),
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, quota: Quota, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * quota.per_second).min(f64::from(quota.burst));
+        self.updated = now;
+    }
+
+    /// Take one token, or return how long until one is available.
+    fn try_acquire(&mut self, quota: Quota, now: Instant) -> Result<(), Duration> {
+        self.refill(quota, now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else if quota.per_second > 0.0 {
+            Err(
+                Duration::try_from_secs_f64((1.0 - self.tokens) / quota.per_second)
+                    .unwrap_or(Duration::MAX),
+            )
+        } else {
+            Err(Duration::MAX)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Client {
+    User(String),
+    Ip(IpAddr),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Scope {
+    /// All calls from an IP.
+    Ip,
+    /// A method with its own quota.
+    Method(String),
+    /// Methods sharing the default quota.
+    Default,
+}
+
+#[derive(Debug)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<(Client, Scope), (TokenBucket, Quota)>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Charge one call to `method`, returning the wait time if throttled.
+    fn check(
+        &self,
+        ip: Option<IpAddr>,
+        user_id: Option<String>,
+        method: &str,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock();
+
+        if buckets.len() > MAX_TRACKED_BUCKETS {
+            buckets.retain(|_, (bucket, quota)| {
+                bucket.refill(*quota, now);
+                bucket.tokens < f64::from(quota.burst)
+            });
+        }
+
+        let mut acquire = |client: Client, scope: Scope, quota: Quota| {
+            buckets
+                .entry((client, scope))
+                .or_insert_with(|| (TokenBucket::full(quota, now), quota))
+                .0
+                .try_acquire(quota, now)
+        };
+
+        if let Some(ip) = ip {
+            acquire(Client::Ip(ip), Scope::Ip, self.config.per_ip)?;
+        }
+
+        let Some(client) = user_id.map(Client::User).or(ip.map(Client::Ip)) else {
+            return Ok(());
+        };
+        match self.config.methods.get(method) {
+            Some(quota) => acquire(client, Scope::Method(method.to_string()), *quota),
+            None => acquire(client, Scope::Default, self.config.default),
+        }
+    }
+
+    fn client_ip<B>(&self, req: &http::Request<B>) -> Option<IpAddr> {
+        if self.config.trust_forwarded_for
+            && let Some(ip) = req
+                .headers()
+                .get("x-forwarded-for")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.split(',').next())
+                .and_then(|v| v.trim().parse().ok())
+        {
+            return Some(ip);
+        }
+        req.extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|info| info.0.ip())
+    }
+}
+
+/// `RESOURCE_EXHAUSTED` with `retry-after` metadata and `RetryInfo` details.
+pub fn throttled(message: &str, retry_after: Duration) -> tonic::Status {
+    let mut status = tonic::Status::with_error_details(
+        tonic::Code::ResourceExhausted,
+        message,
+        ErrorDetails::with_retry_info(Some(retry_after)),
+    );
+    let seconds = retry_after
+        .as_secs()
+        .saturating_add(u64::from(retry_after.subsec_nanos() > 0));
+    if let Ok(value) = seconds.to_string().parse() {
+        status.metadata_mut().insert("retry-after", value);
+    }
+    status
+}
+
+/// Tower layer applying a [`RateLimiter`] to gRPC requests.
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    limiter: Arc<RateLimiter>,
+    jwt_manager: JwtManager,
+}
+
+impl RateLimitLayer {
+    pub fn new(config: RateLimitConfig, jwt_manager: JwtManager) -> Self {
+        Self {
+            limiter: Arc::new(RateLimiter::new(config)),
+            jwt_manager,
+        }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitService {
+            inner,
+            limiter: Arc::clone(&self.limiter),
+            jwt_manager: self.jwt_manager.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimitService<S> {
+    inner: S,
+    limiter: Arc<RateLimiter>,
+    jwt_manager: JwtManager,
+}
+
+impl<S, B, ResBody> Service<http::Request<B>> for RateLimitService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<ResBody>>,
+    S::Future: Send + 'static,
+    S::Error: Send + 'static,
+    ResBody: Default + Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        // Invalid tokens are rejected later by the interceptor; rate limit them by IP
+        let user_id = req
+            .headers()
+            .get(http::header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .and_then(|token| self.jwt_manager.validate_token(token));
+        let ip = self.limiter.client_ip(&req);
+
+        if let Err(retry_after) = self
+            .limiter
+            .check(ip, user_id, req.uri().path(), Instant::now())
+        {
+            tracing::debug!(method = %req.uri().path(), ?ip, "Rate limited");
+            let response = throttled("Rate limit exceeded", retry_after).into_http();
+            return Box::pin(async move { Ok(response) });
+        }
+
+        Box::pin(self.inner.call(req))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOGIN: &str = "/marble.user.UserService/Login";
+
+    fn ip(last: u8) -> Option<IpAddr> {
+        Some(IpAddr::from([10, 0, 0, last]))
+    }
+
+    #[test]
+    fn test_bucket_refills() {
+        let quota = Quota::per_second(2, 2);
+        let start = Instant::now();
+        let mut bucket = TokenBucket::full(quota, start);
+
+        assert!(bucket.try_acquire(quota, start).is_ok());
+        assert!(bucket.try_acquire(quota, start).is_ok());
+        let wait = bucket.try_acquire(quota, start).unwrap_err();
+        assert_eq!(wait, Duration::from_millis(500));
+
+        assert!(
+            bucket
+                .try_acquire(quota, start + Duration::from_millis(500))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_method_quota_per_ip() {
+        let limiter = RateLimiter::new(RateLimitConfig::default());
+        let now = Instant::now();
+
+        for _ in 0..5 {
+            assert!(limiter.check(ip(1), None, LOGIN, now).is_ok());
+        }
+        assert!(limiter.check(ip(1), None, LOGIN, now).is_err());
+
+        // Other clients and other methods are unaffected
+        assert!(limiter.check(ip(2), None, LOGIN, now).is_ok());
+        assert!(
+            limiter
+                .check(ip(1), None, "/marble.room.RoomService/ListRooms", now)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_users_behind_one_ip_are_separate() {
+        let limiter = RateLimiter::new(RateLimitConfig::default());
+        let now = Instant::now();
+        let create_room = "/marble.room.RoomService/CreateRoom";
+
+        for _ in 0..6 {
+            assert!(
+                limiter
+                    .check(ip(1), Some("alice".into()), create_room, now)
+                    .is_ok()
+            );
+        }
+        assert!(
+            limiter
+                .check(ip(1), Some("alice".into()), create_room, now)
+                .is_err()
+        );
+        assert!(
+            limiter
+                .check(ip(1), Some("bob".into()), create_room, now)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_throttled_status() {
+        let status = throttled("slow down", Duration::from_millis(1500));
+        assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+        assert_eq!(
+            status
+                .metadata()
+                .get("retry-after")
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "2"
+        );
+        assert_eq!(
+            status.get_details_retry_info().unwrap().retry_delay,
+            Some(Duration::from_millis(1500))
+        );
+    }
+}
@@ -0,0 +1,275 @@
+//! Server relay for players whose WebRTC links never come up (symmetric NAT, strict
+//! firewalls).
+//!
+//! `GET /relay/{room_id}?token=<jwt>` upgrades a room member to a WebSocket whose binary
+//! frames are encoded `P2pMessage`s. The server acts as one more gossip node: each frame is
+//! deduplicated by message id, its TTL decremented, and forwarded to every other relay
+//! connection of the room. Relayed players talk only to the relay; bridges connect to it too
+//! (their topology carries `relay_url`), so relayed traffic enters and leaves every mesh
+//! group through them.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, Query, State};
+use axum::response::Response;
+use marble_proto::play::P2pMessage;
+use marble_proto::room::{RoomEvent, room_event};
+use parking_lot::Mutex;
+use prost::Message as _;
+use tokio::sync::{broadcast, mpsc};
+
+use crate::handler::jwt::{JwtManager, SocketQuery, authorize_room_socket};
+use crate::service::database::Database;
+use crate::service::metrics::Metrics;
+
+/// Largest accepted frame; game payloads are far smaller
+const MAX_FRAME_BYTES: usize = 64 * 1024;
+/// Frames queued per connection before further frames to that client are dropped
+const CLIENT_BUFFER: usize = 256;
+/// Message ids remembered per room for deduplication
+const SEEN_CAPACITY: usize = 4096;
+
+/// Relay connections of all rooms (clones share state).
+#[derive(Clone)]
+pub struct RelayHub {
+    database: Database,
+    jwt_manager: JwtManager,
+    metrics: Metrics,
+    rooms: Arc<Mutex<HashMap<uuid::Uuid, RelayRoom>>>,
+}
+
+#[derive(Default)]
+struct RelayRoom {
+    /// Outbound frame queue per connected user, tagged with its connection id
+    clients: HashMap<String, (uuid::Uuid, mpsc::Sender<Vec<u8>>)>,
+    /// Forwarded message ids
+    seen: HashSet<String>,
+    /// `seen` in insertion order, for eviction
+    seen_order: VecDeque<String>,
+}
+
+impl RelayRoom {
+    /// Remember a message id. Returns false if it was forwarded before.
+    fn mark_seen(&mut self, message_id: &str) -> bool {
+        if !self.seen.insert(message_id.to_string()) {
+            return false;
+        }
+        self.seen_order.push_back(message_id.to_string());
+        if self.seen_order.len() > SEEN_CAPACITY
+            && let Some(oldest) = self.seen_order.pop_front()
+        {
+            self.seen.remove(&oldest);
+        }
+        true
+    }
+}
+
+impl RelayHub {
+    pub fn new(database: Database, jwt_manager: JwtManager, metrics: Metrics) -> Self {
+        Self {
+            database,
+            jwt_manager,
+            metrics,
+            rooms: Arc::default(),
+        }
+    }
+
+    /// Add a connection for `user_id`, replacing (and thereby closing) any previous one.
+    fn register(
+        &self,
+        room_id: uuid::Uuid,
+        user_id: &str,
+    ) -> (uuid::Uuid, mpsc::Receiver<Vec<u8>>) {
+        let connection_id = uuid::Uuid::new_v4();
+        let (tx, rx) = mpsc::channel(CLIENT_BUFFER);
+        self.rooms
+            .lock()
+            .entry(room_id)
+            .or_default()
+            .clients
+            .insert(user_id.to_string(), (connection_id, tx));
+        (connection_id, rx)
+    }
+
+    fn unregister(&self, room_id: &uuid::Uuid, user_id: &str, connection_id: uuid::Uuid) {
+        let mut rooms = self.rooms.lock();
+        let Some(room) = rooms.get_mut(room_id) else {
+            return;
+        };
+        if room
+            .clients
+            .get(user_id)
+            .is_some_and(|(id, _)| *id == connection_id)
+        {
+            room.clients.remove(user_id);
+        }
+        if room.clients.is_empty() {
+            rooms.remove(room_id);
+        }
+    }
+
+    /// Relay a frame from `user_id` to the other connections of the room, following the
+    /// gossip rules: drop duplicates, stop at TTL 0, decrement the TTL otherwise.
+    ///
+    /// Returns the number of connections the frame was queued for.
+    fn forward(&self, room_id: &uuid::Uuid, user_id: &str, frame: &[u8]) -> usize {
+        let Ok(mut msg) = P2pMessage::decode(frame) else {
+            return 0;
+        };
+
+        let mut rooms = self.rooms.lock();
+        let Some(room) = rooms.get_mut(room_id) else {
+            return 0;
+        };
+        if !room.mark_seen(&msg.message_id) || msg.ttl == 0 {
+            return 0;
+        }
+        msg.ttl -= 1;
+        let data = msg.encode_to_vec();
+
+        room.clients
+            .iter()
+            .filter(|(uid, _)| uid.as_str() != user_id)
+            // A full queue means a stalled client; it recovers via sync snapshots
+            .filter(|(_, (_, tx))| tx.try_send(data.clone()).is_ok())
+            .count()
+    }
+
+    /// Pump frames between one WebSocket and the room until either side goes away or the
+    /// user leaves the room.
+    async fn run(
+        self,
+        mut socket: WebSocket,
+        room_id: uuid::Uuid,
+        user_id: String,
+        mut events: broadcast::Receiver<RoomEvent>,
+    ) {
+        let (connection_id, mut outbound) = self.register(room_id, &user_id);
+        self.metrics.relay_connected();
+        tracing::info!(room_id = %room_id, user_id = %user_id, "Relay connected");
+
+        loop {
+            tokio::select! {
+                incoming = socket.recv() => match incoming {
+                    Some(Ok(Message::Binary(frame))) => {
+                        let reached = self.forward(&room_id, &user_id, &frame);
+                        self.metrics.relay_frames_forwarded(reached);
+                    }
+                    Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+                    // Pings are answered by axum; text frames are not part of the protocol
+                    Some(Ok(_)) => {}
+                },
+                frame = outbound.recv() => {
+                    // `None`: a newer connection of the same user took over
+                    let Some(frame) = frame else { break };
+                    if socket.send(Message::Binary(frame.into())).await.is_err() {
+                        break;
+                    }
+                }
+                event = events.recv() => match event {
+                    Ok(RoomEvent {
+                        event: Some(room_event::Event::MemberLeft(left)),
+                    }) if left.user_id == user_id => break,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                    _ => {}
+                },
+            }
+        }
+
+        self.unregister(&room_id, &user_id, connection_id);
+        self.metrics.relay_disconnected();
+        tracing::info!(room_id = %room_id, user_id = %user_id, "Relay disconnected");
+    }
+}
+
+/// `GET /relay/{room_id}?token=<jwt>`: authenticate a room member and upgrade to the relay
+pub async fn serve_relay(
+    State(hub): State<RelayHub>,
+    Path(room_id): Path<String>,
+    Query(query): Query<SocketQuery>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    match authorize_room_socket(&hub.jwt_manager, &hub.database, &room_id, &query.token) {
+        Ok((room_id, user_id, events)) => ws
+            .max_message_size(MAX_FRAME_BYTES)
+            .on_upgrade(move |socket| hub.run(socket, room_id, user_id, events)),
+        Err(response) => response,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use marble_proto::play::{Ping, p2p_message::Payload};
+
+    fn hub() -> RelayHub {
+        RelayHub::new(
+            Database::new(),
+            JwtManager::new("relay-test-secret".to_string(), 1),
+            Metrics::new(),
+        )
+    }
+
+    fn frame(message_id: &str, ttl: u32) -> Vec<u8> {
+        P2pMessage {
+            message_id: message_id.to_string(),
+            ttl,
+            origin_group: 0,
+            origin_user: "stranded".to_string(),
+            payload: Some(Payload::Ping(Ping {
+                timestamp: 1.0,
+                to_user: String::new(),
+            })),
+            signature: Vec::new(),
+        }
+        .encode_to_vec()
+    }
+
+    #[test]
+    fn test_forward_dedups_and_decrements_ttl() {
+        let hub = hub();
+        let room_id = uuid::Uuid::new_v4();
+        let (_, mut stranded) = hub.register(room_id, "stranded");
+        let (_, mut bridge) = hub.register(room_id, "bridge");
+
+        assert_eq!(hub.forward(&room_id, "stranded", &frame("m1", 3)), 1);
+        let relayed = P2pMessage::decode(&*bridge.try_recv().unwrap()).unwrap();
+        assert_eq!(relayed.ttl, 2);
+        // The sender does not get its own frame back
+        assert!(stranded.try_recv().is_err());
+
+        // Duplicates, expired TTLs and garbage are dropped
+        assert_eq!(hub.forward(&room_id, "bridge", &frame("m1", 3)), 0);
+        assert_eq!(hub.forward(&room_id, "bridge", &frame("m2", 0)), 0);
+        assert_eq!(hub.forward(&room_id, "bridge", b"not a message"), 0);
+
+        assert_eq!(hub.forward(&room_id, "bridge", &frame("m3", 1)), 1);
+        assert_eq!(
+            P2pMessage::decode(&*stranded.try_recv().unwrap())
+                .unwrap()
+                .ttl,
+            0
+        );
+    }
+
+    #[test]
+    fn test_reconnect_replaces_connection() {
+        let hub = hub();
+        let room_id = uuid::Uuid::new_v4();
+        let (old_id, mut old) = hub.register(room_id, "user");
+        let (new_id, _new) = hub.register(room_id, "user");
+
+        // The old queue closes, and its cleanup leaves the new connection alone
+        assert!(matches!(
+            old.try_recv(),
+            Err(mpsc::error::TryRecvError::Disconnected)
+        ));
+        hub.unregister(&room_id, "user", old_id);
+        assert!(hub.rooms.lock()[&room_id].clients.contains_key("user"));
+
+        hub.unregister(&room_id, "user", new_id);
+        assert!(hub.rooms.lock().is_empty());
+    }
+}
@@ -1,32 +1,61 @@
+use marble_core::MapLimits;
 use marble_proto::room::{
-    self, CreateRoomRequest, CreateRoomResponse, GetRoomRequest, GetRoomResponse,
-    GetRoomTopologyRequest, GetRoomTopologyResponse, GetRoomUsersRequest, GetRoomUsersResponse,
-    GetTopologyRequest, GetTopologyResponse, JoinRoomRequest, JoinRoomResponse, KickPlayerRequest,
-    KickPlayerResponse, ListRoomsRequest, ListRoomsResponse, RegisterPeerIdRequest,
+    self, ChangeRoleRequest, ChangeRoleResponse, CreateInviteRequest, CreateInviteResponse,
+    CreateRoomRequest, CreateRoomResponse, GetRoomRequest, GetRoomResponse, GetRoomTopologyRequest,
+    GetRoomTopologyResponse, GetRoomUsersRequest, GetRoomUsersResponse, GetTopologyRequest,
+    GetTopologyResponse, JoinRoomByCodeRequest, JoinRoomRequest, JoinRoomResponse,
+    KickPlayerRequest, KickPlayerResponse, ListInvitesRequest, ListInvitesResponse,
+    ListRoomsRequest, ListRoomsResponse, PeerTopology, RegisterPeerIdRequest,
     RegisterPeerIdResponse, ReportArrivalRequest, ReportArrivalResponse, ReportConnectionRequest,
-    ReportConnectionResponse, ResolvePeerIdsRequest, ResolvePeerIdsResponse, RoomRole, RoomState,
-    RoomSummary, StartGameRequest, StartGameResponse,
+    ReportConnectionResponse, ResetRoomRequest, ResetRoomResponse, ResolvePeerIdsRequest,
+    ResolvePeerIdsResponse, RevokeInviteRequest, RevokeInviteResponse, RoomEvent, RoomRole,
+    RoomState, RoomSummary, SetReadyRequest, SetReadyResponse, StartGameRequest, StartGameResponse,
+    UnbanPlayerRequest, UnbanPlayerResponse, UpdateRoomRequest, UpdateRoomResponse,
+    WatchRoomRequest, room_event,
 };
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
 use tonic::{Request, Response, Status};
 
 use crate::{
-    common::room::Room,
+    common::{
+        invite::{INVITE_CODE_LEN, InviteToken, JoinCredentials, normalize_invite_code},
+        room::{Room, RoomUpdate},
+    },
+    config::{ServerConfig, TopologySettings},
     service::database::{Database, DatabaseError},
+    topology::topology_delta,
     util::{self, required_str},
 };
 
 use super::jwt::AuthenticatedUser;
+use super::rate_limit::UserQuotas;
+
+/// Per-watcher buffer between the room broadcast and the gRPC stream
+const WATCH_ROOM_BUFFER: usize = 32;
+/// Upper bounds for `UpdateRoom` network overrides
+const MAX_GOSSIP_TTL: u32 = 32;
+const MAX_LOCKSTEP_DELAY_FRAMES: u32 = 60;
 
 pub struct RoomServiceImpl {
     database: Database,
     signaling_base_url: String,
+    quotas: UserQuotas,
+    topology: TopologySettings,
+    /// Range `max_players` is clamped to
+    player_range: (u32, u32),
+    map_limits: MapLimits,
 }
 
 impl RoomServiceImpl {
-    pub fn new(database: Database, signaling_base_url: String) -> Self {
+    pub fn new(database: Database, config: &ServerConfig) -> Self {
         Self {
             database,
-            signaling_base_url,
+            signaling_base_url: config.signaling_url(),
+            quotas: config.limits.quotas(),
+            topology: config.topology.clone(),
+            player_range: (config.limits.min_players, config.limits.max_players),
+            map_limits: config.limits.map.clone(),
         }
     }
 
@@ -36,10 +65,130 @@ impl RoomServiceImpl {
             .map(|u| u.user_id.clone())
             .ok_or_else(|| Status::unauthenticated("Authentication required"))
     }
+
+    /// Current revision of `map_id`; empty means the built-in default map (revision 0).
+    fn resolve_map_revision(&self, map_id: &str) -> Result<u32, Status> {
+        if map_id.is_empty() {
+            return Ok(0);
+        }
+        self.database
+            .get_map(map_id)
+            .filter(|m| !m.hidden)
+            .map(|m| m.revision)
+            .ok_or_else(|| {
+                util::invalid_fields(
+                    "map does not exist",
+                    [("map_id", format!("unknown map '{map_id}'"))],
+                )
+            })
+    }
+
+    /// Problems with `UpdateRoom` fields, as `(field, description)`.
+    fn update_room_violations(&self, req: &UpdateRoomRequest) -> Vec<(&'static str, String)> {
+        let mut violations = Vec::new();
+        let (min_players, max_players) = self.player_range;
+        if let Some(players) = req.max_players
+            && !(min_players..=max_players).contains(&players)
+        {
+            violations.push((
+                "max_players",
+                format!("must be {min_players}-{max_players}"),
+            ));
+        }
+        if req.room_name.as_deref().is_some_and(str::is_empty) {
+            violations.push(("room_name", "must not be empty".to_string()));
+        }
+        // The quorum is checked against the room's own max_players by `Room::update`
+        if let Some(network) = &req.network_config {
+            let max_group = self.topology.max_mesh_group_size;
+            if network.mesh_group_size == 1 || network.mesh_group_size > max_group {
+                violations.push((
+                    "network_config.mesh_group_size",
+                    format!("must be 2-{max_group}"),
+                ));
+            }
+            if network.peer_connections > max_group {
+                violations.push((
+                    "network_config.peer_connections",
+                    format!("must be at most {max_group}"),
+                ));
+            }
+            if network.gossip_ttl > MAX_GOSSIP_TTL {
+                violations.push((
+                    "network_config.gossip_ttl",
+                    format!("must be at most {MAX_GOSSIP_TTL}"),
+                ));
+            }
+            if network.lockstep_delay_frames > MAX_LOCKSTEP_DELAY_FRAMES {
+                violations.push((
+                    "network_config.lockstep_delay_frames",
+                    format!("must be at most {MAX_LOCKSTEP_DELAY_FRAMES}"),
+                ));
+            }
+        }
+        violations
+    }
+
+    /// Forward room events to one watcher until it disconnects, is kicked, or the room goes away.
+    ///
+    /// `TopologyChanged` gets the watcher's topology and its delta against the last topology
+    /// the watcher was sent.
+    async fn forward_room_events(
+        database: Database,
+        room_id: uuid::Uuid,
+        user_id: String,
+        mut topology: Option<PeerTopology>,
+        mut events: broadcast::Receiver<RoomEvent>,
+        tx: mpsc::Sender<Result<RoomEvent, Status>>,
+    ) {
+        loop {
+            let mut event = match events.recv().await {
+                Ok(event) => event,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::debug!(
+                        room_id = %room_id,
+                        user_id = %user_id,
+                        skipped,
+                        "Watcher lagged, resending snapshot"
+                    );
+                    match database.watch_room(&room_id, &user_id) {
+                        Ok((snapshot, receiver)) => {
+                            events = receiver;
+                            topology = snapshot_topology(&snapshot);
+                            snapshot
+                        }
+                        Err(_) => break,
+                    }
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+
+            let mut watcher_kicked = false;
+            match &mut event.event {
+                Some(room_event::Event::TopologyChanged(changed)) => {
+                    changed.topology = database.get_topology(&room_id, &user_id).ok();
+                    if let Some(current) = &changed.topology {
+                        changed.delta = Some(topology_delta(topology.as_ref(), current));
+                        topology = Some(current.clone());
+                    }
+                }
+                Some(room_event::Event::MemberLeft(left)) => {
+                    watcher_kicked = left.user_id == user_id;
+                }
+                _ => {}
+            }
+
+            if tx.send(Ok(event)).await.is_err() || watcher_kicked {
+                break;
+            }
+        }
+    }
 }
 
 #[tonic::async_trait]
 impl marble_proto::room::room_service_server::RoomService for RoomServiceImpl {
+    type WatchRoomStream = ReceiverStream<Result<RoomEvent, Status>>;
+
     async fn create_room(
         &self,
         request: Request<CreateRoomRequest>,
@@ -47,7 +196,16 @@ impl marble_proto::room::room_service_server::RoomService for RoomServiceImpl {
         let user_id = Self::get_user_id(request.extensions())?;
         let req = request.into_inner();
 
-        let max_players = util::clamp(req.max_players, 2, 32);
+        if self.database.count_open_rooms_hosted_by(&user_id) >= self.quotas.max_open_rooms {
+            return Err(Status::resource_exhausted(format!(
+                "at most {} open rooms per host",
+                self.quotas.max_open_rooms
+            )));
+        }
+
+        let (min_players, max_players) = self.player_range;
+        // The room's own limit, which the ready check quorum is validated against
+        let max_players = util::clamp(req.max_players, min_players, max_players);
         let room_id = uuid::Uuid::new_v4();
         let room_name = if req.room_name.is_empty() {
             format!("Room-{}", &room_id.to_string()[..8])
@@ -55,7 +213,27 @@ impl marble_proto::room::room_service_server::RoomService for RoomServiceImpl {
             req.room_name
         };
 
-        let room = Room::new(
+        if !req.password.is_empty() && !(4..=64).contains(&req.password.chars().count()) {
+            return Err(util::invalid_fields(
+                "invalid room password",
+                [("password", "must be 4-64 characters")],
+            ));
+        }
+
+        if req
+            .ready_check
+            .is_some_and(|check| check.quorum > max_players)
+        {
+            return Err(util::invalid_fields(
+                "invalid ready check",
+                [("ready_check.quorum", "must not exceed max_players")],
+            ));
+        }
+
+        // Pin the current revision so every peer loads the same map data
+        let map_revision = self.resolve_map_revision(&req.map_id)?;
+
+        let mut room = Room::new(
             room_id,
             room_name,
             req.map_id,
@@ -63,11 +241,15 @@ impl marble_proto::room::room_service_server::RoomService for RoomServiceImpl {
             req.is_public,
             user_id.clone(),
             self.signaling_base_url.clone(),
+            self.topology.for_room(max_players),
         );
+        room.pin_map_revision(map_revision);
+        room.set_password(Some(req.password).filter(|p| !p.is_empty()));
+        room.set_ready_check(req.ready_check.unwrap_or_default());
+        room.set_map_limits(self.map_limits.clone());
 
-        let topology = room
-            .get_topology(&user_id)
-            .unwrap_or_default();
+        let topology = room.get_topology(&user_id).unwrap_or_default();
+        let signing_key = room.signing_key(&user_id).unwrap_or_default();
 
         tracing::info!(room_id = %room.id(), host = %user_id, "Room created");
 
@@ -77,6 +259,7 @@ impl marble_proto::room::room_service_server::RoomService for RoomServiceImpl {
         Ok(Response::new(CreateRoomResponse {
             room: Some(room_info),
             topology: Some(topology),
+            signing_key,
         }))
     }
 
@@ -84,6 +267,7 @@ impl marble_proto::room::room_service_server::RoomService for RoomServiceImpl {
         &self,
         request: Request<GetRoomRequest>,
     ) -> Result<Response<GetRoomResponse>, Status> {
+        let viewer = Self::get_user_id(request.extensions()).ok();
         let req = request.into_inner();
         let room_id = util::tonic_uuid!(&req.room_id)?;
 
@@ -92,9 +276,15 @@ impl marble_proto::room::room_service_server::RoomService for RoomServiceImpl {
             .get_room(&room_id)
             .ok_or(DatabaseError::RoomNotFound)?;
 
-        Ok(Response::new(GetRoomResponse {
-            room: Some(room.to_room_info()),
-        }))
+        let mut info = room.to_room_info();
+        // The invite code admits to private rooms, so only members may pass it on;
+        // the ban list is likewise members-only
+        if !viewer.is_some_and(|v| room.has_member(&v)) {
+            info.invite_code.clear();
+            info.banned_user_ids.clear();
+        }
+
+        Ok(Response::new(GetRoomResponse { room: Some(info) }))
     }
 
     async fn list_rooms(
@@ -129,6 +319,7 @@ impl marble_proto::room::room_service_server::RoomService for RoomServiceImpl {
             map_id,
             name_query,
             req.has_available_slots,
+            false,
         );
 
         let summaries: Vec<RoomSummary> = rooms.iter().map(Room::to_room_summary).collect();
@@ -152,15 +343,104 @@ impl marble_proto::room::room_service_server::RoomService for RoomServiceImpl {
 
         tracing::info!(room_id = %room_id, user_id = %user_id, "User trying to join room");
 
-        match self.database.join_room(&room_id, user_id, role) {
+        let credentials = JoinCredentials {
+            password: Some(req.password.as_str()).filter(|p| !p.is_empty()),
+            via_invite_code: false,
+            invite_token: Some(req.invite_token.as_str()).filter(|t| !t.is_empty()),
+        };
+        match self
+            .database
+            .join_room(&room_id, user_id.clone(), role, credentials)
+        {
             Ok((room, topology)) => Ok(Response::new(JoinRoomResponse {
                 room: Some(room.to_room_info()),
                 topology: Some(topology),
+                signing_key: room.signing_key(&user_id).unwrap_or_default(),
             })),
             Err(err) => Err(err.into()),
         }
     }
 
+    async fn join_room_by_code(
+        &self,
+        request: Request<JoinRoomByCodeRequest>,
+    ) -> Result<Response<JoinRoomResponse>, Status> {
+        let user_id = Self::get_user_id(request.extensions())?;
+        let req = request.into_inner();
+
+        let code = normalize_invite_code(&req.invite_code);
+        if code.len() != INVITE_CODE_LEN {
+            return Err(util::invalid_fields(
+                "invalid invite code",
+                [(
+                    "invite_code",
+                    format!("must be {INVITE_CODE_LEN} characters"),
+                )],
+            ));
+        }
+        let room_id = self
+            .database
+            .find_room_by_invite_code(&code)
+            .ok_or_else(|| Status::not_found("No room with this invite code"))?;
+
+        let role = req.role.and_then(|r| RoomRole::try_from(r).ok());
+
+        tracing::info!(room_id = %room_id, user_id = %user_id, "User joining room by invite code");
+
+        let credentials = JoinCredentials {
+            password: Some(req.password.as_str()).filter(|p| !p.is_empty()),
+            via_invite_code: true,
+            invite_token: None,
+        };
+        let (room, topology) =
+            self.database
+                .join_room(&room_id, user_id.clone(), role, credentials)?;
+
+        Ok(Response::new(JoinRoomResponse {
+            room: Some(room.to_room_info()),
+            topology: Some(topology),
+            signing_key: room.signing_key(&user_id).unwrap_or_default(),
+        }))
+    }
+
+    async fn update_room(
+        &self,
+        request: Request<UpdateRoomRequest>,
+    ) -> Result<Response<UpdateRoomResponse>, Status> {
+        let user_id = Self::get_user_id(request.extensions())?;
+        let req = request.into_inner();
+        let room_id = util::tonic_uuid!(&req.room_id)?;
+
+        let violations = self.update_room_violations(&req);
+        if !violations.is_empty() {
+            return Err(util::invalid_fields("invalid room settings", violations));
+        }
+
+        // Pin the current revision so every peer reloads the same map data
+        let map = match req.map_id {
+            Some(map_id) => {
+                let revision = self.resolve_map_revision(&map_id)?;
+                Some((map_id, revision))
+            }
+            None => None,
+        };
+        let update = RoomUpdate {
+            map,
+            name: req.room_name,
+            max_players: req.max_players,
+            is_public: req.is_public,
+            network_config: req.network_config,
+            ready_check: req.ready_check,
+        };
+        let room = self.database.update_room(&room_id, &user_id, update)?;
+
+        tracing::info!(room_id = %room_id, host = %user_id, "Room settings updated");
+
+        Ok(Response::new(UpdateRoomResponse {
+            room: Some(room.to_room_info()),
+        }))
+    }
+
     async fn get_room_users(
         &self,
         request: Request<GetRoomUsersRequest>,
@@ -188,12 +468,13 @@ impl marble_proto::room::room_service_server::RoomService for RoomServiceImpl {
 
         let room = self
             .database
-            .kick_user(&room_id, &user_id, &req.target_user_id)?;
+            .kick_user(&room_id, &user_id, &req.target_user_id, req.ban)?;
 
         tracing::info!(
             room_id = %room_id,
             host = %user_id,
             target = %req.target_user_id,
+            ban = req.ban,
             "Player kicked from room"
         );
 
@@ -202,6 +483,120 @@ impl marble_proto::room::room_service_server::RoomService for RoomServiceImpl {
         }))
     }
 
+    async fn unban_player(
+        &self,
+        request: Request<UnbanPlayerRequest>,
+    ) -> Result<Response<UnbanPlayerResponse>, Status> {
+        let user_id = Self::get_user_id(request.extensions())?;
+        let req = request.into_inner();
+        let room_id = util::tonic_uuid!(&req.room_id)?;
+
+        let room = self
+            .database
+            .unban_user(&room_id, &user_id, &req.target_user_id)?;
+
+        tracing::info!(
+            room_id = %room_id,
+            host = %user_id,
+            target = %req.target_user_id,
+            "Player unbanned from room"
+        );
+
+        Ok(Response::new(UnbanPlayerResponse {
+            room: Some(room.to_room_info()),
+        }))
+    }
+
+    async fn change_role(
+        &self,
+        request: Request<ChangeRoleRequest>,
+    ) -> Result<Response<ChangeRoleResponse>, Status> {
+        let user_id = Self::get_user_id(request.extensions())?;
+        let req = request.into_inner();
+        let room_id = util::tonic_uuid!(&req.room_id)?;
+
+        let role = RoomRole::try_from(req.role).unwrap_or(RoomRole::Unspecified);
+        let (room, topology) = self.database.change_role(&room_id, &user_id, role)?;
+
+        tracing::info!(room_id = %room_id, user_id = %user_id, role = ?role, "Member changed role");
+
+        Ok(Response::new(ChangeRoleResponse {
+            room: Some(room.to_room_info()),
+            topology: Some(topology),
+        }))
+    }
+
+    async fn set_ready(
+        &self,
+        request: Request<SetReadyRequest>,
+    ) -> Result<Response<SetReadyResponse>, Status> {
+        let user_id = Self::get_user_id(request.extensions())?;
+        let req = request.into_inner();
+        let room_id = util::tonic_uuid!(&req.room_id)?;
+
+        let readiness = req.readiness.unwrap_or_default();
+        let user = self.database.set_readiness(&room_id, &user_id, readiness)?;
+
+        tracing::debug!(room_id = %room_id, user_id = %user_id, ?readiness, "Member readiness set");
+
+        Ok(Response::new(SetReadyResponse { user: Some(user) }))
+    }
+
+    async fn create_invite(
+        &self,
+        request: Request<CreateInviteRequest>,
+    ) -> Result<Response<CreateInviteResponse>, Status> {
+        let user_id = Self::get_user_id(request.extensions())?;
+        let req = request.into_inner();
+        let room_id = util::tonic_uuid!(&req.room_id)?;
+
+        let ttl =
+            (req.ttl_seconds > 0).then(|| chrono::Duration::seconds(i64::from(req.ttl_seconds)));
+        let invite = self
+            .database
+            .create_invite(&room_id, &user_id, ttl, req.max_uses)?;
+
+        tracing::info!(room_id = %room_id, host = %user_id, "Invite token created");
+
+        Ok(Response::new(CreateInviteResponse {
+            invite: Some(invite.to_proto()),
+        }))
+    }
+
+    async fn list_invites(
+        &self,
+        request: Request<ListInvitesRequest>,
+    ) -> Result<Response<ListInvitesResponse>, Status> {
+        let user_id = Self::get_user_id(request.extensions())?;
+        let req = request.into_inner();
+        let room_id = util::tonic_uuid!(&req.room_id)?;
+
+        let invites = self.database.list_invites(&room_id, &user_id)?;
+
+        Ok(Response::new(ListInvitesResponse {
+            invites: invites.iter().map(InviteToken::to_proto).collect(),
+        }))
+    }
+
+    async fn revoke_invite(
+        &self,
+        request: Request<RevokeInviteRequest>,
+    ) -> Result<Response<RevokeInviteResponse>, Status> {
+        let user_id = Self::get_user_id(request.extensions())?;
+        let req = request.into_inner();
+        let room_id = util::tonic_uuid!(&req.room_id)?;
+
+        let invite = self
+            .database
+            .revoke_invite(&room_id, &user_id, &req.token)?;
+
+        tracing::info!(room_id = %room_id, host = %user_id, "Invite token revoked");
+
+        Ok(Response::new(RevokeInviteResponse {
+            invite: Some(invite.to_proto()),
+        }))
+    }
+
     async fn start_game(
         &self,
         request: Request<StartGameRequest>,
@@ -212,12 +607,13 @@ impl marble_proto::room::room_service_server::RoomService for RoomServiceImpl {
 
         let (newly_started, room) =
             self.database
-                .start_game(&room_id, &user_id, req.start_frame)?;
+                .start_game(&room_id, &user_id, req.start_frame, req.force)?;
 
         if newly_started {
             tracing::info!(
                 room_id = %room_id,
                 start_frame = req.start_frame,
+                forced = req.force,
                 "Game started (marbles spawned)"
             );
         } else {
@@ -259,6 +655,41 @@ impl marble_proto::room::room_service_server::RoomService for RoomServiceImpl {
         }))
     }
 
+    async fn reset_room(
+        &self,
+        request: Request<ResetRoomRequest>,
+    ) -> Result<Response<ResetRoomResponse>, Status> {
+        let user_id = Self::get_user_id(request.extensions())?;
+        let req = request.into_inner();
+        let room_id = util::tonic_uuid!(&req.room_id)?;
+
+        // A kept map is resolved again: it may have been deleted or hidden since it was
+        // pinned, and the next round must not point at it
+        let map_id = if req.map_id.is_empty() {
+            self.database
+                .get_room(&room_id)
+                .ok_or(DatabaseError::RoomNotFound)?
+                .map_id()
+                .to_string()
+        } else {
+            req.map_id
+        };
+        let revision = self.resolve_map_revision(&map_id)?;
+        let map = Some((map_id, revision));
+
+        let room = self.database.reset_room(&room_id, &user_id, map)?;
+
+        tracing::info!(
+            room_id = %room_id,
+            round = room.round(),
+            "Room reset for next round"
+        );
+
+        Ok(Response::new(ResetRoomResponse {
+            room: Some(room.to_room_info()),
+        }))
+    }
+
     async fn register_peer_id(
         &self,
         request: Request<RegisterPeerIdRequest>,
@@ -326,7 +757,7 @@ impl marble_proto::room::room_service_server::RoomService for RoomServiceImpl {
         let req = request.into_inner();
         let room_id = util::tonic_uuid!(&req.room_id)?;
 
-        let topologies = self.database.get_room_topology(&room_id, &user_id)?;
+        let (topologies, group_latencies) = self.database.get_room_topology(&room_id, &user_id)?;
 
         let players = topologies
             .into_iter()
@@ -337,7 +768,10 @@ impl marble_proto::room::room_service_server::RoomService for RoomServiceImpl {
             })
             .collect();
 
-        Ok(Response::new(GetRoomTopologyResponse { players }))
+        Ok(Response::new(GetRoomTopologyResponse {
+            players,
+            group_latencies,
+        }))
     }
 
     async fn resolve_peer_ids(
@@ -354,4 +788,41 @@ impl marble_proto::room::room_service_server::RoomService for RoomServiceImpl {
 
         Ok(Response::new(ResolvePeerIdsResponse { peer_to_user }))
     }
+
+    async fn watch_room(
+        &self,
+        request: Request<WatchRoomRequest>,
+    ) -> Result<Response<Self::WatchRoomStream>, Status> {
+        let user_id = Self::get_user_id(request.extensions())?;
+        let req = request.into_inner();
+        let room_id = util::tonic_uuid!(&req.room_id)?;
+
+        let (snapshot, events) = self.database.watch_room(&room_id, &user_id)?;
+        let topology = snapshot_topology(&snapshot);
+
+        let (tx, rx) = mpsc::channel(WATCH_ROOM_BUFFER);
+        tx.send(Ok(snapshot))
+            .await
+            .map_err(|_| Status::internal("Failed to start room watch"))?;
+
+        tracing::debug!(room_id = %room_id, user_id = %user_id, "Watching room");
+        tokio::spawn(Self::forward_room_events(
+            self.database.clone(),
+            room_id,
+            user_id,
+            topology,
+            events,
+            tx,
+        ));
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+}
+
+/// The watcher's own topology in a snapshot event
+fn snapshot_topology(event: &RoomEvent) -> Option<PeerTopology> {
+    match &event.event {
+        Some(room_event::Event::Snapshot(snapshot)) => snapshot.topology.clone(),
+        _ => None,
+    }
 }
@@ -0,0 +1,470 @@
+//! Room-scoped WebRTC signaling, speaking the matchbox JSON protocol.
+//!
+//! `GET /signaling/{room_id}?token=<jwt>` upgrades a room member to a matchbox signaling
+//! socket. Unlike matchbox's full-mesh server, peers are only introduced to the `connect_to`
+//! and `bridge_peers` the `TopologyManager` assigned them, signals are only relayed between
+//! introduced peers, and the socket is closed when its user leaves or is kicked from the
+//! room (its peers get `PeerLeft` and drop the WebRTC links).
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, Query, State};
+use axum::response::Response;
+use marble_proto::room::{RoomEvent, room_event};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, mpsc};
+
+use crate::handler::jwt::{JwtManager, SocketQuery, authorize_room_socket};
+use crate::service::database::Database;
+use crate::service::metrics::Metrics;
+
+/// Largest accepted signaling message (SDP offers with many candidates stay well below)
+const MAX_MESSAGE_BYTES: usize = 64 * 1024;
+
+/// Server-to-client messages (`matchbox_protocol::PeerEvent`)
+#[derive(Debug, Serialize)]
+enum PeerEvent {
+    IdAssigned(String),
+    NewPeer(String),
+    PeerLeft(String),
+    Signal {
+        sender: String,
+        data: serde_json::Value,
+    },
+}
+
+/// Client-to-server messages (`matchbox_protocol::PeerRequest`)
+#[derive(Debug, Deserialize)]
+enum PeerRequest {
+    Signal {
+        receiver: String,
+        data: serde_json::Value,
+    },
+    KeepAlive,
+}
+
+type PeerId = uuid::Uuid;
+
+/// Signaling sockets of all rooms (clones share state).
+#[derive(Clone)]
+pub struct SignalingHub {
+    database: Database,
+    jwt_manager: JwtManager,
+    metrics: Metrics,
+    rooms: Arc<Mutex<HashMap<uuid::Uuid, SignalingRoom>>>,
+}
+
+#[derive(Default)]
+struct SignalingRoom {
+    peers: HashMap<PeerId, SignalingPeer>,
+    /// Introduced peer pairs, stored as (older, newer)
+    links: HashSet<(PeerId, PeerId)>,
+    /// Join counter; the older peer of a link is told about the newer one and initiates
+    next_join: u64,
+}
+
+struct SignalingPeer {
+    user_id: String,
+    joined: u64,
+    /// Users this peer's topology asks for
+    wanted: HashSet<String>,
+    sender: mpsc::UnboundedSender<String>,
+}
+
+impl SignalingRoom {
+    fn send(&self, peer_id: &PeerId, event: &PeerEvent) {
+        if let Some(peer) = self.peers.get(peer_id)
+            && let Ok(text) = serde_json::to_string(event)
+        {
+            // A closed queue means the socket is already shutting down
+            let _ = peer.sender.send(text);
+        }
+    }
+
+    fn link(&self, a: PeerId, b: PeerId) -> Option<(PeerId, PeerId)> {
+        let (pa, pb) = (self.peers.get(&a)?, self.peers.get(&b)?);
+        Some(if pa.joined < pb.joined {
+            (a, b)
+        } else {
+            (b, a)
+        })
+    }
+
+    fn is_linked(&self, a: PeerId, b: PeerId) -> bool {
+        self.link(a, b)
+            .is_some_and(|link| self.links.contains(&link))
+    }
+
+    /// Drop every link of `peer_id` and tell the other side.
+    fn unlink_all(&mut self, peer_id: PeerId) {
+        let dropped: Vec<_> = self
+            .links
+            .iter()
+            .filter(|(a, b)| *a == peer_id || *b == peer_id)
+            .copied()
+            .collect();
+        for (a, b) in dropped {
+            self.links.remove(&(a, b));
+            let other = if a == peer_id { b } else { a };
+            self.send(&other, &PeerEvent::PeerLeft(peer_id.to_string()));
+        }
+    }
+}
+
+impl SignalingHub {
+    pub fn new(database: Database, jwt_manager: JwtManager, metrics: Metrics) -> Self {
+        Self {
+            database,
+            jwt_manager,
+            metrics,
+            rooms: Arc::default(),
+        }
+    }
+
+    /// Add a socket for `user_id` and queue its `IdAssigned`. An older socket of the same
+    /// user is dropped (its queue closes, and its links are torn down).
+    fn register(
+        &self,
+        room_id: uuid::Uuid,
+        user_id: &str,
+    ) -> (PeerId, mpsc::UnboundedReceiver<String>) {
+        let peer_id = uuid::Uuid::new_v4();
+        let (sender, receiver) = mpsc::unbounded_channel();
+
+        let mut rooms = self.rooms.lock();
+        let room = rooms.entry(room_id).or_default();
+        let previous: Vec<_> = room
+            .peers
+            .iter()
+            .filter(|(_, peer)| peer.user_id == user_id)
+            .map(|(id, _)| *id)
+            .collect();
+        for old in previous {
+            room.unlink_all(old);
+            room.peers.remove(&old);
+        }
+
+        room.next_join += 1;
+        room.peers.insert(
+            peer_id,
+            SignalingPeer {
+                user_id: user_id.to_string(),
+                joined: room.next_join,
+                wanted: HashSet::new(),
+                sender,
+            },
+        );
+        room.send(&peer_id, &PeerEvent::IdAssigned(peer_id.to_string()));
+        (peer_id, receiver)
+    }
+
+    fn unregister(&self, room_id: &uuid::Uuid, peer_id: PeerId) {
+        let mut rooms = self.rooms.lock();
+        let Some(room) = rooms.get_mut(room_id) else {
+            return;
+        };
+        // Already gone if a newer socket of the same user replaced it
+        if room.peers.contains_key(&peer_id) {
+            room.unlink_all(peer_id);
+            room.peers.remove(&peer_id);
+        }
+        if room.peers.is_empty() {
+            rooms.remove(room_id);
+        }
+    }
+
+    /// Set the users `peer_id` should be connected to, introducing new pairs and dropping
+    /// pairs neither side wants anymore.
+    fn introduce(&self, room_id: &uuid::Uuid, peer_id: PeerId, wanted: HashSet<String>) {
+        let mut rooms = self.rooms.lock();
+        let Some(room) = rooms.get_mut(room_id) else {
+            return;
+        };
+        let Some(peer) = room.peers.get_mut(&peer_id) else {
+            return;
+        };
+        peer.wanted = wanted;
+        let user_id = peer.user_id.clone();
+
+        let others: Vec<_> = room
+            .peers
+            .iter()
+            .filter(|(id, _)| **id != peer_id)
+            .map(|(id, other)| {
+                let wants = room.peers[&peer_id].wanted.contains(&other.user_id)
+                    || other.wanted.contains(&user_id);
+                (*id, wants)
+            })
+            .collect();
+
+        for (other, wants) in others {
+            let Some(link) = room.link(peer_id, other) else {
+                continue;
+            };
+            match (wants, room.links.contains(&link)) {
+                (true, false) => {
+                    room.links.insert(link);
+                    room.send(&link.0, &PeerEvent::NewPeer(link.1.to_string()));
+                }
+                (false, true) => {
+                    room.links.remove(&link);
+                    room.send(&link.0, &PeerEvent::PeerLeft(link.1.to_string()));
+                    room.send(&link.1, &PeerEvent::PeerLeft(link.0.to_string()));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Introduce `peer_id` to the peers of its current topology.
+    fn introduce_assigned(&self, room_id: &uuid::Uuid, peer_id: PeerId, user_id: &str) {
+        let Ok(topology) = self.database.get_topology(room_id, user_id) else {
+            return;
+        };
+        let wanted = topology
+            .connect_to
+            .iter()
+            .chain(&topology.bridge_peers)
+            .map(|peer| peer.user_id.clone())
+            .collect();
+        self.introduce(room_id, peer_id, wanted);
+    }
+
+    /// Relay a signal to `receiver` if the two peers were introduced.
+    fn forward_signal(
+        &self,
+        room_id: &uuid::Uuid,
+        sender: PeerId,
+        receiver: &str,
+        data: serde_json::Value,
+    ) -> bool {
+        let Ok(receiver) = uuid::Uuid::parse_str(receiver) else {
+            return false;
+        };
+        let rooms = self.rooms.lock();
+        let Some(room) = rooms.get(room_id) else {
+            return false;
+        };
+        if !room.is_linked(sender, receiver) {
+            return false;
+        }
+        room.send(
+            &receiver,
+            &PeerEvent::Signal {
+                sender: sender.to_string(),
+                data,
+            },
+        );
+        true
+    }
+
+    /// Serve one signaling socket until it closes, a newer socket of the same user takes
+    /// over, or the user leaves the room.
+    async fn run(
+        self,
+        mut socket: WebSocket,
+        room_id: uuid::Uuid,
+        user_id: String,
+        mut events: broadcast::Receiver<RoomEvent>,
+    ) {
+        let (peer_id, mut outbound) = self.register(room_id, &user_id);
+        self.metrics.signaling_connected();
+        tracing::info!(
+            room_id = %room_id,
+            user_id = %user_id,
+            peer_id = %peer_id,
+            "Peer connected"
+        );
+
+        // The server assigned the id, so it can map it to the user right away. The
+        // resulting TopologyChanged makes the other sockets introduce themselves.
+        let _ = self
+            .database
+            .register_peer_id(&room_id, &user_id, &peer_id.to_string());
+        self.introduce_assigned(&room_id, peer_id, &user_id);
+
+        loop {
+            tokio::select! {
+                incoming = socket.recv() => match incoming {
+                    Some(Ok(Message::Text(text))) => {
+                        match serde_json::from_str::<PeerRequest>(text.as_str()) {
+                            Ok(PeerRequest::Signal { receiver, data }) => {
+                                if !self.forward_signal(&room_id, peer_id, &receiver, data) {
+                                    tracing::debug!(
+                                        peer_id = %peer_id,
+                                        receiver = %receiver,
+                                        "Dropped signal to unassigned peer"
+                                    );
+                                }
+                            }
+                            Ok(PeerRequest::KeepAlive) => {}
+                            Err(err) => {
+                                tracing::debug!(peer_id = %peer_id, "Bad signaling request: {err}");
+                            }
+                        }
+                    }
+                    Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+                    Some(Ok(_)) => {}
+                },
+                text = outbound.recv() => {
+                    // `None`: a newer socket of the same user took over
+                    let Some(text) = text else { break };
+                    if socket.send(Message::Text(text.into())).await.is_err() {
+                        break;
+                    }
+                }
+                event = events.recv() => match event {
+                    Ok(RoomEvent {
+                        event: Some(room_event::Event::MemberLeft(left)),
+                    }) if left.user_id == user_id => break,
+                    Ok(RoomEvent {
+                        event: Some(room_event::Event::TopologyChanged(_)),
+                    })
+                    | Err(broadcast::error::RecvError::Lagged(_)) => {
+                        self.introduce_assigned(&room_id, peer_id, &user_id);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                    _ => {}
+                },
+            }
+        }
+
+        self.unregister(&room_id, peer_id);
+        self.metrics.signaling_disconnected();
+        tracing::info!(
+            room_id = %room_id,
+            user_id = %user_id,
+            peer_id = %peer_id,
+            "Peer disconnected"
+        );
+    }
+}
+
+/// `GET /signaling/{room_id}?token=<jwt>`: authenticate a room member and upgrade to signaling
+pub async fn serve_signaling(
+    State(hub): State<SignalingHub>,
+    Path(room_id): Path<String>,
+    Query(query): Query<SocketQuery>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    match authorize_room_socket(&hub.jwt_manager, &hub.database, &room_id, &query.token) {
+        Ok((room_id, user_id, events)) => ws
+            .max_message_size(MAX_MESSAGE_BYTES)
+            .on_upgrade(move |socket| hub.run(socket, room_id, user_id, events)),
+        Err(response) => response,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hub() -> SignalingHub {
+        SignalingHub::new(
+            Database::new(),
+            JwtManager::new("signaling-test-secret".to_string(), 1),
+            Metrics::new(),
+        )
+    }
+
+    fn drain(receiver: &mut mpsc::UnboundedReceiver<String>) -> Vec<serde_json::Value> {
+        std::iter::from_fn(|| receiver.try_recv().ok())
+            .map(|text| serde_json::from_str(&text).unwrap())
+            .collect()
+    }
+
+    fn wants(users: &[&str]) -> HashSet<String> {
+        users.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_introduces_only_assigned_peers() {
+        let hub = hub();
+        let room_id = uuid::Uuid::new_v4();
+        let (a, mut a_rx) = hub.register(room_id, "a");
+        let (b, mut b_rx) = hub.register(room_id, "b");
+        let (c, mut c_rx) = hub.register(room_id, "c");
+        assert_eq!(
+            drain(&mut a_rx),
+            vec![serde_json::json!({ "IdAssigned": a.to_string() })]
+        );
+        drain(&mut b_rx);
+        drain(&mut c_rx);
+
+        // a and b share a group, c is elsewhere
+        hub.introduce(&room_id, a, wants(&["b"]));
+        hub.introduce(&room_id, b, wants(&["a"]));
+        hub.introduce(&room_id, c, wants(&[]));
+
+        // The older peer hears about the newer one exactly once
+        assert_eq!(
+            drain(&mut a_rx),
+            vec![serde_json::json!({ "NewPeer": b.to_string() })]
+        );
+        assert!(drain(&mut b_rx).is_empty());
+        assert!(drain(&mut c_rx).is_empty());
+
+        // Signals only flow between introduced peers
+        assert!(hub.forward_signal(&room_id, b, &a.to_string(), serde_json::json!("offer")));
+        assert_eq!(
+            drain(&mut a_rx),
+            vec![serde_json::json!({ "Signal": { "sender": b.to_string(), "data": "offer" } })]
+        );
+        assert!(!hub.forward_signal(&room_id, c, &a.to_string(), serde_json::json!("offer")));
+        assert!(drain(&mut a_rx).is_empty());
+
+        // The pair stays while either side still wants it
+        hub.introduce(&room_id, a, wants(&[]));
+        assert!(drain(&mut a_rx).is_empty());
+
+        // Moving b away drops the pair on both ends
+        hub.introduce(&room_id, b, wants(&["c"]));
+        assert_eq!(
+            drain(&mut a_rx),
+            vec![serde_json::json!({ "PeerLeft": b.to_string() })]
+        );
+        let b_events = drain(&mut b_rx);
+        assert_eq!(b_events.len(), 2);
+        assert!(b_events.contains(&serde_json::json!({ "PeerLeft": a.to_string() })));
+        assert!(b_events.contains(&serde_json::json!({ "NewPeer": c.to_string() })));
+    }
+
+    #[test]
+    fn test_teardown_notifies_linked_peers() {
+        let hub = hub();
+        let room_id = uuid::Uuid::new_v4();
+        let (a, mut a_rx) = hub.register(room_id, "a");
+        let (b, mut b_rx) = hub.register(room_id, "b");
+        hub.introduce(&room_id, a, wants(&["b"]));
+        drain(&mut a_rx);
+        drain(&mut b_rx);
+
+        // A reconnect of b replaces the old socket and drops its links
+        let (b2, _b2_rx) = hub.register(room_id, "b");
+        assert!(matches!(
+            b_rx.try_recv(),
+            Err(mpsc::error::TryRecvError::Disconnected)
+        ));
+        assert_eq!(
+            drain(&mut a_rx),
+            vec![serde_json::json!({ "PeerLeft": b.to_string() })]
+        );
+        // Cleanup of the replaced socket leaves the new one alone
+        hub.unregister(&room_id, b);
+        assert!(hub.rooms.lock()[&room_id].peers.contains_key(&b2));
+
+        hub.introduce(&room_id, b2, wants(&["a"]));
+        drain(&mut a_rx);
+        hub.unregister(&room_id, b2);
+        assert_eq!(
+            drain(&mut a_rx),
+            vec![serde_json::json!({ "PeerLeft": b2.to_string() })]
+        );
+
+        hub.unregister(&room_id, a);
+        assert!(hub.rooms.lock().is_empty());
+    }
+}
@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+
+use marble_proto::stats::{
+    GetMapStatsRequest, GetMapStatsResponse, GetPlayerHistoryRequest, GetPlayerHistoryResponse,
+    GetPlayerStatsRequest, GetPlayerStatsResponse, JoinOrderStats, MapPlayerStats, PlayerRace,
+    PlayerStats,
+};
+use tonic::{Request, Response, Status};
+
+use crate::service::database::{Database, StoredMatch};
+
+use super::jwt::AuthenticatedUser;
+
+pub struct StatsServiceImpl {
+    database: Database,
+}
+
+impl StatsServiceImpl {
+    pub fn new(database: Database) -> Self {
+        Self { database }
+    }
+
+    /// Requested `user_id`, falling back to the caller when empty.
+    fn resolve_user_id(
+        request_extensions: &http::Extensions,
+        user_id: String,
+    ) -> Result<String, Status> {
+        if !user_id.is_empty() {
+            return Ok(user_id);
+        }
+        request_extensions
+            .get::<AuthenticatedUser>()
+            .map(|u| u.user_id.clone())
+            .ok_or_else(|| Status::unauthenticated("Authentication required"))
+    }
+}
+
+fn stored_to_player_race(m: &StoredMatch, user_id: &str) -> PlayerRace {
+    PlayerRace {
+        match_id: m.match_id.clone(),
+        room_id: m.room_id.clone(),
+        round: m.record.round,
+        map_id: m.record.map_id.clone(),
+        rank: m.rank_of(user_id),
+        field_size: u32::try_from(m.participants.len()).unwrap_or(u32::MAX),
+        played_at: m.record.ended_at.to_rfc3339(),
+    }
+}
+
+/// Aggregate races, wins and average rank of `user_id` over `matches`.
+#[allow(clippy::cast_precision_loss)]
+fn aggregate_player_stats<'a>(
+    matches: impl IntoIterator<Item = &'a StoredMatch>,
+    user_id: &str,
+) -> PlayerStats {
+    let mut races = 0u32;
+    let mut wins = 0u32;
+    let mut ranked = 0u32;
+    let mut rank_sum = 0u64;
+
+    for m in matches {
+        races += 1;
+        let rank = m.rank_of(user_id);
+        if rank == 1 {
+            wins += 1;
+        }
+        if rank > 0 {
+            ranked += 1;
+            rank_sum += u64::from(rank);
+        }
+    }
+
+    PlayerStats {
+        races,
+        wins,
+        average_rank: if ranked == 0 {
+            0.0
+        } else {
+            rank_sum as f64 / f64::from(ranked)
+        },
+    }
+}
+
+/// Aggregate plays, average duration and win distribution by join order over `matches`.
+fn aggregate_map_stats(map_id: &str, matches: &[StoredMatch]) -> GetMapStatsResponse {
+    let mut total_duration_ms = 0u64;
+    let mut win_distribution: Vec<JoinOrderStats> = Vec::new();
+
+    for m in matches {
+        let duration = m.record.ended_at - m.record.started_at;
+        total_duration_ms += u64::try_from(duration.num_milliseconds()).unwrap_or(0);
+
+        if win_distribution.len() < m.participants.len() {
+            win_distribution.resize_with(m.participants.len(), JoinOrderStats::default);
+        }
+        for (order, participant) in m.participants.iter().enumerate() {
+            let slot = &mut win_distribution[order];
+            slot.races += 1;
+            if m.winner() == Some(participant.as_str()) {
+                slot.wins += 1;
+            }
+        }
+    }
+
+    for (order, slot) in win_distribution.iter_mut().enumerate() {
+        slot.join_order = u32::try_from(order).unwrap_or(u32::MAX);
+    }
+
+    let plays = u32::try_from(matches.len()).unwrap_or(u32::MAX);
+
+    GetMapStatsResponse {
+        map_id: map_id.to_string(),
+        plays,
+        average_duration_ms: total_duration_ms.checked_div(u64::from(plays)).unwrap_or(0),
+        win_distribution,
+    }
+}
+
+#[tonic::async_trait]
+impl marble_proto::stats::stats_service_server::StatsService for StatsServiceImpl {
+    async fn get_player_history(
+        &self,
+        request: Request<GetPlayerHistoryRequest>,
+    ) -> Result<Response<GetPlayerHistoryResponse>, Status> {
+        let (_, extensions, req) = request.into_parts();
+        let user_id = Self::resolve_user_id(&extensions, req.user_id)?;
+
+        let page_size = if req.page_size == 0 { 20 } else { req.page_size };
+
+        let (matches, next_page_token, total_count) =
+            self.database
+                .list_player_matches(&user_id, page_size, &req.page_token);
+
+        let races = matches
+            .iter()
+            .map(|m| stored_to_player_race(m, &user_id))
+            .collect();
+
+        Ok(Response::new(GetPlayerHistoryResponse {
+            races,
+            next_page_token,
+            total_count,
+        }))
+    }
+
+    async fn get_player_stats(
+        &self,
+        request: Request<GetPlayerStatsRequest>,
+    ) -> Result<Response<GetPlayerStatsResponse>, Status> {
+        let (_, extensions, req) = request.into_parts();
+        let user_id = Self::resolve_user_id(&extensions, req.user_id)?;
+
+        let matches = self.database.get_player_matches(&user_id);
+
+        let mut by_map: HashMap<&str, Vec<&StoredMatch>> = HashMap::new();
+        for m in &matches {
+            by_map.entry(m.record.map_id.as_str()).or_default().push(m);
+        }
+
+        let mut per_map: Vec<MapPlayerStats> = by_map
+            .into_iter()
+            .map(|(map_id, map_matches)| MapPlayerStats {
+                map_id: map_id.to_string(),
+                stats: Some(aggregate_player_stats(map_matches, &user_id)),
+            })
+            .collect();
+        per_map.sort_by(|a, b| {
+            let a_races = a.stats.as_ref().map_or(0, |s| s.races);
+            let b_races = b.stats.as_ref().map_or(0, |s| s.races);
+            b_races.cmp(&a_races).then_with(|| a.map_id.cmp(&b.map_id))
+        });
+
+        Ok(Response::new(GetPlayerStatsResponse {
+            overall: Some(aggregate_player_stats(&matches, &user_id)),
+            user_id,
+            per_map,
+        }))
+    }
+
+    async fn get_map_stats(
+        &self,
+        request: Request<GetMapStatsRequest>,
+    ) -> Result<Response<GetMapStatsResponse>, Status> {
+        let req = request.into_inner();
+
+        if req.map_id.is_empty() {
+            return Err(Status::invalid_argument("map_id is required"));
+        }
+
+        let matches = self.database.get_map_matches(&req.map_id);
+
+        Ok(Response::new(aggregate_map_stats(&req.map_id, &matches)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+
+    use super::*;
+    use crate::common::room::{GameResult, RoundRecord};
+
+    fn create_match(map_id: &str, participants: &[&str], ranking: &[&str]) -> StoredMatch {
+        let started_at = Utc::now();
+        StoredMatch {
+            match_id: uuid::Uuid::new_v4().to_string(),
+            room_id: uuid::Uuid::new_v4().to_string(),
+            record: RoundRecord {
+                round: 1,
+                map_id: map_id.to_string(),
+                map_revision: 1,
+                rng_seed: 42,
+                start_frame: 0,
+                started_at,
+                ended_at: started_at + Duration::seconds(30),
+                results: ranking
+                    .iter()
+                    .zip(1..)
+                    .map(|(user_id, rank)| GameResult {
+                        user_id: (*user_id).to_string(),
+                        rank,
+                        arrival_frame: u64::from(rank) * 60,
+                    })
+                    .collect(),
+            },
+            participants: participants.iter().map(|p| (*p).to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_player_stats() {
+        let matches = [
+            create_match("map_a", &["p1", "p2", "p3"], &["p1", "p2", "p3"]),
+            create_match("map_a", &["p1", "p2"], &["p2", "p1"]),
+            create_match("map_b", &["p1", "p2"], &["p2"]),
+        ];
+
+        let stats = aggregate_player_stats(&matches, "p1");
+        assert_eq!(stats.races, 3);
+        assert_eq!(stats.wins, 1);
+        // Unranked race is excluded from the average
+        assert!((stats.average_rank - 1.5).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_map_stats_win_distribution() {
+        let matches = vec![
+            create_match("map_a", &["host", "p2", "p3"], &["p2", "host", "p3"]),
+            create_match("map_a", &["host", "p2"], &["host", "p2"]),
+        ];
+
+        let stats = aggregate_map_stats("map_a", &matches);
+        assert_eq!(stats.plays, 2);
+        assert_eq!(stats.average_duration_ms, 30_000);
+        assert_eq!(stats.win_distribution.len(), 3);
+        assert_eq!(stats.win_distribution[0].races, 2);
+        assert_eq!(stats.win_distribution[0].wins, 1);
+        assert_eq!(stats.win_distribution[1].wins, 1);
+        assert_eq!(stats.win_distribution[2].join_order, 2);
+        assert_eq!(stats.win_distribution[2].races, 1);
+        assert_eq!(stats.win_distribution[2].wins, 0);
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+
 use marble_proto::user::{
     login_request, GetUserRequest, GetUserResponse, GetUsersRequest, GetUsersResponse,
     LoginRequest, LoginResponse, UpdateProfileRequest, UpdateProfileResponse, UserInfo,
@@ -6,18 +8,25 @@ use tonic::{Request, Response, Status};
 
 use crate::service::database::{AuthType, Database};
 
-use super::jwt::JwtManager;
+use super::jwt::{ADMIN_ROLE, JwtManager};
 
 pub struct UserServiceImpl {
     database: Database,
     jwt_manager: JwtManager,
+    /// Users whose tokens carry the admin role claim
+    admin_user_ids: HashSet<String>,
 }
 
 impl UserServiceImpl {
-    pub fn new(database: Database, jwt_manager: JwtManager) -> Self {
+    pub fn new(
+        database: Database,
+        jwt_manager: JwtManager,
+        admin_user_ids: HashSet<String>,
+    ) -> Self {
         Self {
             database,
             jwt_manager,
+            admin_user_ids,
         }
     }
 }
@@ -57,7 +66,16 @@ impl marble_proto::user::user_service_server::UserService for UserServiceImpl {
             }
         };
 
-        let (token, expires_at) = self.jwt_manager.generate_token(&user.user_id);
+        if self.database.is_banned(&user.user_id) {
+            return Err(Status::permission_denied("User is banned"));
+        }
+
+        let roles = if self.admin_user_ids.contains(&user.user_id) {
+            vec![ADMIN_ROLE.to_string()]
+        } else {
+            Vec::new()
+        };
+        let (token, expires_at) = self.jwt_manager.generate_token(&user.user_id, &roles);
 
         let user_info = UserInfo {
             user_id: user.user_id,
@@ -1,39 +1,54 @@
 //! Marble-Live Server
 //!
-//! Axum backend with gRPC-Web, matchbox signaling, and SPA serving.
+//! Axum backend with gRPC-Web, room-scoped WebRTC signaling, and SPA serving.
 //! Static files are embedded in the binary via rust-embed.
 
 use std::net::SocketAddr;
 
 use axum::{
     Router,
-    http::StatusCode,
+    extract::{Path, State},
+    http::{HeaderValue, StatusCode},
     response::{IntoResponse, Response},
+    routing::get,
 };
+use clap::Parser;
 use http::{Method, header};
+use marble_proto::admin::admin_service_server::AdminServiceServer;
 use marble_proto::avatar::avatar_service_server::AvatarServiceServer;
 use marble_proto::map::map_service_server::MapServiceServer;
 use marble_proto::room::room_service_server::RoomServiceServer;
+use marble_proto::stats::stats_service_server::StatsServiceServer;
 use marble_proto::user::user_service_server::UserServiceServer;
-use matchbox_signaling::SignalingServer;
 use rust_embed::Embed;
 use tonic::service::Routes;
 use tonic_web::GrpcWebLayer;
-use tower_http::cors::{Any, CorsLayer};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 use crate::{
+    config::{Cli, Command, ServerConfig},
     handler::{
+        admin_service::AdminServiceImpl,
         avatar_service::AvatarServiceImpl,
         jwt::{JwtManager, jwt_interceptor},
         map_service::MapServiceImpl,
+        rate_limit::RateLimitLayer,
+        relay::{RelayHub, serve_relay},
         room_service::RoomServiceImpl,
+        signaling::{SignalingHub, serve_signaling},
+        stats_service::StatsServiceImpl,
         user_service::UserServiceImpl,
     },
-    service::database::Database,
+    service::{
+        database::Database,
+        metrics::{GrpcMetricsLayer, Metrics},
+        thumbnail::ThumbnailCache,
+    },
 };
 
 mod common;
+mod config;
 mod handler;
 mod service;
 mod topology;
@@ -54,22 +69,52 @@ async fn main() -> () {
         )
         .init();
 
-    let addr = SocketAddr::from(([0, 0, 0, 0], 3000));
+    let cli = Cli::parse();
+    let config = match ServerConfig::load(&cli) {
+        Ok(config) => config,
+        Err(err) => {
+            tracing::error!("{err}");
+            std::process::exit(2);
+        }
+    };
 
-    // JWT manager with a random secret (reset on restart, which is fine for in-memory stage)
-    let jwt_secret = uuid::Uuid::new_v4().to_string();
-    let jwt_manager = JwtManager::new(jwt_secret, 24); // 24 hour expiry
+    if let Some(Command::SimulateGossip(args)) = &cli.command {
+        match args.simulation(&config.topology) {
+            Ok(simulation) => println!("{}", topology::simulate(&simulation)),
+            Err(err) => {
+                tracing::error!("{err}");
+                std::process::exit(2);
+            }
+        }
+        return;
+    }
+    let addr = config.server.bind_addr;
 
-    let database = Database::new();
+    // Without a configured secret, tokens are invalidated on restart (fine for in-memory storage)
+    let jwt_secret = config
+        .auth
+        .jwt_secret
+        .clone()
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let jwt_manager = JwtManager::new(jwt_secret, config.auth.jwt_expiry_hours);
 
-    let signaling_base_url = std::env::var("SIGNALING_URL")
-        .unwrap_or_else(|_| format!("ws://localhost:{}/signaling", addr.port()));
+    let database = Database::new();
+    let metrics = Metrics::new();
 
     // Create service implementations (database is Clone via Arc)
-    let user_service = UserServiceImpl::new(database.clone(), jwt_manager.clone());
-    let map_service = MapServiceImpl::new(database.clone());
+    let user_service = UserServiceImpl::new(
+        database.clone(),
+        jwt_manager.clone(),
+        config.auth.admin_user_ids.iter().cloned().collect(),
+    );
+    let map_service = MapServiceImpl::new(database.clone(), &config);
     let avatar_service = AvatarServiceImpl::new(database.clone(), jwt_manager.clone());
-    let room_service = RoomServiceImpl::new(database, signaling_base_url);
+    let stats_service = StatsServiceImpl::new(database.clone());
+    let thumbnails = ThumbnailCache::new(database.clone(), config.storage.thumbnail_cache_size);
+    let room_service = RoomServiceImpl::new(database.clone(), &config);
+    let admin_service = AdminServiceImpl::new(database.clone());
+    let relay = RelayHub::new(database.clone(), jwt_manager.clone(), metrics.clone());
+    let signaling = SignalingHub::new(database.clone(), jwt_manager.clone(), metrics.clone());
 
     let reflection_v1 = tonic_reflection::server::Builder::configure()
         .register_encoded_file_descriptor_set(marble_proto::FILE_DESCRIPTOR_SET)
@@ -80,19 +125,36 @@ async fn main() -> () {
         .build_v1alpha()
         .expect("failed to build gRPC reflection v1alpha service");
 
-    let interceptor = jwt_interceptor(jwt_manager.clone());
+    let interceptor = jwt_interceptor(jwt_manager.clone(), database.clone());
+    let rate_limit = RateLimitLayer::new(config.rate_limit.config(), jwt_manager.clone());
     let grpc_router = Routes::new(UserServiceServer::with_interceptor(user_service, interceptor.clone()))
         .add_service(MapServiceServer::with_interceptor(map_service, interceptor.clone()))
         .add_service(RoomServiceServer::with_interceptor(room_service, interceptor.clone()))
-        .add_service(AvatarServiceServer::with_interceptor(avatar_service, interceptor))
+        .add_service(AvatarServiceServer::with_interceptor(avatar_service, interceptor.clone()))
+        .add_service(StatsServiceServer::with_interceptor(stats_service, interceptor.clone()))
+        .add_service(AdminServiceServer::with_interceptor(admin_service, interceptor))
         .add_service(reflection_v1)
         .add_service(reflection_v1alpha)
         .into_axum_router()
+        // Inside GrpcWebLayer so throttled responses get gRPC-Web framing
+        .layer(rate_limit)
+        .layer(GrpcMetricsLayer::new(metrics.clone()))
         .layer(GrpcWebLayer::new());
 
-    // CORS layer for gRPC-Web
+    // CORS layer for gRPC-Web (origins were validated with the config)
+    let allow_origin = if config.server.cors_origins.is_empty() {
+        AllowOrigin::from(Any)
+    } else {
+        AllowOrigin::list(
+            config
+                .server
+                .cors_origins
+                .iter()
+                .filter_map(|origin| HeaderValue::from_str(origin).ok()),
+        )
+    };
     let cors = CorsLayer::new()
-        .allow_origin(Any)
+        .allow_origin(allow_origin)
         .allow_methods([Method::GET, Method::POST, Method::OPTIONS])
         .allow_headers([
             header::CONTENT_TYPE,
@@ -104,34 +166,54 @@ async fn main() -> () {
         .expose_headers([
             "grpc-status".parse().unwrap(),
             "grpc-message".parse().unwrap(),
+            "retry-after".parse().unwrap(),
         ]);
 
-    // App router (gRPC only - fallback will be added in build_with)
-    let app_router = Router::new().merge(grpc_router).layer(cors);
-
-    // Build signaling server with integrated app router
-    let signaling_server = SignalingServer::full_mesh_builder(addr)
-        .cors()
-        .trace()
-        .on_peer_connected(|peer_id| {
-            tracing::info!("Peer connected: {peer_id}");
-        })
-        .on_peer_disconnected(|peer_id| {
-            tracing::info!("Peer disconnected: {peer_id}");
-        })
-        .build_with(move |signaling_router| {
-            Router::new()
-                .nest("/signaling", signaling_router)
-                .merge(app_router)
-                .fallback(serve_embedded)
-        });
+    // App router (gRPC + WebSockets + thumbnails + observability, SPA as fallback)
+    let app = Router::new()
+        .merge(grpc_router)
+        .route(
+            "/thumbnails/{map_id}/{revision}",
+            get(serve_thumbnail).with_state(thumbnails),
+        )
+        .route(
+            "/metrics",
+            get(serve_metrics).with_state((metrics.clone(), database)),
+        )
+        .route(
+            "/signaling/{room_id}",
+            get(serve_signaling).with_state(signaling),
+        )
+        .route("/relay/{room_id}", get(serve_relay).with_state(relay))
+        .route("/healthz", get(|| async { "ok" }))
+        .route("/readyz", get(serve_readyz).with_state(metrics.clone()))
+        .layer(cors)
+        .fallback(serve_embedded);
+
+    let listener = match tokio::net::TcpListener::bind(addr).await {
+        Ok(listener) => listener,
+        Err(err) => {
+            tracing::error!("Failed to bind {addr}: {err}");
+            std::process::exit(1);
+        }
+    };
 
     tracing::info!("Server listening on {addr}");
     tracing::info!("  - gRPC-Web: http://{addr}/ (root-mounted)");
-    tracing::info!("  - Signaling: ws://{addr}/signaling/{{room_id}}");
+    tracing::info!("  - Signaling: ws://{addr}/signaling/{{room_id}}?token=...");
+    tracing::info!("  - Relay: ws://{addr}/relay/{{room_id}}?token=...");
+    tracing::info!("  - Thumbnails: http://{addr}/thumbnails/{{map_id}}/{{revision}}");
+    tracing::info!("  - Metrics: http://{addr}/metrics (health: /healthz, /readyz)");
     tracing::info!("  - SPA (embedded): http://{addr}/");
 
-    signaling_server.serve().await.unwrap();
+    metrics.set_ready(true);
+    serve(listener, app).await.unwrap();
+}
+
+/// Serve the app with each connection's peer address attached, which the per-IP rate
+/// limits key on
+async fn serve(listener: tokio::net::TcpListener, app: Router) -> std::io::Result<()> {
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await
 }
 
 /// Serve embedded static files with SPA fallback
@@ -164,3 +246,83 @@ async fn serve_embedded(uri: axum::http::Uri) -> Response {
         }
     }
 }
+
+/// Serve the SVG thumbnail of a map revision
+async fn serve_thumbnail(
+    State(thumbnails): State<ThumbnailCache>,
+    Path((map_id, revision)): Path<(String, u32)>,
+) -> Response {
+    match thumbnails.get(&map_id, revision) {
+        Some(svg) => (
+            [
+                (header::CONTENT_TYPE, "image/svg+xml"),
+                // Revisions are immutable
+                (header::CACHE_CONTROL, "public, max-age=31536000, immutable"),
+            ],
+            svg.to_string(),
+        )
+            .into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// Serve Prometheus metrics
+async fn serve_metrics(State((metrics, database)): State<(Metrics, Database)>) -> Response {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics.render(&database),
+    )
+        .into_response()
+}
+
+/// Readiness probe: 200 once the server is accepting traffic
+async fn serve_readyz(State(metrics): State<Metrics>) -> StatusCode {
+    if metrics.is_ready() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handler::rate_limit::{Quota, RateLimitConfig};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Raw response to a plain HTTP/1.1 GET
+    async fn http_get(addr: SocketAddr, path: &str) -> String {
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let request =
+            format!("GET {path} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n");
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn test_served_router_enforces_ip_quota() {
+        let config = RateLimitConfig {
+            per_ip: Quota::per_second(1, 2),
+            ..RateLimitConfig::default()
+        };
+        let jwt_manager = JwtManager::new("serve-test-secret".to_string(), 1);
+        let app = Router::new()
+            .route("/ping", get(|| async { "pong" }))
+            .layer(RateLimitLayer::new(config, jwt_manager));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(listener, app));
+
+        for _ in 0..2 {
+            let response = http_get(addr, "/ping").await;
+            assert!(!response.contains("grpc-status"), "{response}");
+        }
+        // The third call from the same address within a second exhausts the per-IP bucket
+        let response = http_get(addr, "/ping").await;
+        assert!(response.contains("grpc-status: 8"), "{response}");
+        assert!(response.contains("retry-after"), "{response}");
+    }
+}
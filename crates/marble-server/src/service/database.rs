@@ -1,14 +1,21 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use chrono::{DateTime, Utc};
-use marble_proto::room::{PeerConnectionStatus, PeerTopology, RoomRole, RoomState};
+use marble_proto::room::{
+    GroupLatency, PeerConnectionStatus, PeerTopology, Readiness, RoomEvent, RoomRole, RoomSnapshot,
+    RoomState, RoomUser, room_event,
+};
 use parking_lot::RwLock;
 use std::sync::Arc;
 use thiserror::Error;
+use tokio::sync::broadcast;
 
 use marble_proto::avatar::AvatarInfo;
+use marble_proto::map::MapSort;
 
-use crate::common::room::{Room, RoomError};
+use crate::common::invite::{InviteToken, JoinCredentials, generate_invite_code};
+use crate::common::room::{Room, RoomError, RoomUpdate, RoundRecord};
+use crate::topology::TopologyChurn;
 
 // ========================================
 // User storage
@@ -46,6 +53,147 @@ pub struct StoredMap {
     pub data: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Current revision (revision of `data`)
+    pub revision: u32,
+    /// `user_id`s that liked the map
+    pub liked_by: HashSet<String>,
+    /// Number of rooms started with this map
+    pub play_count: u32,
+    /// Map this was forked from (`None` = original)
+    pub parent_map_id: Option<String>,
+    /// Revision of the parent that was forked
+    pub parent_revision: u32,
+    /// Hidden by an admin: excluded from listings, lookups by non-owners and new rooms
+    pub hidden: bool,
+}
+
+impl StoredMap {
+    pub fn like_count(&self) -> u32 {
+        u32::try_from(self.liked_by.len()).unwrap_or(u32::MAX)
+    }
+
+    /// Primary sort key for `sort` (descending), ties broken by `map_id` ascending.
+    fn sort_key(&self, sort: MapSort) -> i64 {
+        match sort {
+            MapSort::Unspecified | MapSort::Newest => self.created_at.timestamp_micros(),
+            MapSort::MostLiked => i64::from(self.like_count()),
+            MapSort::MostPlayed => i64::from(self.play_count),
+            MapSort::RecentlyUpdated => self.updated_at.timestamp_micros(),
+        }
+    }
+}
+
+/// Immutable snapshot of a map's data, stored on every data change.
+#[derive(Debug, Clone)]
+pub struct StoredMapRevision {
+    pub revision: u32,
+    pub author_id: String,
+    pub data: String,
+    pub created_at: DateTime<Utc>,
+    /// Source revision if created by a restore (0 = edit)
+    pub restored_from: u32,
+}
+
+/// One step of a fork chain. `name` and `creator_id` are empty once the ancestor is deleted.
+#[derive(Debug, Clone)]
+pub struct MapAncestor {
+    pub map_id: String,
+    pub revision: u32,
+    pub name: String,
+    pub creator_id: String,
+}
+
+/// Keyset cursor for `list_maps`: `{sort_key}.{map_id}` of the last returned map.
+///
+/// Unlike an offset or a position lookup, the cursor stays valid when counters
+/// change between pages: listing resumes strictly after the last seen key.
+fn encode_map_cursor(key: i64, map_id: &str) -> String {
+    format!("{key}.{map_id}")
+}
+
+fn decode_map_cursor(token: &str) -> Option<(i64, &str)> {
+    let (key, map_id) = token.split_once('.')?;
+    Some((key.parse().ok()?, map_id))
+}
+
+// ========================================
+// Match history storage
+// ========================================
+
+/// A finished race, recorded when its room reaches ENDED.
+#[derive(Debug, Clone)]
+pub struct StoredMatch {
+    pub match_id: String,
+    pub room_id: String,
+    pub record: RoundRecord,
+    /// Participant `user_id`s in join order (host first)
+    pub participants: Vec<String>,
+}
+
+impl StoredMatch {
+    pub fn has_participant(&self, user_id: &str) -> bool {
+        self.participants.iter().any(|p| p == user_id)
+    }
+
+    /// Rank of a participant (0 = did not arrive).
+    pub fn rank_of(&self, user_id: &str) -> u32 {
+        self.record
+            .results
+            .iter()
+            .find(|r| r.user_id == user_id)
+            .map_or(0, |r| r.rank)
+    }
+
+    /// `user_id` of the rank 1 finisher.
+    pub fn winner(&self) -> Option<&str> {
+        self.record
+            .results
+            .iter()
+            .find(|r| r.rank == 1)
+            .map(|r| r.user_id.as_str())
+    }
+}
+
+// ========================================
+// Moderation storage
+// ========================================
+
+/// One admin action, appended for every `AdminService` call.
+#[derive(Debug, Clone)]
+pub struct StoredAuditEntry {
+    pub entry_id: String,
+    pub admin_id: String,
+    /// RPC name, e.g. `BanUser`
+    pub action: String,
+    pub target_id: String,
+    pub reason: String,
+    pub created_at: DateTime<Utc>,
+}
+
+// ========================================
+// Statistics
+// ========================================
+
+/// Point-in-time counts for the metrics endpoint.
+#[derive(Debug, Default)]
+pub struct DatabaseStats {
+    pub users: usize,
+    pub maps: usize,
+    pub map_revisions: usize,
+    pub avatars: usize,
+    pub matches: usize,
+    pub rooms_by_state: HashMap<RoomState, usize>,
+    pub members_by_role: HashMap<RoomRole, usize>,
+    pub room_topologies: Vec<RoomTopologyStats>,
+    /// Topology changes across all rooms, including rooms that no longer exist
+    pub topology_churn: TopologyChurn,
+}
+
+#[derive(Debug)]
+pub struct RoomTopologyStats {
+    pub room_id: uuid::Uuid,
+    pub mesh_groups: usize,
+    pub bridges: usize,
 }
 
 // ========================================
@@ -59,7 +207,17 @@ pub struct Database {
     /// (salt, fingerprint) -> `user_id` index for anonymous login lookup
     anon_index: Arc<RwLock<HashMap<(String, String), String>>>,
     maps: Arc<RwLock<HashMap<String, StoredMap>>>,
+    /// `map_id` -> revisions, oldest first (revision N at index N-1)
+    map_revisions: Arc<RwLock<HashMap<String, Vec<StoredMapRevision>>>>,
     avatars: Arc<RwLock<HashMap<String, AvatarInfo>>>,
+    /// Finished races, oldest first
+    matches: Arc<RwLock<Vec<StoredMatch>>>,
+    /// Banned `user_id`s
+    banned_users: Arc<RwLock<HashSet<String>>>,
+    /// Admin actions, oldest first
+    audit_log: Arc<RwLock<Vec<StoredAuditEntry>>>,
+    /// Topology changes of rooms that were dropped
+    closed_rooms_churn: Arc<RwLock<TopologyChurn>>,
 }
 
 #[derive(Error, Debug)]
@@ -82,6 +240,9 @@ pub enum DatabaseError {
     #[error("Map is in use by an active room")]
     MapInUse,
 
+    #[error("Map revision not found")]
+    MapRevisionNotFound,
+
     #[error("Unauthorized: not a room member")]
     NotRoomMember,
 }
@@ -90,7 +251,10 @@ impl DatabaseError {
     fn to_code(&self) -> tonic::Code {
         match self {
             Self::RoomError(err) => err.to_code(),
-            Self::RoomNotFound | Self::UserNotFound | Self::MapNotFound => tonic::Code::NotFound,
+            Self::RoomNotFound
+            | Self::UserNotFound
+            | Self::MapNotFound
+            | Self::MapRevisionNotFound => tonic::Code::NotFound,
             Self::MapOwnerOnly | Self::NotRoomMember => tonic::Code::PermissionDenied,
             Self::MapInUse => tonic::Code::FailedPrecondition,
         }
@@ -110,7 +274,12 @@ impl Database {
             users: Arc::new(RwLock::new(HashMap::new())),
             anon_index: Arc::new(RwLock::new(HashMap::new())),
             maps: Arc::new(RwLock::new(HashMap::new())),
+            map_revisions: Arc::new(RwLock::new(HashMap::new())),
             avatars: Arc::new(RwLock::new(HashMap::new())),
+            matches: Arc::new(RwLock::new(Vec::new())),
+            banned_users: Arc::new(RwLock::new(HashSet::new())),
+            audit_log: Arc::new(RwLock::new(Vec::new())),
+            closed_rooms_churn: Arc::new(RwLock::new(TopologyChurn::default())),
         }
     }
 
@@ -186,6 +355,90 @@ impl Database {
         Ok(user.clone())
     }
 
+    /// Replace a display name with a generated `Player-xxxxxxxx`.
+    pub fn reset_display_name(&self, user_id: &str) -> Result<StoredUser, DatabaseError> {
+        let display_name = format!("Player-{}", user_id.get(..8).unwrap_or(user_id));
+        self.update_user_profile(user_id, &display_name)
+    }
+
+    // ========================================
+    // Moderation operations
+    // ========================================
+
+    /// Ban a user server-wide. They are kicked from every room they are in, so their
+    /// watch streams and sockets close on `MemberLeft`, and rooms they host are closed.
+    pub fn ban_user(&self, user_id: &str) -> Result<(), DatabaseError> {
+        if !self.users.read().contains_key(user_id) {
+            return Err(DatabaseError::UserNotFound);
+        }
+        self.banned_users.write().insert(user_id.to_string());
+
+        let mut hosted = Vec::new();
+        for room in self.rooms.write().values_mut() {
+            if room.host_user_id() == user_id {
+                hosted.push(*room.id());
+            } else if room.has_member(user_id) {
+                self.kick_from(room, user_id, false)?;
+            }
+        }
+        for room_id in hosted {
+            self.force_close_room(&room_id)?;
+        }
+        Ok(())
+    }
+
+    /// Lift a ban. Returns whether the user was banned.
+    pub fn unban_user(&self, user_id: &str) -> bool {
+        self.banned_users.write().remove(user_id)
+    }
+
+    pub fn is_banned(&self, user_id: &str) -> bool {
+        self.banned_users.read().contains(user_id)
+    }
+
+    pub fn record_audit(&self, admin_id: &str, action: &str, target_id: &str, reason: &str) {
+        let entry = StoredAuditEntry {
+            entry_id: uuid::Uuid::new_v4().to_string(),
+            admin_id: admin_id.to_string(),
+            action: action.to_string(),
+            target_id: target_id.to_string(),
+            reason: reason.to_string(),
+            created_at: Utc::now(),
+        };
+        self.audit_log.write().push(entry);
+    }
+
+    /// Audit log, newest first. `page_token` is the `entry_id` of the last entry seen.
+    pub fn list_audit_log(
+        &self,
+        page_size: u32,
+        page_token: &str,
+    ) -> (Vec<StoredAuditEntry>, String, u32) {
+        let log = self.audit_log.read();
+        let page_size = page_size.clamp(1, 100) as usize;
+        let total_count = u32::try_from(log.len()).unwrap_or(u32::MAX);
+
+        let start = if page_token.is_empty() {
+            0
+        } else {
+            log.iter()
+                .rev()
+                .position(|e| e.entry_id == page_token)
+                .map_or(0, |p| p + 1)
+        };
+
+        let page: Vec<StoredAuditEntry> = log
+            .iter()
+            .rev()
+            .skip(start)
+            .take(page_size)
+            .cloned()
+            .collect();
+        let next_token = page.last().map(|e| e.entry_id.clone()).unwrap_or_default();
+
+        (page, next_token, total_count)
+    }
+
     // ========================================
     // Map operations
     // ========================================
@@ -197,9 +450,48 @@ impl Database {
         description: &str,
         tags: Vec<String>,
         data: &str,
+    ) -> StoredMap {
+        self.insert_map(creator_id, name, description, tags, data, None)
+    }
+
+    /// Copy a map (at `revision`, or its current one) to `user_id`, recording the parent.
+    pub fn fork_map(
+        &self,
+        map_id: &str,
+        revision: Option<u32>,
+        user_id: &str,
+        name: Option<&str>,
+    ) -> Result<StoredMap, DatabaseError> {
+        let parent = match revision {
+            Some(revision) => self.get_map_at_revision(map_id, revision)?,
+            None => self.get_map(map_id).ok_or(DatabaseError::MapNotFound)?,
+        };
+        if parent.hidden {
+            return Err(DatabaseError::MapNotFound);
+        }
+
+        Ok(self.insert_map(
+            user_id,
+            name.unwrap_or(&parent.name),
+            &parent.description,
+            parent.tags.clone(),
+            &parent.data,
+            Some((parent.map_id.clone(), parent.revision)),
+        ))
+    }
+
+    fn insert_map(
+        &self,
+        creator_id: &str,
+        name: &str,
+        description: &str,
+        tags: Vec<String>,
+        data: &str,
+        parent: Option<(String, u32)>,
     ) -> StoredMap {
         let map_id = uuid::Uuid::new_v4().to_string();
         let now = Utc::now();
+        let (parent_map_id, parent_revision) = parent.unzip();
         let map = StoredMap {
             map_id: map_id.clone(),
             name: name.to_string(),
@@ -209,18 +501,168 @@ impl Database {
             data: data.to_string(),
             created_at: now,
             updated_at: now,
+            revision: 1,
+            liked_by: HashSet::new(),
+            play_count: 0,
+            parent_map_id,
+            parent_revision: parent_revision.unwrap_or(0),
+            hidden: false,
+        };
+        let revision = StoredMapRevision {
+            revision: 1,
+            author_id: creator_id.to_string(),
+            data: data.to_string(),
+            created_at: now,
+            restored_from: 0,
         };
 
         let mut maps = self.maps.write();
-        maps.insert(map_id, map.clone());
+        let mut revisions = self.map_revisions.write();
+        maps.insert(map_id.clone(), map.clone());
+        revisions.insert(map_id, vec![revision]);
         map
     }
 
+    /// Number of maps owned by `creator_id`.
+    pub fn count_maps_by(&self, creator_id: &str) -> usize {
+        let maps = self.maps.read();
+        maps.values().filter(|m| m.creator_id == creator_id).count()
+    }
+
     pub fn get_map(&self, map_id: &str) -> Option<StoredMap> {
         let maps = self.maps.read();
         maps.get(map_id).cloned()
     }
 
+    /// Fork ancestors of `map`, nearest parent first.
+    ///
+    /// The chain stops at the first deleted ancestor, since its own parent is no longer known.
+    pub fn map_lineage(&self, map: &StoredMap) -> Vec<MapAncestor> {
+        let maps = self.maps.read();
+        let mut lineage = Vec::new();
+        let mut visited = HashSet::from([map.map_id.as_str()]);
+        let mut next = map
+            .parent_map_id
+            .as_deref()
+            .map(|id| (id, map.parent_revision));
+
+        while let Some((map_id, revision)) = next {
+            if !visited.insert(map_id) {
+                break;
+            }
+            let parent = maps.get(map_id);
+            lineage.push(MapAncestor {
+                map_id: map_id.to_string(),
+                revision,
+                name: parent.map(|p| p.name.clone()).unwrap_or_default(),
+                creator_id: parent.map(|p| p.creator_id.clone()).unwrap_or_default(),
+            });
+            next =
+                parent.and_then(|p| p.parent_map_id.as_deref().map(|id| (id, p.parent_revision)));
+        }
+        lineage
+    }
+
+    /// Get a map with `data` (and `revision`) taken from a past revision.
+    pub fn get_map_at_revision(
+        &self,
+        map_id: &str,
+        revision: u32,
+    ) -> Result<StoredMap, DatabaseError> {
+        let maps = self.maps.read();
+        let revisions = self.map_revisions.read();
+        let mut map = maps
+            .get(map_id)
+            .cloned()
+            .ok_or(DatabaseError::MapNotFound)?;
+        let stored = revisions
+            .get(map_id)
+            .and_then(|revs| revs.iter().find(|r| r.revision == revision))
+            .ok_or(DatabaseError::MapRevisionNotFound)?;
+        map.data = stored.data.clone();
+        map.revision = stored.revision;
+        Ok(map)
+    }
+
+    /// Revisions of a map, newest first.
+    pub fn list_map_revisions(
+        &self,
+        map_id: &str,
+        page_size: u32,
+        page_token: &str,
+    ) -> Result<(Vec<StoredMapRevision>, String, u32), DatabaseError> {
+        let revisions = self.map_revisions.read();
+        let revs = revisions.get(map_id).ok_or(DatabaseError::MapNotFound)?;
+        let page_size = page_size.clamp(1, 100) as usize;
+
+        let total_count = u32::try_from(revs.len()).unwrap_or(u32::MAX);
+
+        // Cursor is the last returned revision number; continue with older ones
+        let before = page_token.parse::<u32>().unwrap_or(u32::MAX);
+
+        let page: Vec<StoredMapRevision> = revs
+            .iter()
+            .rev()
+            .filter(|r| r.revision < before)
+            .take(page_size)
+            .cloned()
+            .collect();
+
+        let next_token = page
+            .last()
+            .map(|r| r.revision.to_string())
+            .unwrap_or_default();
+
+        Ok((page, next_token, total_count))
+    }
+
+    /// Restore a past revision by storing its data as a new revision. Owner only.
+    pub fn restore_map_revision(
+        &self,
+        map_id: &str,
+        user_id: &str,
+        revision: u32,
+    ) -> Result<StoredMap, DatabaseError> {
+        let mut maps = self.maps.write();
+        let mut revisions = self.map_revisions.write();
+        let map = maps.get_mut(map_id).ok_or(DatabaseError::MapNotFound)?;
+
+        if map.creator_id != user_id {
+            return Err(DatabaseError::MapOwnerOnly);
+        }
+
+        let revs = revisions.entry(map_id.to_string()).or_default();
+        let data = revs
+            .iter()
+            .find(|r| r.revision == revision)
+            .map(|r| r.data.clone())
+            .ok_or(DatabaseError::MapRevisionNotFound)?;
+
+        Self::push_map_revision(map, revs, user_id, data, revision);
+        Ok(map.clone())
+    }
+
+    /// Append a new immutable revision and make it current.
+    fn push_map_revision(
+        map: &mut StoredMap,
+        revs: &mut Vec<StoredMapRevision>,
+        author_id: &str,
+        data: String,
+        restored_from: u32,
+    ) {
+        let now = Utc::now();
+        map.revision += 1;
+        map.data.clone_from(&data);
+        map.updated_at = now;
+        revs.push(StoredMapRevision {
+            revision: map.revision,
+            author_id: author_id.to_string(),
+            data,
+            created_at: now,
+            restored_from,
+        });
+    }
+
     pub fn update_map(
         &self,
         map_id: &str,
@@ -231,6 +673,7 @@ impl Database {
         data: Option<&str>,
     ) -> Result<StoredMap, DatabaseError> {
         let mut maps = self.maps.write();
+        let mut revisions = self.map_revisions.write();
         let map = maps.get_mut(map_id).ok_or(DatabaseError::MapNotFound)?;
 
         if map.creator_id != user_id {
@@ -246,15 +689,28 @@ impl Database {
         if let Some(t) = tags {
             map.tags = t;
         }
-        if let Some(d) = data {
-            map.data = d.to_string();
-        }
         map.updated_at = Utc::now();
+        if let Some(d) = data
+            && d != map.data
+        {
+            let revs = revisions.entry(map_id.to_string()).or_default();
+            Self::push_map_revision(map, revs, user_id, d.to_string(), 0);
+        }
 
         Ok(map.clone())
     }
 
     pub fn delete_map(&self, map_id: &str, user_id: &str) -> Result<StoredMap, DatabaseError> {
+        self.remove_map(map_id, Some(user_id))
+    }
+
+    /// Delete a map regardless of owner. Still refused while an active room uses it.
+    pub fn admin_delete_map(&self, map_id: &str) -> Result<StoredMap, DatabaseError> {
+        self.remove_map(map_id, None)
+    }
+
+    /// Remove a map, checking ownership when `owner_id` is given.
+    fn remove_map(&self, map_id: &str, owner_id: Option<&str>) -> Result<StoredMap, DatabaseError> {
         // Check if map is in use by any active room
         {
             let rooms = self.rooms.read();
@@ -272,14 +728,25 @@ impl Database {
         let mut maps = self.maps.write();
         let map = maps.get(map_id).ok_or(DatabaseError::MapNotFound)?;
 
-        if map.creator_id != user_id {
+        if let Some(owner_id) = owner_id
+            && map.creator_id != owner_id
+        {
             return Err(DatabaseError::MapOwnerOnly);
         }
 
         let map = maps.remove(map_id).unwrap();
+        self.map_revisions.write().remove(map_id);
         Ok(map)
     }
 
+    pub fn set_map_hidden(&self, map_id: &str, hidden: bool) -> Result<StoredMap, DatabaseError> {
+        let mut maps = self.maps.write();
+        let map = maps.get_mut(map_id).ok_or(DatabaseError::MapNotFound)?;
+        map.hidden = hidden;
+        Ok(map.clone())
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn list_maps(
         &self,
         page_size: u32,
@@ -287,6 +754,8 @@ impl Database {
         creator_id: Option<&str>,
         name_query: Option<&str>,
         tags: &[String],
+        forked_from: Option<&str>,
+        sort: MapSort,
     ) -> (Vec<StoredMap>, String, u32) {
         let maps = self.maps.read();
         let page_size = page_size.clamp(1, 100) as usize;
@@ -294,6 +763,9 @@ impl Database {
         let mut filtered: Vec<&StoredMap> = maps
             .values()
             .filter(|m| {
+                if m.hidden {
+                    return false;
+                }
                 if let Some(cid) = creator_id
                     && m.creator_id != cid
                 {
@@ -308,24 +780,31 @@ impl Database {
                 if !tags.is_empty() && !tags.iter().all(|t| m.tags.contains(t)) {
                     return false;
                 }
+                if let Some(parent) = forked_from
+                    && m.parent_map_id.as_deref() != Some(parent)
+                {
+                    return false;
+                }
                 true
             })
             .collect();
 
         let total_count = u32::try_from(filtered.len()).unwrap_or(u32::MAX);
 
-        // Sort by created_at descending
-        filtered.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        // Sort by key descending, then map_id ascending for a total order
+        filtered.sort_by(|a, b| {
+            b.sort_key(sort)
+                .cmp(&a.sort_key(sort))
+                .then_with(|| a.map_id.cmp(&b.map_id))
+        });
 
-        // Apply cursor
-        let start = if page_token.is_empty() {
-            0
-        } else {
-            filtered
-                .iter()
-                .position(|m| m.map_id == page_token)
-                .map_or(0, |p| p + 1)
-        };
+        // Apply cursor: skip everything up to and including the last seen (key, map_id)
+        let start = decode_map_cursor(page_token).map_or(0, |(key, map_id)| {
+            filtered.partition_point(|m| {
+                let k = m.sort_key(sort);
+                k > key || (k == key && m.map_id.as_str() <= map_id)
+            })
+        });
 
         let page: Vec<StoredMap> = filtered
             .into_iter()
@@ -334,11 +813,38 @@ impl Database {
             .cloned()
             .collect();
 
-        let next_token = page.last().map(|m| m.map_id.clone()).unwrap_or_default();
+        let next_token = page
+            .last()
+            .map(|m| encode_map_cursor(m.sort_key(sort), &m.map_id))
+            .unwrap_or_default();
 
         (page, next_token, total_count)
     }
 
+    /// Like or unlike a map. Idempotent.
+    pub fn set_map_liked(
+        &self,
+        map_id: &str,
+        user_id: &str,
+        liked: bool,
+    ) -> Result<StoredMap, DatabaseError> {
+        let mut maps = self.maps.write();
+        let map = maps.get_mut(map_id).ok_or(DatabaseError::MapNotFound)?;
+        if liked {
+            map.liked_by.insert(user_id.to_string());
+        } else {
+            map.liked_by.remove(user_id);
+        }
+        Ok(map.clone())
+    }
+
+    fn increment_play_count(&self, map_id: &str) {
+        let mut maps = self.maps.write();
+        if let Some(map) = maps.get_mut(map_id) {
+            map.play_count = map.play_count.saturating_add(1);
+        }
+    }
+
     // ========================================
     // Avatar operations
     // ========================================
@@ -353,6 +859,83 @@ impl Database {
         avatars.get(user_id).cloned()
     }
 
+    /// Remove a user's avatar. Returns whether one was set.
+    pub fn reset_avatar(&self, user_id: &str) -> bool {
+        self.avatars.write().remove(user_id).is_some()
+    }
+
+    // ========================================
+    // Match history operations
+    // ========================================
+
+    fn record_match(&self, room: &Room) {
+        let stored = StoredMatch {
+            match_id: uuid::Uuid::new_v4().to_string(),
+            room_id: room.id().to_string(),
+            record: room.current_round_record(),
+            participants: room.participants_in_join_order(),
+        };
+        let mut matches = self.matches.write();
+        matches.push(stored);
+    }
+
+    /// Races a player participated in, newest first.
+    pub fn list_player_matches(
+        &self,
+        user_id: &str,
+        page_size: u32,
+        page_token: &str,
+    ) -> (Vec<StoredMatch>, String, u32) {
+        let matches = self.matches.read();
+        let page_size = page_size.clamp(1, 100) as usize;
+
+        let filtered: Vec<&StoredMatch> = matches
+            .iter()
+            .rev()
+            .filter(|m| m.has_participant(user_id))
+            .collect();
+
+        let total_count = u32::try_from(filtered.len()).unwrap_or(u32::MAX);
+
+        let start = if page_token.is_empty() {
+            0
+        } else {
+            filtered
+                .iter()
+                .position(|m| m.match_id == page_token)
+                .map_or(0, |p| p + 1)
+        };
+
+        let page: Vec<StoredMatch> = filtered
+            .into_iter()
+            .skip(start)
+            .take(page_size)
+            .cloned()
+            .collect();
+
+        let next_token = page.last().map(|m| m.match_id.clone()).unwrap_or_default();
+
+        (page, next_token, total_count)
+    }
+
+    pub fn get_player_matches(&self, user_id: &str) -> Vec<StoredMatch> {
+        let matches = self.matches.read();
+        matches
+            .iter()
+            .filter(|m| m.has_participant(user_id))
+            .cloned()
+            .collect()
+    }
+
+    pub fn get_map_matches(&self, map_id: &str) -> Vec<StoredMatch> {
+        let matches = self.matches.read();
+        matches
+            .iter()
+            .filter(|m| m.record.map_id == map_id)
+            .cloned()
+            .collect()
+    }
+
     // ========================================
     // Room operations
     // ========================================
@@ -362,45 +945,215 @@ impl Database {
         rooms.get(room_id).cloned()
     }
 
-    pub fn add_room(&self, room: Room) {
+    /// Counts for the metrics endpoint.
+    pub fn stats(&self) -> DatabaseStats {
+        let mut stats = DatabaseStats {
+            users: self.users.read().len(),
+            maps: self.maps.read().len(),
+            map_revisions: self.map_revisions.read().values().map(Vec::len).sum(),
+            avatars: self.avatars.read().len(),
+            matches: self.matches.read().len(),
+            topology_churn: *self.closed_rooms_churn.read(),
+            ..DatabaseStats::default()
+        };
+
+        let rooms = self.rooms.read();
+        for room in rooms.values() {
+            *stats.rooms_by_state.entry(room.state()).or_default() += 1;
+            for user in room.get_room_users() {
+                *stats.members_by_role.entry(user.role()).or_default() += 1;
+            }
+            let topology = room.topology_manager();
+            stats.room_topologies.push(RoomTopologyStats {
+                room_id: *room.id(),
+                mesh_groups: topology.group_count(),
+                bridges: topology.bridge_count(),
+            });
+            stats.topology_churn += topology.churn();
+        }
+        stats
+    }
+
+    /// Number of rooms hosted by `user_id` that have not ended.
+    pub fn count_open_rooms_hosted_by(&self, user_id: &str) -> usize {
+        let rooms = self.rooms.read();
+        rooms
+            .values()
+            .filter(|r| r.host_user_id() == user_id && r.state() != RoomState::Ended)
+            .count()
+    }
+
+    pub fn add_room(&self, mut room: Room) {
         let mut rooms = self.rooms.write();
+        // Invite codes must resolve to exactly one room
+        while rooms
+            .values()
+            .any(|r| r.invite_code() == room.invite_code())
+        {
+            room.set_invite_code(generate_invite_code());
+        }
         rooms.insert(*room.id(), room);
     }
 
+    /// Room whose invite code is `code` (already normalized).
+    pub fn find_room_by_invite_code(&self, code: &str) -> Option<uuid::Uuid> {
+        let rooms = self.rooms.read();
+        rooms
+            .values()
+            .find(|r| r.invite_code() == code)
+            .map(|r| *r.id())
+    }
+
+    /// End and drop a room. Watchers see a final ENDED state, then their streams close.
+    pub fn force_close_room(&self, room_id: &uuid::Uuid) -> Result<Room, DatabaseError> {
+        let mut rooms = self.rooms.write();
+        let room = rooms.remove(room_id).ok_or(DatabaseError::RoomNotFound)?;
+        *self.closed_rooms_churn.write() += room.topology_manager().churn();
+        room.close();
+        Ok(room)
+    }
+
     pub fn join_room(
         &self,
         room_id: &uuid::Uuid,
         user_id: String,
         role: Option<RoomRole>,
+        credentials: JoinCredentials,
     ) -> Result<(Room, PeerTopology), DatabaseError> {
         let mut rooms = self.rooms.write();
         let room = rooms.get_mut(room_id).ok_or(DatabaseError::RoomNotFound)?;
-        let topology = room.add_user(user_id, role)?;
+        let topology = room.add_user(user_id, role, credentials)?;
         Ok((room.clone(), topology))
     }
 
+    pub fn create_invite(
+        &self,
+        room_id: &uuid::Uuid,
+        user_id: &str,
+        ttl: Option<chrono::Duration>,
+        max_uses: u32,
+    ) -> Result<InviteToken, DatabaseError> {
+        let mut rooms = self.rooms.write();
+        let room = rooms.get_mut(room_id).ok_or(DatabaseError::RoomNotFound)?;
+        Ok(room.create_invite(user_id, ttl, max_uses)?)
+    }
+
+    pub fn list_invites(
+        &self,
+        room_id: &uuid::Uuid,
+        user_id: &str,
+    ) -> Result<Vec<InviteToken>, DatabaseError> {
+        let rooms = self.rooms.read();
+        let room = rooms.get(room_id).ok_or(DatabaseError::RoomNotFound)?;
+        Ok(room.invites(user_id)?)
+    }
+
+    pub fn revoke_invite(
+        &self,
+        room_id: &uuid::Uuid,
+        user_id: &str,
+        token: &str,
+    ) -> Result<InviteToken, DatabaseError> {
+        let mut rooms = self.rooms.write();
+        let room = rooms.get_mut(room_id).ok_or(DatabaseError::RoomNotFound)?;
+        Ok(room.revoke_invite(user_id, token)?)
+    }
+
     pub fn kick_user(
         &self,
         room_id: &uuid::Uuid,
         host_user_id: &str,
         target_user_id: &str,
+        ban: bool,
     ) -> Result<Room, DatabaseError> {
         let mut rooms = self.rooms.write();
         let room = rooms.get_mut(room_id).ok_or(DatabaseError::RoomNotFound)?;
         room.assert_host(host_user_id, "kick_user")?;
-        room.kick_user(target_user_id)?;
+        self.kick_from(room, target_user_id, ban)?;
+        Ok(room.clone())
+    }
+
+    /// Kick a member, recording the match if that finished the running round.
+    fn kick_from(&self, room: &mut Room, target_user_id: &str, ban: bool) -> Result<(), RoomError> {
+        let was_ended = room.state() == RoomState::Ended;
+        room.kick_user(target_user_id, ban)?;
+        if !was_ended && room.state() == RoomState::Ended {
+            self.record_match(room);
+        }
+        Ok(())
+    }
+
+    pub fn update_room(
+        &self,
+        room_id: &uuid::Uuid,
+        user_id: &str,
+        update: RoomUpdate,
+    ) -> Result<Room, DatabaseError> {
+        let mut rooms = self.rooms.write();
+        let room = rooms.get_mut(room_id).ok_or(DatabaseError::RoomNotFound)?;
+        room.update(user_id, update)?;
         Ok(room.clone())
     }
 
+    pub fn unban_user(
+        &self,
+        room_id: &uuid::Uuid,
+        host_user_id: &str,
+        target_user_id: &str,
+    ) -> Result<Room, DatabaseError> {
+        let mut rooms = self.rooms.write();
+        let room = rooms.get_mut(room_id).ok_or(DatabaseError::RoomNotFound)?;
+        room.unban_user(host_user_id, target_user_id)?;
+        Ok(room.clone())
+    }
+
+    pub fn change_role(
+        &self,
+        room_id: &uuid::Uuid,
+        user_id: &str,
+        role: RoomRole,
+    ) -> Result<(Room, PeerTopology), DatabaseError> {
+        let mut rooms = self.rooms.write();
+        let room = rooms.get_mut(room_id).ok_or(DatabaseError::RoomNotFound)?;
+
+        if !room.has_member(user_id) {
+            return Err(DatabaseError::NotRoomMember);
+        }
+
+        let topology = room.change_role(user_id, role)?;
+        Ok((room.clone(), topology))
+    }
+
+    pub fn set_readiness(
+        &self,
+        room_id: &uuid::Uuid,
+        user_id: &str,
+        readiness: Readiness,
+    ) -> Result<RoomUser, DatabaseError> {
+        let mut rooms = self.rooms.write();
+        let room = rooms.get_mut(room_id).ok_or(DatabaseError::RoomNotFound)?;
+
+        if !room.has_member(user_id) {
+            return Err(DatabaseError::NotRoomMember);
+        }
+
+        let user = room.set_readiness(user_id, readiness)?;
+        Ok(user)
+    }
+
     pub fn start_game(
         &self,
         room_id: &uuid::Uuid,
         user_id: &str,
         start_frame: u64,
+        force: bool,
     ) -> Result<(bool, Room), DatabaseError> {
         let mut rooms = self.rooms.write();
         let room = rooms.get_mut(room_id).ok_or(DatabaseError::RoomNotFound)?;
-        let newly_started = room.start_game(user_id, start_frame)?;
+        let newly_started = room.start_game(user_id, start_frame, force)?;
+        if newly_started {
+            self.increment_play_count(room.map_id());
+        }
         Ok((newly_started, room.clone()))
     }
 
@@ -414,11 +1167,27 @@ impl Database {
     ) -> Result<(bool, Room), DatabaseError> {
         let mut rooms = self.rooms.write();
         let room = rooms.get_mut(room_id).ok_or(DatabaseError::RoomNotFound)?;
+        let was_ended = room.state() == RoomState::Ended;
         let game_ended =
             room.report_arrival(user_id, arrived_user_id, arrival_frame, rank)?;
+        if game_ended && !was_ended {
+            self.record_match(room);
+        }
         Ok((game_ended, room.clone()))
     }
 
+    pub fn reset_room(
+        &self,
+        room_id: &uuid::Uuid,
+        user_id: &str,
+        map: Option<(String, u32)>,
+    ) -> Result<Room, DatabaseError> {
+        let mut rooms = self.rooms.write();
+        let room = rooms.get_mut(room_id).ok_or(DatabaseError::RoomNotFound)?;
+        room.reset(user_id, map)?;
+        Ok(room.clone())
+    }
+
     pub fn report_connection(
         &self,
         room_id: &uuid::Uuid,
@@ -471,7 +1240,7 @@ impl Database {
         &self,
         room_id: &uuid::Uuid,
         user_id: &str,
-    ) -> Result<Vec<(String, PeerTopology)>, DatabaseError> {
+    ) -> Result<(Vec<(String, PeerTopology)>, Vec<GroupLatency>), DatabaseError> {
         let rooms = self.rooms.read();
         let room = rooms.get(room_id).ok_or(DatabaseError::RoomNotFound)?;
 
@@ -479,7 +1248,35 @@ impl Database {
             return Err(DatabaseError::NotRoomMember);
         }
 
-        Ok(room.get_all_topologies())
+        Ok((
+            room.get_all_topologies(),
+            room.topology_manager().group_latencies(),
+        ))
+    }
+
+    /// Snapshot the room for `user_id` and subscribe to its live events.
+    ///
+    /// Both happen under the same lock so no event falls between the snapshot and the stream.
+    pub fn watch_room(
+        &self,
+        room_id: &uuid::Uuid,
+        user_id: &str,
+    ) -> Result<(RoomEvent, broadcast::Receiver<RoomEvent>), DatabaseError> {
+        let rooms = self.rooms.read();
+        let room = rooms.get(room_id).ok_or(DatabaseError::RoomNotFound)?;
+
+        if !room.has_member(user_id) {
+            return Err(DatabaseError::NotRoomMember);
+        }
+
+        let snapshot = RoomEvent {
+            event: Some(room_event::Event::Snapshot(RoomSnapshot {
+                room: Some(room.to_room_info()),
+                users: room.get_room_users(),
+                topology: room.get_topology(user_id),
+            })),
+        };
+        Ok((snapshot, room.subscribe()))
     }
 
     pub fn resolve_peer_ids(
@@ -498,6 +1295,7 @@ impl Database {
         Ok(room.resolve_peer_ids(peer_ids))
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn list_rooms(
         &self,
         page_size: u32,
@@ -506,6 +1304,7 @@ impl Database {
         map_id: Option<&str>,
         name_query: Option<&str>,
         has_available_slots: bool,
+        include_private: bool,
     ) -> (Vec<Room>, String, u32) {
         let rooms = self.rooms.read();
         let page_size = page_size.clamp(1, 100) as usize;
@@ -513,7 +1312,7 @@ impl Database {
         let mut filtered: Vec<&Room> = rooms
             .values()
             .filter(|r| {
-                if !r.is_public() {
+                if !include_private && !r.is_public() {
                     return false;
                 }
                 if !states.is_empty() && !states.contains(&r.state()) {
@@ -575,3 +1374,279 @@ impl Database {
         (page, next_token, total_count)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use marble_proto::room::{MemberLeft, StateChanged};
+
+    use super::*;
+    use crate::config::TopologySettings;
+
+    #[test]
+    fn test_list_maps_cursor_survives_counter_changes() {
+        let db = Database::new();
+        let ids: Vec<String> = (0..4)
+            .map(|i| {
+                db.create_map("creator", &format!("map{i}"), "", vec![], "{}")
+                    .map_id
+            })
+            .collect();
+
+        // map0 has 3 likes, map1 has 2, map2 has 1, map3 has none
+        for (i, id) in ids.iter().enumerate() {
+            for u in 0..(3 - i) {
+                db.set_map_liked(id, &format!("user{u}"), true).unwrap();
+            }
+        }
+
+        let (page1, token, total) = db.list_maps(2, "", None, None, &[], None, MapSort::MostLiked);
+        assert_eq!(total, 4);
+        assert_eq!(page1[0].map_id, ids[0]);
+        assert_eq!(page1[1].map_id, ids[1]);
+
+        // A map from the first page gains likes before the second page is fetched
+        db.set_map_liked(&ids[1], "user9", true).unwrap();
+
+        let (page2, _, _) = db.list_maps(2, &token, None, None, &[], None, MapSort::MostLiked);
+        let page2_ids: Vec<&str> = page2.iter().map(|m| m.map_id.as_str()).collect();
+        assert_eq!(page2_ids, vec![ids[2].as_str(), ids[3].as_str()]);
+    }
+
+    #[test]
+    fn test_play_count_incremented_on_start() {
+        let db = Database::new();
+        let map = db.create_map("host", "map", "", vec![], "{}");
+        let room_id = uuid::Uuid::new_v4();
+        db.add_room(Room::new(
+            room_id,
+            "Room".to_string(),
+            map.map_id.clone(),
+            4,
+            true,
+            "host".to_string(),
+            "ws://localhost:3000/signaling".to_string(),
+            TopologySettings::default().for_room(4),
+        ));
+
+        db.start_game(&room_id, "host", 0, false).unwrap();
+        // Idempotent start does not count twice
+        db.start_game(&room_id, "host", 0, false).unwrap();
+
+        assert_eq!(db.get_map(&map.map_id).unwrap().play_count, 1);
+    }
+
+    #[test]
+    fn test_map_revisions_are_immutable() {
+        let db = Database::new();
+        let map = db.create_map("owner", "map", "", vec![], "{\"v\":1}");
+        assert_eq!(map.revision, 1);
+
+        let updated = db
+            .update_map(&map.map_id, "owner", None, None, None, Some("{\"v\":2}"))
+            .unwrap();
+        assert_eq!(updated.revision, 2);
+
+        // Same data does not create a revision
+        let unchanged = db
+            .update_map(
+                &map.map_id,
+                "owner",
+                Some("renamed"),
+                None,
+                None,
+                Some("{\"v\":2}"),
+            )
+            .unwrap();
+        assert_eq!(unchanged.revision, 2);
+
+        let old = db.get_map_at_revision(&map.map_id, 1).unwrap();
+        assert_eq!(old.data, "{\"v\":1}");
+
+        assert!(matches!(
+            db.restore_map_revision(&map.map_id, "intruder", 1),
+            Err(DatabaseError::MapOwnerOnly)
+        ));
+        let restored = db.restore_map_revision(&map.map_id, "owner", 1).unwrap();
+        assert_eq!(restored.revision, 3);
+        assert_eq!(restored.data, "{\"v\":1}");
+
+        let (revs, _, total) = db.list_map_revisions(&map.map_id, 20, "").unwrap();
+        assert_eq!(total, 3);
+        assert_eq!(revs[0].revision, 3);
+        assert_eq!(revs[0].restored_from, 1);
+        assert_eq!(revs[2].revision, 1);
+    }
+
+    #[test]
+    fn test_fork_lineage() {
+        let db = Database::new();
+        let original = db.create_map("alice", "original", "", vec![], "{\"v\":1}");
+        db.update_map(
+            &original.map_id,
+            "alice",
+            None,
+            None,
+            None,
+            Some("{\"v\":2}"),
+        )
+        .unwrap();
+
+        let fork = db.fork_map(&original.map_id, Some(1), "bob", None).unwrap();
+        assert_eq!(fork.creator_id, "bob");
+        assert_eq!(fork.name, "original");
+        assert_eq!(fork.data, "{\"v\":1}");
+        assert_eq!(fork.revision, 1);
+        assert_eq!(
+            fork.parent_map_id.as_deref(),
+            Some(original.map_id.as_str())
+        );
+        assert_eq!(fork.parent_revision, 1);
+
+        let remix = db
+            .fork_map(&fork.map_id, None, "carol", Some("remix"))
+            .unwrap();
+        let lineage = db.map_lineage(&remix);
+        assert_eq!(lineage.len(), 2);
+        assert_eq!(lineage[0].map_id, fork.map_id);
+        assert_eq!(lineage[1].map_id, original.map_id);
+        assert_eq!(lineage[1].creator_id, "alice");
+
+        let (forks, _, total) = db.list_maps(
+            20,
+            "",
+            None,
+            None,
+            &[],
+            Some(&original.map_id),
+            MapSort::Newest,
+        );
+        assert_eq!(total, 1);
+        assert_eq!(forks[0].map_id, fork.map_id);
+
+        // Deleted ancestors keep their id but lose name and creator
+        db.delete_map(&fork.map_id, "bob").unwrap();
+        let lineage = db.map_lineage(&remix);
+        assert_eq!(lineage.len(), 1);
+        assert_eq!(lineage[0].map_id, fork.map_id);
+        assert!(lineage[0].creator_id.is_empty());
+    }
+
+    #[test]
+    fn test_ban_and_audit_log() {
+        let db = Database::new();
+        let (user, _) = db.find_or_create_anonymous_user("troll", "salt", "fp");
+
+        assert!(matches!(
+            db.ban_user("missing"),
+            Err(DatabaseError::UserNotFound)
+        ));
+        db.ban_user(&user.user_id).unwrap();
+        assert!(db.is_banned(&user.user_id));
+        db.record_audit("admin", "BanUser", &user.user_id, "spam");
+
+        assert!(db.unban_user(&user.user_id));
+        assert!(!db.unban_user(&user.user_id));
+        db.record_audit("admin", "UnbanUser", &user.user_id, "");
+
+        // Newest first, paged by entry_id
+        let (page1, token, total) = db.list_audit_log(1, "");
+        assert_eq!(total, 2);
+        assert_eq!(page1[0].action, "UnbanUser");
+        let (page2, _, _) = db.list_audit_log(1, &token);
+        assert_eq!(page2[0].action, "BanUser");
+        assert_eq!(page2[0].reason, "spam");
+    }
+
+    #[test]
+    fn test_hidden_map_is_unlisted_and_unforkable() {
+        let db = Database::new();
+        let map = db.create_map("owner", "map", "", vec![], "{}");
+        db.set_map_hidden(&map.map_id, true).unwrap();
+
+        let (maps, _, total) = db.list_maps(10, "", None, None, &[], None, MapSort::Newest);
+        assert!(maps.is_empty());
+        assert_eq!(total, 0);
+        assert!(matches!(
+            db.fork_map(&map.map_id, None, "other", None),
+            Err(DatabaseError::MapNotFound)
+        ));
+
+        // Admin deletion skips the owner check
+        db.admin_delete_map(&map.map_id).unwrap();
+        assert!(db.get_map(&map.map_id).is_none());
+    }
+
+    #[test]
+    fn test_ban_removes_user_from_rooms() {
+        let db = Database::new();
+        let (user, _) = db.find_or_create_anonymous_user("troll", "salt", "fp");
+        let new_room = |host: &str| {
+            let room_id = uuid::Uuid::new_v4();
+            db.add_room(Room::new(
+                room_id,
+                "Room".to_string(),
+                String::new(),
+                4,
+                true,
+                host.to_string(),
+                "ws://localhost:3000/signaling".to_string(),
+                TopologySettings::default().for_room(4),
+            ));
+            room_id
+        };
+        let joined = new_room("host");
+        let hosted = new_room(&user.user_id);
+        db.join_room(&joined, user.user_id.clone(), None, JoinCredentials::default())
+            .unwrap();
+        let (_, mut events) = db.watch_room(&joined, "host").unwrap();
+
+        db.ban_user(&user.user_id).unwrap();
+
+        // Kicked where a member, so their streams and sockets close
+        let room = db.get_room(&joined).unwrap();
+        assert!(!room.has_member(&user.user_id));
+        assert!(matches!(
+            events.try_recv().unwrap().event,
+            Some(room_event::Event::MemberLeft(MemberLeft { ref user_id, kicked: true }))
+                if *user_id == user.user_id
+        ));
+        // Rooms they host are closed
+        assert!(db.get_room(&hosted).is_none());
+    }
+
+    #[test]
+    fn test_force_close_room_notifies_watchers() {
+        let db = Database::new();
+        let room_id = uuid::Uuid::new_v4();
+        db.add_room(Room::new(
+            room_id,
+            "Private".to_string(),
+            String::new(),
+            4,
+            false,
+            "host".to_string(),
+            "ws://localhost:3000/signaling".to_string(),
+            TopologySettings::default().for_room(4),
+        ));
+
+        let (rooms, _, _) = db.list_rooms(10, "", &[], None, None, false, false);
+        assert!(rooms.is_empty());
+        let (rooms, _, _) = db.list_rooms(10, "", &[], None, None, false, true);
+        assert_eq!(rooms.len(), 1);
+
+        let (_, mut events) = db.watch_room(&room_id, "host").unwrap();
+        db.force_close_room(&room_id).unwrap();
+
+        let event = events.try_recv().unwrap();
+        assert!(matches!(
+            event.event,
+            Some(room_event::Event::StateChanged(StateChanged { state, .. }))
+                if state == i32::from(RoomState::Ended)
+        ));
+        assert!(matches!(
+            events.try_recv(),
+            Err(broadcast::error::TryRecvError::Closed)
+        ));
+        assert!(db.get_room(&room_id).is_none());
+    }
+}
@@ -0,0 +1,68 @@
+use marble_core::{MapLimits, RouletteConfig};
+use tonic::Status;
+
+use crate::util;
+
+/// Parse and validate uploaded map data.
+///
+/// Field paths in the returned status are prefixed with `data.` so clients can
+/// point at the offending part of the document.
+pub fn validate_map_data(data: &str, limits: &MapLimits) -> Result<RouletteConfig, Status> {
+    let config = RouletteConfig::from_json(data).map_err(|e| {
+        util::invalid_fields(
+            "map data is not a valid RouletteConfig",
+            [("data", e.to_string())],
+        )
+    })?;
+
+    let violations = config.validate(limits);
+    if violations.is_empty() {
+        return Ok(config);
+    }
+
+    Err(util::invalid_fields(
+        format!("map data has {} problem(s)", violations.len()),
+        violations
+            .into_iter()
+            .map(|v| (format!("data.{}", v.field), v.description)),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use tonic_types::StatusExt;
+
+    use super::*;
+
+    #[test]
+    fn test_valid_map_is_accepted() {
+        let data = RouletteConfig::default_classic().to_json().unwrap();
+        assert!(validate_map_data(&data, &MapLimits::default()).is_ok());
+    }
+
+    #[test]
+    fn test_violations_are_reported_per_field() {
+        let status = validate_map_data(
+            "{\"meta\":{\"name\":\"x\"},\"objects\":[]}",
+            &MapLimits::default(),
+        )
+        .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+        let bad_request = status.get_details_bad_request().unwrap();
+        assert!(
+            bad_request
+                .field_violations
+                .iter()
+                .all(|v| v.field == "data.objects")
+        );
+        assert_eq!(bad_request.field_violations.len(), 3);
+    }
+
+    #[test]
+    fn test_malformed_json() {
+        let status = validate_map_data("{", &MapLimits::default()).unwrap_err();
+        let bad_request = status.get_details_bad_request().unwrap();
+        assert_eq!(bad_request.field_violations[0].field, "data");
+    }
+}
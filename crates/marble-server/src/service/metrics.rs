@@ -0,0 +1,504 @@
+//! Prometheus metrics in the text exposition format.
+//!
+//! Counters and histograms are recorded as events happen; room, member and
+//! table gauges are read from the [`Database`] at scrape time.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::task::{Context, Poll, ready};
+use std::time::{Duration, Instant};
+
+use http_body::{Body, Frame, SizeHint};
+use marble_proto::room::{RoomRole, RoomState};
+use parking_lot::Mutex;
+use tower::{Layer, Service};
+
+use super::database::Database;
+
+/// Upper bounds of the gRPC latency histogram in seconds.
+const LATENCY_BUCKETS: [f64; 12] = [
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 10.0,
+];
+
+#[derive(Debug, Default)]
+struct Histogram {
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        for (bucket, bound) in self.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+        self.sum += seconds;
+        self.count += 1;
+    }
+}
+
+#[derive(Debug, Default)]
+struct MetricsInner {
+    /// (method, code) -> latency histogram
+    grpc: Mutex<HashMap<(String, tonic::Code), Histogram>>,
+    signaling_connects: AtomicU64,
+    signaling_disconnects: AtomicU64,
+    relay_connections: AtomicU64,
+    relay_frames: AtomicU64,
+    ready: AtomicBool,
+}
+
+/// Process-wide metrics registry (clones share state).
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    inner: Arc<MetricsInner>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_grpc(&self, method: &str, code: tonic::Code, elapsed: Duration) {
+        // Unknown methods would otherwise create unbounded label values
+        let method = if code == tonic::Code::Unimplemented {
+            "other"
+        } else {
+            method
+        };
+        self.inner
+            .grpc
+            .lock()
+            .entry((method.to_string(), code))
+            .or_default()
+            .observe(elapsed.as_secs_f64());
+    }
+
+    pub fn signaling_connected(&self) {
+        self.inner
+            .signaling_connects
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn signaling_disconnected(&self) {
+        self.inner
+            .signaling_disconnects
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn relay_connected(&self) {
+        self.inner.relay_connections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn relay_disconnected(&self) {
+        self.inner.relay_connections.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// Count frames queued to relay clients
+    pub fn relay_frames_forwarded(&self, frames: usize) {
+        self.inner
+            .relay_frames
+            .fetch_add(frames as u64, Ordering::Relaxed);
+    }
+
+    /// Mark the server as ready to accept traffic (`/readyz`).
+    pub fn set_ready(&self, ready: bool) {
+        self.inner.ready.store(ready, Ordering::Relaxed);
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.inner.ready.load(Ordering::Relaxed)
+    }
+
+    /// Render all metrics in the Prometheus text format.
+    pub fn render(&self, database: &Database) -> String {
+        let stats = database.stats();
+        let mut out = String::new();
+
+        header(&mut out, "marble_rooms", "gauge", "Rooms by state.");
+        for state in [RoomState::Waiting, RoomState::Playing, RoomState::Ended] {
+            let count = stats.rooms_by_state.get(&state).copied().unwrap_or(0);
+            let _ = writeln!(
+                out,
+                "marble_rooms{{state=\"{}\"}} {count}",
+                state.as_str_name()
+            );
+        }
+
+        header(
+            &mut out,
+            "marble_room_members",
+            "gauge",
+            "Room members by role.",
+        );
+        for role in [RoomRole::Participant, RoomRole::Spectator] {
+            let count = stats.members_by_role.get(&role).copied().unwrap_or(0);
+            let _ = writeln!(
+                out,
+                "marble_room_members{{role=\"{}\"}} {count}",
+                role.as_str_name()
+            );
+        }
+
+        header(
+            &mut out,
+            "marble_room_mesh_groups",
+            "gauge",
+            "Mesh groups per room.",
+        );
+        for room in &stats.room_topologies {
+            let _ = writeln!(
+                out,
+                "marble_room_mesh_groups{{room_id=\"{}\"}} {}",
+                room.room_id, room.mesh_groups
+            );
+        }
+
+        header(
+            &mut out,
+            "marble_room_bridges",
+            "gauge",
+            "Bridge nodes per room.",
+        );
+        for room in &stats.room_topologies {
+            let _ = writeln!(
+                out,
+                "marble_room_bridges{{room_id=\"{}\"}} {}",
+                room.room_id, room.bridges
+            );
+        }
+
+        header(
+            &mut out,
+            "marble_topology_recalculations_total",
+            "counter",
+            "Bridge recalculations across all rooms.",
+        );
+        let _ = writeln!(
+            out,
+            "marble_topology_recalculations_total {}",
+            stats.topology_churn.recalculations
+        );
+
+        header(
+            &mut out,
+            "marble_topology_migrations_total",
+            "counter",
+            "Players moved to another mesh group by rebalancing.",
+        );
+        let _ = writeln!(
+            out,
+            "marble_topology_migrations_total {}",
+            stats.topology_churn.migrations
+        );
+
+        self.render_grpc(&mut out);
+
+        header(
+            &mut out,
+            "marble_signaling_peer_connects_total",
+            "counter",
+            "Signaling peers connected.",
+        );
+        let _ = writeln!(
+            out,
+            "marble_signaling_peer_connects_total {}",
+            self.inner.signaling_connects.load(Ordering::Relaxed)
+        );
+        header(
+            &mut out,
+            "marble_signaling_peer_disconnects_total",
+            "counter",
+            "Signaling peers disconnected.",
+        );
+        let _ = writeln!(
+            out,
+            "marble_signaling_peer_disconnects_total {}",
+            self.inner.signaling_disconnects.load(Ordering::Relaxed)
+        );
+
+        header(
+            &mut out,
+            "marble_relay_connections",
+            "gauge",
+            "Open relay WebSocket connections.",
+        );
+        let _ = writeln!(
+            out,
+            "marble_relay_connections {}",
+            self.inner.relay_connections.load(Ordering::Relaxed)
+        );
+        header(
+            &mut out,
+            "marble_relay_frames_total",
+            "counter",
+            "P2P frames forwarded by the relay.",
+        );
+        let _ = writeln!(
+            out,
+            "marble_relay_frames_total {}",
+            self.inner.relay_frames.load(Ordering::Relaxed)
+        );
+
+        header(
+            &mut out,
+            "marble_db_records",
+            "gauge",
+            "Stored records by table.",
+        );
+        for (table, count) in [
+            ("users", stats.users),
+            ("maps", stats.maps),
+            ("map_revisions", stats.map_revisions),
+            ("avatars", stats.avatars),
+            ("matches", stats.matches),
+            ("rooms", stats.rooms_by_state.values().sum()),
+        ] {
+            let _ = writeln!(out, "marble_db_records{{table=\"{table}\"}} {count}");
+        }
+
+        out
+    }
+
+    fn render_grpc(&self, out: &mut String) {
+        let grpc = self.inner.grpc.lock();
+        let mut series: Vec<_> = grpc.iter().collect();
+        series.sort_by(|a, b| a.0.0.cmp(&b.0.0).then((a.0.1 as i32).cmp(&(b.0.1 as i32))));
+
+        header(
+            out,
+            "marble_grpc_requests_total",
+            "counter",
+            "gRPC calls by method and status code.",
+        );
+        for ((method, code), histogram) in &series {
+            let _ = writeln!(
+                out,
+                "marble_grpc_requests_total{{method=\"{method}\",code=\"{code:?}\"}} {}",
+                histogram.count
+            );
+        }
+
+        header(
+            out,
+            "marble_grpc_request_duration_seconds",
+            "histogram",
+            "gRPC call latency by method and status code.",
+        );
+        for ((method, code), histogram) in &series {
+            let labels = format!("method=\"{method}\",code=\"{code:?}\"");
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets) {
+                let _ = writeln!(
+                    out,
+                    "marble_grpc_request_duration_seconds_bucket{{{labels},le=\"{bound}\"}} {count}"
+                );
+            }
+            let _ = writeln!(
+                out,
+                "marble_grpc_request_duration_seconds_bucket{{{labels},le=\"+Inf\"}} {}",
+                histogram.count
+            );
+            let _ = writeln!(
+                out,
+                "marble_grpc_request_duration_seconds_sum{{{labels}}} {}",
+                histogram.sum
+            );
+            let _ = writeln!(
+                out,
+                "marble_grpc_request_duration_seconds_count{{{labels}}} {}",
+                histogram.count
+            );
+        }
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+/// `grpc-status` from response headers or trailers.
+fn grpc_status(headers: &http::HeaderMap) -> Option<tonic::Code> {
+    headers
+        .get("grpc-status")?
+        .to_str()
+        .ok()?
+        .parse::<i32>()
+        .ok()
+        .map(tonic::Code::from)
+}
+
+/// Tower layer recording gRPC call counts and latency into [`Metrics`].
+#[derive(Clone)]
+pub struct GrpcMetricsLayer {
+    metrics: Metrics,
+}
+
+impl GrpcMetricsLayer {
+    pub fn new(metrics: Metrics) -> Self {
+        Self { metrics }
+    }
+}
+
+impl<S> Layer<S> for GrpcMetricsLayer {
+    type Service = GrpcMetricsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        GrpcMetricsService {
+            inner,
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct GrpcMetricsService<S> {
+    inner: S,
+    metrics: Metrics,
+}
+
+impl<S, B, ResBody> Service<http::Request<B>> for GrpcMetricsService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<ResBody>>,
+    S::Future: Send + 'static,
+    S::Error: Send + 'static,
+    ResBody: Send + 'static,
+{
+    type Response = http::Response<MetricsBody<ResBody>>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let call = CallGuard {
+            metrics: self.metrics.clone(),
+            method: req.uri().path().to_string(),
+            started: Instant::now(),
+            code: None,
+        };
+        let future = self.inner.call(req);
+
+        Box::pin(async move {
+            let response = future.await?;
+            // Trailers-only responses (errors) carry the status in the headers
+            let mut call = call;
+            call.code = grpc_status(response.headers());
+            Ok(response.map(|body| MetricsBody {
+                inner: Box::pin(body),
+                call,
+            }))
+        })
+    }
+}
+
+/// Records the call when dropped, i.e. after the response body finished or the client went away.
+struct CallGuard {
+    metrics: Metrics,
+    method: String,
+    started: Instant,
+    code: Option<tonic::Code>,
+}
+
+impl Drop for CallGuard {
+    fn drop(&mut self) {
+        self.metrics.record_grpc(
+            &self.method,
+            self.code.unwrap_or(tonic::Code::Cancelled),
+            self.started.elapsed(),
+        );
+    }
+}
+
+/// Response body that picks up `grpc-status` from the trailers.
+pub struct MetricsBody<B> {
+    inner: Pin<Box<B>>,
+    call: CallGuard,
+}
+
+impl<B: Body> Body for MetricsBody<B> {
+    type Data = B::Data;
+    type Error = B::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let frame = ready!(self.inner.as_mut().poll_frame(cx));
+        if let Some(Ok(frame)) = &frame
+            && let Some(trailers) = frame.trailers_ref()
+            && let Some(code) = grpc_status(trailers)
+        {
+            self.call.code = Some(code);
+        }
+        Poll::Ready(frame)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::room::Room;
+    use crate::config::TopologySettings;
+
+    #[test]
+    fn test_histogram_buckets_are_cumulative() {
+        let mut histogram = Histogram::default();
+        histogram.observe(0.003);
+        histogram.observe(0.2);
+
+        assert_eq!(histogram.buckets[0], 0); // le 0.001
+        assert_eq!(histogram.buckets[2], 1); // le 0.005
+        assert_eq!(histogram.buckets[7], 2); // le 0.25
+        assert_eq!(histogram.count, 2);
+    }
+
+    #[test]
+    fn test_render() {
+        let database = Database::new();
+        let room = Room::new(
+            uuid::Uuid::new_v4(),
+            "room".to_string(),
+            String::new(),
+            8,
+            true,
+            "host".to_string(),
+            "ws://localhost/signaling".to_string(),
+            TopologySettings::default().for_room(8),
+        );
+        database.add_room(room);
+
+        let metrics = Metrics::new();
+        metrics.record_grpc(
+            "/marble.room.RoomService/CreateRoom",
+            tonic::Code::Ok,
+            Duration::from_millis(3),
+        );
+        metrics.signaling_connected();
+
+        let text = metrics.render(&database);
+        assert!(text.contains("marble_rooms{state=\"ROOM_STATE_WAITING\"} 1"));
+        assert!(text.contains("marble_room_members{role=\"ROOM_ROLE_PARTICIPANT\"} 1"));
+        assert!(text.contains(
+            "marble_grpc_requests_total{method=\"/marble.room.RoomService/CreateRoom\",code=\"Ok\"} 1"
+        ));
+        assert!(text.contains("marble_signaling_peer_connects_total 1"));
+        assert!(text.contains("marble_db_records{table=\"rooms\"} 1"));
+    }
+}
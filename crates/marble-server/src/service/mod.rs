@@ -1 +1,4 @@
 pub mod database;
+pub mod map_validation;
+pub mod metrics;
+pub mod thumbnail;
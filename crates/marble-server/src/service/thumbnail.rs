@@ -0,0 +1,196 @@
+//! Server-rendered SVG map previews.
+//!
+//! Every object is evaluated at t=0 and drawn as plain SVG primitives, so no
+//! GPU or image codec is needed. Revisions are immutable, which makes
+//! `(map_id, revision)` a stable cache key.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Arc;
+
+use marble_core::{EvaluatedShape, GameContext, MapObject, ObjectRole, RouletteConfig};
+use parking_lot::RwLock;
+
+use super::database::Database;
+
+/// Width of the generated image in pixels; height follows the map aspect ratio.
+const THUMBNAIL_WIDTH: f32 = 320.0;
+/// Margin around the map bounds in meters.
+const THUMBNAIL_PADDING: f32 = 0.5;
+
+/// Path under which the thumbnail of a map revision is served.
+pub fn thumbnail_url(map_id: &str, revision: u32) -> String {
+    format!("/thumbnails/{map_id}/{revision}")
+}
+
+/// Renders and caches thumbnails per map revision.
+#[derive(Clone)]
+pub struct ThumbnailCache {
+    database: Database,
+    images: Arc<RwLock<HashMap<(String, u32), Arc<str>>>>,
+    /// Upper bound on cached images before the cache is flushed
+    capacity: usize,
+}
+
+impl ThumbnailCache {
+    pub fn new(database: Database, capacity: usize) -> Self {
+        Self {
+            database,
+            images: Arc::new(RwLock::new(HashMap::new())),
+            capacity,
+        }
+    }
+
+    /// SVG for `map_id` at `revision`, or `None` if the map or revision doesn't exist.
+    pub fn get(&self, map_id: &str, revision: u32) -> Option<Arc<str>> {
+        // Check the database first so deleted and hidden maps stop being served
+        let map = self
+            .database
+            .get_map_at_revision(map_id, revision)
+            .ok()
+            .filter(|m| !m.hidden)?;
+
+        let key = (map_id.to_string(), revision);
+        if let Some(svg) = self.images.read().get(&key) {
+            return Some(Arc::clone(svg));
+        }
+
+        let config = RouletteConfig::from_json(&map.data).ok()?;
+        let svg: Arc<str> = render_svg(&config).into();
+
+        let mut images = self.images.write();
+        if images.len() >= self.capacity {
+            images.clear();
+        }
+        images.insert(key, Arc::clone(&svg));
+        Some(svg)
+    }
+}
+
+/// Stroke and fill for an object, or `None` if it isn't drawn.
+fn object_style(obj: &MapObject) -> Option<(&'static str, &'static str)> {
+    match obj.role {
+        ObjectRole::Obstacle if obj.properties.bumper.is_some() => Some(("#f5a623", "none")),
+        ObjectRole::Obstacle => Some(("#d8dee9", "none")),
+        ObjectRole::Spawner => Some(("#4caf50", "#4caf5040")),
+        ObjectRole::Trigger => Some(("#e53935", "#e5393540")),
+        ObjectRole::VectorField => Some(("#42a5f5", "#42a5f520")),
+        // Editor-only
+        ObjectRole::Guideline => None,
+    }
+}
+
+/// Render `config` at t=0 as a standalone SVG document.
+pub fn render_svg(config: &RouletteConfig) -> String {
+    let ctx = GameContext::new(0.0, 0);
+    let ((min_x, min_y), (max_x, max_y)) = config.calculate_bounds();
+    let min_x = min_x - THUMBNAIL_PADDING;
+    let max_y = max_y + THUMBNAIL_PADDING;
+    let width = (max_x + THUMBNAIL_PADDING - min_x).max(f32::EPSILON);
+    let height = (max_y - (min_y - THUMBNAIL_PADDING)).max(f32::EPSILON);
+    let scale = THUMBNAIL_WIDTH / width;
+
+    // World space is y-up, SVG is y-down
+    let point = |p: [f32; 2]| ((p[0] - min_x) * scale, (max_y - p[1]) * scale);
+
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w:.0}" height="{h:.0}" viewBox="0 0 {w:.2} {h:.2}"><rect width="100%" height="100%" fill="#1e2230"/>"#,
+        w = width * scale,
+        h = height * scale,
+    );
+
+    for obj in &config.objects {
+        let Some((stroke, fill)) = object_style(obj) else {
+            continue;
+        };
+        let style = format!(r#"stroke="{stroke}" fill="{fill}" stroke-width="2""#);
+
+        match obj.shape.evaluate(&ctx) {
+            EvaluatedShape::Line { start, end } => {
+                let (x1, y1) = point(start);
+                let (x2, y2) = point(end);
+                let _ = write!(
+                    svg,
+                    r#"<line x1="{x1:.2}" y1="{y1:.2}" x2="{x2:.2}" y2="{y2:.2}" {style} stroke-linecap="round"/>"#
+                );
+            }
+            EvaluatedShape::Circle { center, radius } => {
+                let (cx, cy) = point(center);
+                let r = radius.abs() * scale;
+                let _ = write!(
+                    svg,
+                    r#"<circle cx="{cx:.2}" cy="{cy:.2}" r="{r:.2}" {style}/>"#
+                );
+            }
+            EvaluatedShape::Rect {
+                center,
+                size,
+                rotation,
+            } => {
+                let (cx, cy) = point(center);
+                let w = size[0].abs() * scale;
+                let h = size[1].abs() * scale;
+                // Counter-clockwise in world space is clockwise after the y flip
+                let _ = write!(
+                    svg,
+                    r#"<rect x="{x:.2}" y="{y:.2}" width="{w:.2}" height="{h:.2}" transform="rotate({r:.2} {cx:.2} {cy:.2})" {style}/>"#,
+                    x = cx - w / 2.0,
+                    y = cy - h / 2.0,
+                    r = -rotation,
+                );
+            }
+            shape @ EvaluatedShape::Bezier { .. } => {
+                let points = shape.bezier_to_points().unwrap_or_default();
+                let mut coords = String::new();
+                for p in points {
+                    let (x, y) = point(p);
+                    let _ = write!(coords, "{x:.2},{y:.2} ");
+                }
+                let _ = write!(
+                    svg,
+                    r#"<polyline points="{}" {style} fill-opacity="0" stroke-linecap="round"/>"#,
+                    coords.trim_end()
+                );
+            }
+        }
+    }
+
+    svg.push_str("</svg>");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_default_map() {
+        let config = RouletteConfig::default_classic();
+        let svg = render_svg(&config);
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.ends_with("</svg>"));
+        // Trigger and spawner colors are present
+        assert!(svg.contains("#e53935"));
+        assert!(svg.contains("#4caf50"));
+    }
+
+    #[test]
+    fn test_cache_per_revision() {
+        let database = Database::new();
+        let data = RouletteConfig::default_classic().to_json().unwrap();
+        let map = database.create_map("owner", "Map", "", vec![], &data);
+        let cache = ThumbnailCache::new(database.clone(), 16);
+
+        let first = cache.get(&map.map_id, 1).unwrap();
+        let second = cache.get(&map.map_id, 1).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+
+        assert!(cache.get(&map.map_id, 2).is_none());
+
+        database.delete_map(&map.map_id, "owner").unwrap();
+        assert!(cache.get(&map.map_id, 1).is_none());
+    }
+}
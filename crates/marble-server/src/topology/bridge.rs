@@ -1,10 +1,19 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+/// Weight of RTT jitter relative to the mean RTT in the quality score
+const JITTER_WEIGHT: f32 = 2.0;
+/// Score advantage (fraction of the incumbent's score) a challenger needs to replace a bridge
+const HYSTERESIS_MARGIN: f32 = 0.25;
+/// Standby bridges kept per group to take over from a failed bridge
+const STANDBY_PER_GROUP: usize = 1;
 
 /// Player connection quality score
 #[derive(Debug, Clone, Default)]
 pub struct ConnectionQuality {
     /// Average RTT in milliseconds
     pub avg_rtt_ms: f32,
+    /// Average deviation of RTT samples from `avg_rtt_ms` in milliseconds
+    pub rtt_jitter_ms: f32,
     /// Packet loss rate (0.0 ~ 1.0)
     pub packet_loss: f32,
     /// Connection stability (number of successful reports)
@@ -15,9 +24,9 @@ impl ConnectionQuality {
     /// Calculate overall quality score (higher is better)
     #[allow(clippy::cast_precision_loss)]
     pub fn score(&self) -> f32 {
-        // Lower RTT and packet loss = better score
+        // Lower RTT, jitter and packet loss = better score
         // Higher stability = better score
-        let rtt_score = 1000.0 / (self.avg_rtt_ms + 1.0);
+        let rtt_score = 1000.0 / (self.avg_rtt_ms + JITTER_WEIGHT * self.rtt_jitter_ms + 1.0);
         let loss_score = 1.0 - self.packet_loss;
         let stability = self.stability_score.min(100) as f32 / 100.0;
 
@@ -27,8 +36,10 @@ impl ConnectionQuality {
     /// Update with new measurement
     #[allow(clippy::cast_precision_loss)]
     pub fn update(&mut self, rtt_ms: u32, packet_loss: f32, connected: bool) {
-        // Exponential moving average for RTT
-        self.avg_rtt_ms = self.avg_rtt_ms * 0.7 + rtt_ms as f32 * 0.3;
+        // Exponential moving averages for RTT and its deviation
+        let rtt_ms = rtt_ms as f32;
+        self.rtt_jitter_ms = self.rtt_jitter_ms * 0.7 + (rtt_ms - self.avg_rtt_ms).abs() * 0.3;
+        self.avg_rtt_ms = self.avg_rtt_ms * 0.7 + rtt_ms * 0.3;
 
         // Exponential moving average for packet loss
         self.packet_loss = self.packet_loss * 0.7 + packet_loss * 0.3;
@@ -42,6 +53,14 @@ impl ConnectionQuality {
     }
 }
 
+/// Result of `BridgeSelector::select_bridges` for one group
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BridgeSelection {
+    pub bridges: HashSet<String>,
+    /// Best non-bridge players, in takeover order
+    pub standby: Vec<String>,
+}
+
 /// Bridge node selector based on connection quality
 #[derive(Debug, Clone)]
 pub struct BridgeSelector {
@@ -75,28 +94,69 @@ impl BridgeSelector {
         quality.update(rtt_ms, packet_loss, connected);
     }
 
-    /// Select best bridge candidates from a group
-    pub fn select_bridges(&self, player_ids: &[String]) -> Vec<String> {
-        let mut scored_players: Vec<(String, f32)> = player_ids
+    /// Player's quality score; new players get a neutral default
+    fn score_of(&self, player_id: &str) -> f32 {
+        self.player_qualities
+            .get(player_id)
+            .map_or(0.5, ConnectionQuality::score)
+    }
+
+    /// Select bridges and standby bridges from a group.
+    ///
+    /// Incumbent bridges keep their role unless a challenger beats them by
+    /// `HYSTERESIS_MARGIN`, so small score changes do not make every group reconnect.
+    /// Standbys are the best remaining players, in takeover order.
+    pub fn select_bridges(
+        &self,
+        player_ids: &[String],
+        incumbents: &HashSet<String>,
+    ) -> BridgeSelection {
+        let mut ranked: Vec<(&String, f32)> = player_ids
             .iter()
-            .map(|pid| {
-                let score = self
-                    .player_qualities
-                    .get(pid)
-                    .map_or(0.5, ConnectionQuality::score); // Default score for new players
-                (pid.clone(), score)
-            })
+            .map(|pid| (pid, self.score_of(pid)))
             .collect();
+        // Best first; ids break ties so the result is deterministic
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
 
-        // Sort by score descending
-        scored_players.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
-
-        // Take top N as bridges
-        scored_players
-            .into_iter()
+        let mut bridges: Vec<(&String, f32)> = ranked
+            .iter()
+            .filter(|(pid, _)| incumbents.contains(*pid))
             .take(self.bridges_per_group)
-            .map(|(pid, _)| pid)
-            .collect()
+            .copied()
+            .collect();
+        let kept: HashSet<&String> = bridges.iter().map(|(pid, _)| *pid).collect();
+        let mut challengers = ranked
+            .iter()
+            .filter(|(pid, _)| !kept.contains(pid))
+            .copied()
+            .peekable();
+
+        // Fill vacancies, then replace the weakest incumbents while a challenger clearly wins
+        while bridges.len() < self.bridges_per_group
+            && let Some(challenger) = challengers.next()
+        {
+            bridges.push(challenger);
+        }
+        bridges.sort_by(|a, b| a.1.total_cmp(&b.1));
+        for weakest in &mut bridges {
+            match challengers.peek() {
+                Some(&(_, score)) if score > weakest.1 * (1.0 + HYSTERESIS_MARGIN) => {
+                    *weakest = challengers.next().unwrap_or(*weakest);
+                }
+                _ => break,
+            }
+        }
+
+        let chosen: HashSet<&String> = bridges.iter().map(|(pid, _)| *pid).collect();
+        BridgeSelection {
+            bridges: bridges.into_iter().map(|(pid, _)| pid.clone()).collect(),
+            standby: ranked
+                .into_iter()
+                .filter(|(pid, _)| !chosen.contains(pid))
+                .take(STANDBY_PER_GROUP)
+                .map(|(pid, _)| pid.clone())
+                .collect(),
+        }
     }
 
     /// Remove player quality data
@@ -140,10 +200,49 @@ mod tests {
             selector.update_quality("p2", "peer2", 50, 0.0, true);
         }
 
-        let bridges =
-            selector.select_bridges(&["p1".to_string(), "p2".to_string(), "p3".to_string()]);
-        assert_eq!(bridges.len(), 2);
+        let selection = selector.select_bridges(
+            &["p1".to_string(), "p2".to_string(), "p3".to_string()],
+            &HashSet::new(),
+        );
+        assert_eq!(selection.bridges.len(), 2);
         // p2 should be first due to best RTT and stability
-        assert!(bridges.contains(&"p2".to_string()));
+        assert!(selection.bridges.contains("p2"));
+        assert_eq!(selection.standby, vec!["p3".to_string()]);
+    }
+
+    #[test]
+    fn test_jitter_lowers_score() {
+        let mut steady = ConnectionQuality::default();
+        let mut jittery = ConnectionQuality::default();
+        for i in 0..20 {
+            steady.update(60, 0.0, true);
+            jittery.update(if i % 2 == 0 { 20 } else { 100 }, 0.0, true);
+        }
+        assert!(jittery.rtt_jitter_ms > steady.rtt_jitter_ms);
+        assert!(steady.score() > jittery.score());
+    }
+
+    #[test]
+    fn test_incumbent_kept_within_hysteresis_margin() {
+        let mut selector = BridgeSelector::new(1);
+        let players = ["a".to_string(), "b".to_string()];
+        for _ in 0..10 {
+            selector.update_quality("a", "peer-a", 50, 0.0, true);
+            selector.update_quality("b", "peer-b", 45, 0.0, true);
+        }
+        let incumbents: HashSet<String> = ["a".to_string()].into();
+
+        // b is slightly better, but not by the margin
+        let selection = selector.select_bridges(&players, &incumbents);
+        assert_eq!(selection.bridges, incumbents);
+        assert_eq!(selection.standby, vec!["b".to_string()]);
+
+        // Once a degrades badly, b takes over
+        for _ in 0..10 {
+            selector.update_quality("a", "peer-a", 200, 0.0, true);
+        }
+        let selection = selector.select_bridges(&players, &incumbents);
+        assert_eq!(selection.bridges, ["b".to_string()].into());
+        assert_eq!(selection.standby, vec!["a".to_string()]);
     }
 }
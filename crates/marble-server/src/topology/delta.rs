@@ -0,0 +1,79 @@
+use std::collections::HashSet;
+
+use marble_proto::room::{PeerConnection, PeerTopology, TopologyDelta};
+
+/// Links to open and close when a player's topology goes from `previous` to `current`.
+///
+/// Without a previous topology every link of `current` is new.
+pub fn topology_delta(previous: Option<&PeerTopology>, current: &PeerTopology) -> TopologyDelta {
+    let links = |topology: &PeerTopology| -> Vec<PeerConnection> {
+        let mut seen = HashSet::new();
+        topology
+            .connect_to
+            .iter()
+            .chain(&topology.bridge_peers)
+            .filter(|peer| seen.insert((peer.user_id.clone(), peer.peer_id.clone())))
+            .cloned()
+            .collect()
+    };
+    let before = previous.map(links).unwrap_or_default();
+    let after = links(current);
+
+    let missing_from = |from: &[PeerConnection], other: &[PeerConnection]| -> Vec<PeerConnection> {
+        from.iter()
+            .filter(|peer| {
+                !other
+                    .iter()
+                    .any(|o| o.user_id == peer.user_id && o.peer_id == peer.peer_id)
+            })
+            .cloned()
+            .collect()
+    };
+
+    TopologyDelta {
+        connect: missing_from(&after, &before),
+        disconnect: missing_from(&before, &after),
+        role_changed: previous.is_none_or(|p| {
+            p.is_bridge != current.is_bridge
+                || p.mesh_group != current.mesh_group
+                || p.relayed != current.relayed
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(user_id: &str, peer_id: &str) -> PeerConnection {
+        PeerConnection {
+            user_id: user_id.to_string(),
+            peer_id: peer_id.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_delta_lists_only_changed_links() {
+        let previous = PeerTopology {
+            connect_to: vec![peer("a", "pa"), peer("b", "pb")],
+            ..Default::default()
+        };
+        let current = PeerTopology {
+            is_bridge: true,
+            connect_to: vec![peer("a", "pa"), peer("b", "pb2")],
+            bridge_peers: vec![peer("x", "px"), peer("a", "pa")],
+            ..Default::default()
+        };
+
+        let delta = topology_delta(Some(&previous), &current);
+        assert_eq!(delta.connect, vec![peer("b", "pb2"), peer("x", "px")]);
+        assert_eq!(delta.disconnect, vec![peer("b", "pb")]);
+        assert!(delta.role_changed);
+
+        let unchanged = topology_delta(Some(&current), &current);
+        assert!(unchanged.connect.is_empty() && unchanged.disconnect.is_empty());
+        assert!(!unchanged.role_changed);
+
+        assert_eq!(topology_delta(None, &previous).connect.len(), 2);
+    }
+}
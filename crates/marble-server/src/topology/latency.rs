@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+
+/// Weight of a new sample in the RTT moving average
+const RTT_SMOOTHING: f32 = 0.3;
+
+/// Symmetric player-to-player round-trip times collected from connection reports
+#[derive(Debug, Clone, Default)]
+pub struct LatencyMatrix {
+    /// Smoothed RTT in milliseconds, keyed by (`player_a`, `player_b`) with `player_a < player_b`
+    rtt_ms: HashMap<(String, String), f32>,
+}
+
+impl LatencyMatrix {
+    fn key(a: &str, b: &str) -> (String, String) {
+        if a < b {
+            (a.to_string(), b.to_string())
+        } else {
+            (b.to_string(), a.to_string())
+        }
+    }
+
+    /// Record a measurement between two players (either side may report it)
+    #[allow(clippy::cast_precision_loss)]
+    pub fn record(&mut self, a: &str, b: &str, rtt_ms: u32) {
+        if a == b {
+            return;
+        }
+        let sample = rtt_ms as f32;
+        self.rtt_ms
+            .entry(Self::key(a, b))
+            .and_modify(|avg| *avg = *avg * (1.0 - RTT_SMOOTHING) + sample * RTT_SMOOTHING)
+            .or_insert(sample);
+    }
+
+    /// Smoothed RTT between two players, if measured
+    pub fn rtt(&self, a: &str, b: &str) -> Option<f32> {
+        self.rtt_ms.get(&Self::key(a, b)).copied()
+    }
+
+    /// Mean RTT from `player_id` to the measured players in `others`, with the sample count
+    #[allow(clippy::cast_precision_loss)]
+    pub fn mean_rtt_to<'a>(
+        &self,
+        player_id: &str,
+        others: impl IntoIterator<Item = &'a String>,
+    ) -> Option<(f32, u32)> {
+        let (sum, count) = others
+            .into_iter()
+            .filter(|other| other.as_str() != player_id)
+            .filter_map(|other| self.rtt(player_id, other))
+            .fold((0.0, 0u32), |(sum, count), rtt| (sum + rtt, count + 1));
+        (count > 0).then(|| (sum / count as f32, count))
+    }
+
+    /// Forget every measurement involving `player_id`
+    pub fn remove_player(&mut self, player_id: &str) {
+        self.rtt_ms
+            .retain(|(a, b), _| a != player_id && b != player_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symmetric_smoothed_rtt() {
+        let mut matrix = LatencyMatrix::default();
+        matrix.record("p1", "p2", 100);
+        assert_eq!(matrix.rtt("p2", "p1"), Some(100.0));
+
+        matrix.record("p2", "p1", 200);
+        let smoothed = matrix.rtt("p1", "p2").unwrap();
+        assert!((smoothed - 130.0).abs() < 0.01);
+
+        matrix.record("p1", "p3", 40);
+        let others = ["p2".to_string(), "p3".to_string(), "p4".to_string()];
+        let (mean, samples) = matrix.mean_rtt_to("p1", &others).unwrap();
+        assert!((mean - 85.0).abs() < 0.01);
+        assert_eq!(samples, 2);
+
+        matrix.remove_player("p2");
+        assert_eq!(matrix.rtt("p1", "p2"), None);
+        assert_eq!(matrix.mean_rtt_to("p1", &others), Some((40.0, 1)));
+    }
+}
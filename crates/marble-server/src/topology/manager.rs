@@ -1,8 +1,15 @@
 use std::collections::HashMap;
 
-use marble_proto::room::{PeerConnection, PeerConnectionStatus, PeerTopology};
+use marble_proto::room::{GroupLatency, PeerConnection, PeerConnectionStatus, PeerTopology};
 
-use super::{BridgeSelector, MeshGroup};
+use super::{BridgeSelector, LatencyMatrix, MeshGroup};
+
+/// Minimum RTT a player must save before being moved to a closer group
+const LATENCY_MIGRATION_MIN_GAIN_MS: f32 = 15.0;
+/// Share of the current group RTT a move must save as well
+const LATENCY_MIGRATION_MIN_RATIO: f32 = 0.25;
+/// Consecutive connection reports with every link down before a player is moved to the relay
+const RELAY_AFTER_FAILED_REPORTS: u32 = 3;
 
 /// Configuration for topology manager
 #[derive(Debug, Clone)]
@@ -17,6 +24,8 @@ pub struct TopologyManagerConfig {
     pub gossip_ttl: u32,
     /// Lockstep delay frames
     pub lockstep_delay_frames: u32,
+    /// Groups filled below this percentage of `mesh_group_size` are merged by `rebalance`
+    pub min_group_fill_percent: u32,
 }
 
 impl Default for TopologyManagerConfig {
@@ -27,10 +36,27 @@ impl Default for TopologyManagerConfig {
             bridges_per_group: 2,
             gossip_ttl: 10,
             lockstep_delay_frames: 6,
+            min_group_fill_percent: 50,
         }
     }
 }
 
+/// Topology changes counted for metrics
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TopologyChurn {
+    /// Bridge recalculations
+    pub recalculations: u64,
+    /// Players moved to another mesh group
+    pub migrations: u64,
+}
+
+impl std::ops::AddAssign for TopologyChurn {
+    fn add_assign(&mut self, other: Self) {
+        self.recalculations += other.recalculations;
+        self.migrations += other.migrations;
+    }
+}
+
 /// Topology manager for a room
 #[derive(Debug, Clone)]
 pub struct TopologyManager {
@@ -44,8 +70,14 @@ pub struct TopologyManager {
     player_peers: HashMap<String, String>,
     /// Bridge selector
     bridge_selector: BridgeSelector,
+    /// RTTs reported between players
+    latency: LatencyMatrix,
+    /// Consecutive all-links-down reports per player (`player_id` -> count)
+    failed_reports: HashMap<String, u32>,
     /// Flag indicating if topology needs recalculation
     topology_dirty: bool,
+    /// Changes since the room was created
+    churn: TopologyChurn,
 }
 
 impl TopologyManager {
@@ -56,7 +88,10 @@ impl TopologyManager {
             groups: Vec::new(),
             player_groups: HashMap::new(),
             player_peers: HashMap::new(),
+            latency: LatencyMatrix::default(),
+            failed_reports: HashMap::new(),
             topology_dirty: false,
+            churn: TopologyChurn::default(),
         }
     }
 
@@ -90,15 +125,192 @@ impl TopologyManager {
         }
         self.player_peers.remove(player_id);
         self.bridge_selector.remove_player(player_id);
+        self.latency.remove_player(player_id);
+        self.failed_reports.remove(player_id);
+        self.topology_dirty = true;
+    }
+
+    /// Replace the configuration. Existing groups adopt the new size; call `rebalance`
+    /// to split groups that are now oversized.
+    pub fn set_config(&mut self, config: TopologyManagerConfig) {
+        for group in &mut self.groups {
+            group.max_size = config.mesh_group_size;
+        }
+        self.bridge_selector.bridges_per_group = config.bridges_per_group;
+        self.config = config;
+        self.topology_dirty = true;
+    }
+
+    /// Split oversized groups and merge under-filled ones, moving as few players as
+    /// possible. Bridges are reselected if anyone moved.
+    ///
+    /// Returns the moved players, sorted. Their `connect_to` and `bridge_peers` changed,
+    /// as did `bridge_peers` of every bridge.
+    pub fn rebalance(&mut self) -> Vec<String> {
+        let mut moved = Vec::new();
+        self.split_oversized_groups(&mut moved);
+        self.merge_underfilled_groups(&mut moved);
+
+        // Group ids are indices: only trailing empty groups can go, others stay as free slots
+        while self.groups.last().is_some_and(|g| g.players.is_empty()) {
+            self.groups.pop();
+        }
+
+        moved.sort();
+        moved.dedup();
+        if !moved.is_empty() {
+            self.churn.migrations += moved.len() as u64;
+            self.recalculate_bridges();
+            self.topology_dirty = false;
+        }
+        moved
+    }
+
+    /// Move players out of groups above `mesh_group_size`, bridges last.
+    fn split_oversized_groups(&mut self, moved: &mut Vec<String>) {
+        let size = self.config.mesh_group_size as usize;
+        for index in 0..self.groups.len() {
+            let group = &self.groups[index];
+            let excess = group.players.len().saturating_sub(size);
+            if excess == 0 {
+                continue;
+            }
+
+            let mut candidates: Vec<&String> = group.players.keys().collect();
+            candidates.sort_by_key(|pid| (group.is_bridge(pid), *pid));
+            let leaving: Vec<String> = candidates.into_iter().take(excess).cloned().collect();
+
+            for player_id in leaving {
+                // The source group is over capacity, so it is never picked
+                let target = self.find_or_create_group();
+                self.move_player(&player_id, target);
+                moved.push(player_id);
+            }
+        }
+    }
+
+    /// Dissolve under-filled groups into the free slots of the other groups, smallest first.
+    fn merge_underfilled_groups(&mut self, moved: &mut Vec<String>) {
+        let min_fill = (self.config.mesh_group_size * self.config.min_group_fill_percent)
+            .div_ceil(100) as usize;
+
+        loop {
+            let free_elsewhere = |source: &MeshGroup| -> usize {
+                self.groups
+                    .iter()
+                    .filter(|g| g.group_id != source.group_id && !g.players.is_empty())
+                    .map(|g| (g.max_size as usize).saturating_sub(g.players.len()))
+                    .sum()
+            };
+            let Some(source) = self
+                .groups
+                .iter()
+                .filter(|g| !g.players.is_empty() && g.players.len() < min_fill)
+                .filter(|g| free_elsewhere(g) >= g.players.len())
+                .min_by_key(|g| (g.players.len(), g.group_id))
+                .map(|g| g.group_id)
+            else {
+                break;
+            };
+
+            let mut players: Vec<String> = self.groups[source as usize]
+                .players
+                .keys()
+                .cloned()
+                .collect();
+            players.sort();
+            for player_id in players {
+                // Fullest group with a free slot keeps the remaining groups dense
+                let Some(target) = self
+                    .groups
+                    .iter()
+                    .filter(|g| g.group_id != source && !g.players.is_empty() && g.has_capacity())
+                    .max_by_key(|g| (g.players.len(), std::cmp::Reverse(g.group_id)))
+                    .map(|g| g.group_id)
+                else {
+                    break;
+                };
+                self.move_player(&player_id, target);
+                moved.push(player_id);
+            }
+        }
+    }
+
+    /// Move players to a group with a free slot whose measured RTT is clearly lower
+    /// than their own group's, largest gain first. Returns the moved players, sorted.
+    fn recluster_by_latency(&mut self) -> Vec<String> {
+        let mut proposals: Vec<(f32, String, u32)> = Vec::new();
+        for (player_id, &group_id) in &self.player_groups {
+            let own = &self.groups[group_id as usize];
+            let Some((home_rtt, _)) = self.latency.mean_rtt_to(player_id, own.players.keys())
+            else {
+                continue;
+            };
+
+            let best = self
+                .groups
+                .iter()
+                .filter(|g| g.group_id != group_id && !g.players.is_empty() && g.has_capacity())
+                .filter_map(|g| {
+                    let (rtt, _) = self.latency.mean_rtt_to(player_id, g.players.keys())?;
+                    Some((home_rtt - rtt, g.group_id))
+                })
+                .max_by(|a, b| a.0.total_cmp(&b.0));
+            if let Some((gain, target)) = best
+                && gain >= LATENCY_MIGRATION_MIN_GAIN_MS
+                && gain >= home_rtt * LATENCY_MIGRATION_MIN_RATIO
+            {
+                proposals.push((gain, player_id.clone(), target));
+            }
+        }
+        proposals.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+
+        let mut moved = Vec::new();
+        for (_, player_id, target) in proposals {
+            // Earlier moves may have filled the target
+            if self.groups[target as usize].has_capacity() {
+                self.move_player(&player_id, target);
+                moved.push(player_id);
+            }
+        }
+        self.churn.migrations += moved.len() as u64;
+        moved.sort();
+        moved
+    }
+
+    fn move_player(&mut self, player_id: &str, target: u32) {
+        let Some(source) = self.player_groups.get(player_id).copied() else {
+            return;
+        };
+        let Some(peer_id) = self.peer_id(player_id).map(str::to_string) else {
+            return;
+        };
+        let relayed = self.groups[source as usize].is_relayed(player_id);
+        self.groups[source as usize].remove_player(player_id);
+        let group = &mut self.groups[target as usize];
+        group.add_player(player_id.to_string(), peer_id);
+        if relayed {
+            group.relayed_players.insert(player_id.to_string());
+        }
+        self.player_groups.insert(player_id.to_string(), target);
         self.topology_dirty = true;
     }
 
     /// Update connection status and check if topology changed
+    ///
+    /// Reported RTTs feed the latency matrix, which may move players to closer groups while
+    /// `regroup` is set (the room is waiting); otherwise groups stay as they are.
+    /// A player whose links are all down for `RELAY_AFTER_FAILED_REPORTS` reports in a row
+    /// is moved to the server relay, and moved back once a report shows a working link.
     pub fn update_connection_status(
         &mut self,
         player_id: &str,
         statuses: &[PeerConnectionStatus],
+        regroup: bool,
     ) -> Option<PeerTopology> {
+        let peer_ids: Vec<String> = statuses.iter().map(|s| s.peer_id.clone()).collect();
+        let peer_players = self.resolve_peer_ids(&peer_ids);
+
         // Update quality scores
         for status in statuses {
             self.bridge_selector.update_quality(
@@ -108,10 +320,27 @@ impl TopologyManager {
                 status.packet_loss,
                 status.connected,
             );
+            if status.connected
+                && let Some(peer_player) = peer_players.get(&status.peer_id)
+            {
+                self.latency.record(player_id, peer_player, status.rtt_ms);
+            }
         }
 
+        if self.record_link_failures(player_id, statuses) {
+            self.mark_relayed(player_id);
+        } else if self.failed_reports.contains_key(player_id) {
+            self.fail_over_bridge(player_id);
+        }
+
+        let moved = if regroup {
+            self.recluster_by_latency()
+        } else {
+            Vec::new()
+        };
+
         // Check if we need to recalculate bridges
-        if self.should_recalculate_bridges() {
+        if self.should_recalculate_bridges() || !moved.is_empty() {
             self.recalculate_bridges();
             self.topology_dirty = false;
 
@@ -122,6 +351,102 @@ impl TopologyManager {
         }
     }
 
+    /// Count reports in which every link is down. Returns true once the player should be
+    /// relayed. A working link resets the count and takes a relayed player off the relay.
+    fn record_link_failures(&mut self, player_id: &str, statuses: &[PeerConnectionStatus]) -> bool {
+        if statuses.is_empty() {
+            return false;
+        }
+        if statuses.iter().any(|s| s.connected) {
+            self.failed_reports.remove(player_id);
+            self.clear_relayed(player_id);
+            return false;
+        }
+        if self.is_relayed(player_id) {
+            return false;
+        }
+        let failed = self
+            .failed_reports
+            .entry(player_id.to_string())
+            .or_default();
+        *failed += 1;
+        *failed >= RELAY_AFTER_FAILED_REPORTS
+    }
+
+    /// Route a player through the server relay. It keeps its WebRTC peers, which it retries
+    /// in the background, but is no longer picked as a bridge.
+    fn mark_relayed(&mut self, player_id: &str) {
+        let Some(&group_id) = self.player_groups.get(player_id) else {
+            return;
+        };
+        let group = &mut self.groups[group_id as usize];
+        group.relayed_players.insert(player_id.to_string());
+        group.bridge_players.remove(player_id);
+        group.standby_players.retain(|pid| pid != player_id);
+        self.failed_reports.remove(player_id);
+        self.topology_dirty = true;
+        tracing::info!(
+            player_id,
+            "WebRTC links keep failing, moving player to the relay"
+        );
+    }
+
+    /// Take a player off the relay once its WebRTC links work again
+    fn clear_relayed(&mut self, player_id: &str) {
+        let Some(&group_id) = self.player_groups.get(player_id) else {
+            return;
+        };
+        if self.groups[group_id as usize]
+            .relayed_players
+            .remove(player_id)
+        {
+            self.topology_dirty = true;
+            tracing::info!(player_id, "WebRTC links recovered, leaving the relay");
+        }
+    }
+
+    /// Hand a bridge's role to its group's first standby right away, instead of waiting
+    /// for the next reselection to pick a replacement.
+    fn fail_over_bridge(&mut self, player_id: &str) {
+        let Some(&group_id) = self.player_groups.get(player_id) else {
+            return;
+        };
+        let group = &mut self.groups[group_id as usize];
+        if !group.is_bridge(player_id) {
+            return;
+        }
+        let standby = group.promote_standby(player_id);
+        self.topology_dirty = true;
+        tracing::info!(
+            player_id,
+            standby = standby.as_deref().unwrap_or("none"),
+            "Bridge reported its links down, failing over"
+        );
+    }
+
+    /// Whether a player is reached through the server relay
+    pub fn is_relayed(&self, player_id: &str) -> bool {
+        self.player_groups
+            .get(player_id)
+            .is_some_and(|&g| self.groups[g as usize].is_relayed(player_id))
+    }
+
+    /// Whether any player in the room uses the server relay
+    pub fn has_relayed_players(&self) -> bool {
+        self.groups.iter().any(|g| !g.relayed_players.is_empty())
+    }
+
+    /// Reselect bridges from the connection quality reported so far
+    pub fn refresh_bridges(&mut self) {
+        self.recalculate_bridges();
+        self.topology_dirty = false;
+    }
+
+    /// Bridge recalculations and group migrations since the room was created
+    pub fn churn(&self) -> TopologyChurn {
+        self.churn
+    }
+
     /// Get current topology for a player
     pub fn get_topology(&self, player_id: &str) -> Option<PeerTopology> {
         if self.player_groups.contains_key(player_id) {
@@ -137,10 +462,15 @@ impl TopologyManager {
         let group = &self.groups[group_id as usize];
 
         let is_bridge = group.is_bridge(player_id);
+        let relayed = group.is_relayed(player_id);
 
-        // Get peers to connect within the group
+        // Get peers to connect within the group (relayed players keep theirs to recover)
         let connect_to: Vec<PeerConnection> = group
-            .select_peers_for_player(player_id, self.config.peer_connections as usize)
+            .select_peers_for_player(
+                player_id,
+                self.config.peer_connections as usize,
+                &self.latency,
+            )
             .into_iter()
             .map(|(uid, peer_id)| PeerConnection {
                 user_id: uid,
@@ -161,6 +491,10 @@ impl TopologyManager {
             is_bridge,
             connect_to,
             bridge_peers,
+            relayed,
+            relay_url: String::new(),    // Set by Room
+            member_keys: HashMap::new(), // Set by Room
+            host_user_id: String::new(), // Set by Room
         }
     }
 
@@ -205,25 +539,74 @@ impl TopologyManager {
         self.topology_dirty
     }
 
-    /// Recalculate bridge nodes for all groups
+    /// Recalculate bridge nodes for all groups.
+    ///
+    /// Players whose last report had every link down are not eligible until they recover.
     fn recalculate_bridges(&mut self) {
+        self.churn.recalculations += 1;
         for group in &mut self.groups {
-            let player_ids: Vec<String> = group.players.keys().cloned().collect();
-            let bridges = self.bridge_selector.select_bridges(&player_ids);
-            group.set_bridges(bridges.into_iter().collect());
+            let mut player_ids = group.direct_player_ids();
+            player_ids.retain(|pid| !self.failed_reports.contains_key(pid));
+            let selection = self
+                .bridge_selector
+                .select_bridges(&player_ids, &group.bridge_players);
+            group.set_bridges(selection.bridges);
+            group.standby_players = selection.standby;
         }
     }
 
+    /// Current `peer_id` of a player
+    pub fn peer_id(&self, player_id: &str) -> Option<&str> {
+        self.player_peers.get(player_id).map(String::as_str)
+    }
+
     /// Get total player count
     #[allow(dead_code)]
     pub fn player_count(&self) -> usize {
         self.player_groups.len()
     }
 
-    /// Get group count
-    #[allow(dead_code)]
+    /// Mean measured RTT within and between mesh groups, for diagnostics
+    #[allow(clippy::cast_precision_loss)]
+    pub fn group_latencies(&self) -> Vec<GroupLatency> {
+        let mut result = Vec::new();
+        for (i, a) in self.groups.iter().enumerate() {
+            for b in &self.groups[i..] {
+                let mut sum = 0.0;
+                let mut samples = 0u32;
+                for player_a in a.players.keys() {
+                    for player_b in b.players.keys() {
+                        // Count each pair once inside a group
+                        if a.group_id == b.group_id && player_a >= player_b {
+                            continue;
+                        }
+                        if let Some(rtt) = self.latency.rtt(player_a, player_b) {
+                            sum += rtt;
+                            samples += 1;
+                        }
+                    }
+                }
+                if samples > 0 {
+                    result.push(GroupLatency {
+                        group_a: a.group_id,
+                        group_b: b.group_id,
+                        mean_rtt_ms: sum / samples as f32,
+                        samples,
+                    });
+                }
+            }
+        }
+        result
+    }
+
+    /// Get count of groups with at least one player
     pub fn group_count(&self) -> usize {
-        self.groups.len()
+        self.groups.iter().filter(|g| !g.players.is_empty()).count()
+    }
+
+    /// Get bridge count over all groups
+    pub fn bridge_count(&self) -> usize {
+        self.groups.iter().map(|g| g.bridge_players.len()).sum()
     }
 
     /// Update `peer_id` for a player (returns true if player exists and was updated)
@@ -327,11 +710,290 @@ mod tests {
                 packet_loss: 0.0,
                 connected: true,
             }];
-            manager.update_connection_status(&format!("p{}", i), &statuses);
+            manager.update_connection_status(&format!("p{}", i), &statuses, true);
         }
 
         // Check that bridges are assigned
         let group = &manager.groups[0];
         assert_eq!(group.bridge_players.len(), 2);
     }
+
+    fn small_group_manager(mesh_group_size: u32) -> TopologyManager {
+        TopologyManager::new(TopologyManagerConfig {
+            mesh_group_size,
+            peer_connections: 2,
+            ..Default::default()
+        })
+    }
+
+    fn group_sizes(manager: &TopologyManager) -> Vec<usize> {
+        manager.groups.iter().map(|g| g.players.len()).collect()
+    }
+
+    /// Every player sits in exactly the group it is mapped to and no group is oversized.
+    fn assert_consistent(manager: &TopologyManager) {
+        for (player_id, &group_id) in &manager.player_groups {
+            assert_eq!(
+                manager.groups[group_id as usize]
+                    .players
+                    .get(player_id)
+                    .map(String::as_str),
+                manager.peer_id(player_id)
+            );
+        }
+        let total: usize = group_sizes(manager).iter().sum();
+        assert_eq!(total, manager.player_count());
+        let max = manager.config.mesh_group_size as usize;
+        assert!(group_sizes(manager).iter().all(|&n| n <= max));
+    }
+
+    #[test]
+    fn test_rebalance_merges_fragmented_groups() {
+        let mut manager = small_group_manager(4);
+        for i in 0..12 {
+            manager.add_player(&format!("p{i}"), &format!("peer{i}"));
+        }
+        let in_group = |manager: &TopologyManager, group: usize| -> Vec<String> {
+            let mut ids: Vec<String> = manager.groups[group].players.keys().cloned().collect();
+            ids.sort();
+            ids
+        };
+        for player_id in in_group(&manager, 1).iter().skip(1) {
+            manager.remove_player(player_id);
+        }
+        for player_id in in_group(&manager, 2).iter().skip(1) {
+            manager.remove_player(player_id);
+        }
+        assert_eq!(group_sizes(&manager), vec![4, 1, 1]);
+
+        // Only the lone player of group 1 moves, joining the other lone player
+        let lone = in_group(&manager, 1);
+        let moved = manager.rebalance();
+        assert_eq!(moved, lone);
+        assert_eq!(group_sizes(&manager), vec![4, 0, 2]);
+        assert_eq!(manager.group_count(), 2);
+        assert_consistent(&manager);
+
+        let topology = manager.get_topology(&moved[0]).unwrap();
+        assert_eq!(topology.mesh_group, 2);
+        assert_eq!(topology.connect_to.len(), 1);
+
+        // Balanced groups are left alone
+        assert!(manager.rebalance().is_empty());
+
+        // The free slot is reused before a new group is created
+        let topology = manager.add_player("late", "peer_late");
+        assert_eq!(topology.mesh_group, 1);
+    }
+
+    #[test]
+    fn test_rebalance_splits_oversized_groups() {
+        let mut manager = small_group_manager(6);
+        for i in 0..6 {
+            manager.add_player(&format!("p{i}"), &format!("peer{i}"));
+        }
+
+        let mut config = manager.config.clone();
+        config.mesh_group_size = 3;
+        manager.set_config(config);
+        let moved = manager.rebalance();
+
+        assert_eq!(moved.len(), 3);
+        assert_eq!(group_sizes(&manager), vec![3, 3]);
+        assert_eq!(
+            manager.churn(),
+            TopologyChurn {
+                recalculations: 1,
+                migrations: 3,
+            }
+        );
+        assert_consistent(&manager);
+        for player_id in &moved {
+            let topology = manager.get_topology(player_id).unwrap();
+            assert_eq!(topology.mesh_group, 1);
+            assert!(
+                topology
+                    .connect_to
+                    .iter()
+                    .all(|p| moved.contains(&p.user_id))
+            );
+        }
+    }
+
+    #[test]
+    fn test_rebalance_under_churn() {
+        let mut manager = small_group_manager(5);
+        let min_fill = 3; // ceil(5 * 50%)
+        let mut players: Vec<String> = Vec::new();
+        let mut next_id = 0;
+        // Deterministic pseudo-random join/leave sequence
+        let mut state: u32 = 7;
+        for _ in 0..400 {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            let roll = (state >> 16) % 100;
+            if players.is_empty() || roll < 55 {
+                let player_id = format!("p{next_id}");
+                next_id += 1;
+                manager.add_player(&player_id, &format!("peer_{player_id}"));
+                players.push(player_id);
+            } else {
+                let index = (state >> 8) as usize % players.len();
+                let player_id = players.swap_remove(index);
+                manager.remove_player(&player_id);
+                let moved = manager.rebalance();
+                assert!(moved.len() < min_fill, "moved {moved:?}");
+            }
+
+            assert_consistent(&manager);
+            let underfilled = group_sizes(&manager)
+                .into_iter()
+                .filter(|&n| n > 0 && n < min_fill)
+                .count();
+            assert!(underfilled <= 1, "groups {:?}", group_sizes(&manager));
+        }
+    }
+
+    #[test]
+    fn test_latency_clustering_moves_player_to_closer_group() {
+        let mut manager = small_group_manager(4);
+        for i in 0..7 {
+            manager.add_player(&format!("p{i}"), &format!("peer{i}"));
+        }
+        manager.remove_player("p3");
+        assert_eq!(group_sizes(&manager), vec![3, 3]);
+
+        // p6 sits next to group 0 but far from its own group
+        let status = |peer: usize, rtt_ms: u32| PeerConnectionStatus {
+            peer_id: format!("peer{peer}"),
+            rtt_ms,
+            packet_loss: 0.0,
+            connected: true,
+        };
+        let statuses = vec![status(4, 120), status(5, 100), status(0, 10)];
+
+        // Groups stay put while the race runs
+        manager.update_connection_status("p6", &statuses, false);
+        assert_eq!(manager.get_topology("p6").unwrap().mesh_group, 1);
+        assert_eq!(group_sizes(&manager), vec![3, 3]);
+
+        let topology = manager
+            .update_connection_status("p6", &statuses, true)
+            .unwrap();
+
+        assert_eq!(topology.mesh_group, 0);
+        assert_eq!(group_sizes(&manager), vec![4, 2]);
+        assert!(topology.connect_to.iter().any(|p| p.user_id == "p0"));
+        assert_consistent(&manager);
+
+        // Within group 0 only p0-p6 is measured; group 1 to group 0 only p4/p5-p6
+        let latencies = manager.group_latencies();
+        let within = latencies
+            .iter()
+            .find(|l| l.group_a == 0 && l.group_b == 0)
+            .unwrap();
+        assert_eq!(within.samples, 1);
+        assert!((within.mean_rtt_ms - 10.0).abs() < 0.01);
+        let between = latencies
+            .iter()
+            .find(|l| l.group_a == 0 && l.group_b == 1)
+            .unwrap();
+        assert_eq!(between.samples, 2);
+        assert!((between.mean_rtt_ms - 110.0).abs() < 0.01);
+
+        // The same report again does not move anyone back
+        let statuses = vec![status(0, 10), status(1, 12)];
+        manager.update_connection_status("p6", &statuses, true);
+        assert_eq!(manager.get_topology("p6").unwrap().mesh_group, 0);
+    }
+
+    #[test]
+    fn test_relay_after_failed_reports() {
+        let mut manager = TopologyManager::new(TopologyManagerConfig::default());
+        for i in 1..=4 {
+            manager.add_player(&format!("p{i}"), &format!("peer{i}"));
+        }
+        let status = |peer: usize, connected: bool| PeerConnectionStatus {
+            peer_id: format!("peer{peer}"),
+            rtt_ms: 40,
+            packet_loss: if connected { 0.0 } else { 1.0 },
+            connected,
+        };
+        let all_down = vec![status(1, false), status(2, false)];
+
+        // A working link in between resets the count
+        manager.update_connection_status("p4", &all_down, true);
+        manager.update_connection_status("p4", &all_down, true);
+        manager.update_connection_status("p4", &[status(1, true), status(2, false)], true);
+        manager.update_connection_status("p4", &all_down, true);
+        assert!(!manager.is_relayed("p4"));
+
+        manager.update_connection_status("p4", &all_down, true);
+        let topology = manager
+            .update_connection_status("p4", &all_down, true)
+            .expect("relaying changes the topology");
+        assert!(topology.relayed);
+        assert!(!topology.is_bridge);
+        assert!(manager.has_relayed_players());
+
+        // p4 stays in the mesh so its links can recover
+        assert!(!topology.connect_to.is_empty());
+        for other in ["p1", "p2", "p3"] {
+            let topology = manager.get_topology(other).unwrap();
+            assert!(!topology.relayed);
+            assert!(topology.connect_to.iter().any(|p| p.user_id == "p4"));
+        }
+
+        // A working link takes p4 off the relay again
+        let topology = manager
+            .update_connection_status("p4", &[status(1, true), status(2, false)], true)
+            .expect("leaving the relay changes the topology");
+        assert!(!topology.relayed);
+        assert!(!manager.has_relayed_players());
+
+        manager.update_connection_status("p4", &all_down, true);
+        manager.update_connection_status("p4", &all_down, true);
+        manager.update_connection_status("p4", &all_down, true);
+        assert!(manager.is_relayed("p4"));
+        manager.remove_player("p4");
+        assert!(!manager.has_relayed_players());
+    }
+
+    #[test]
+    fn test_standby_takes_over_failed_bridge() {
+        let mut manager = TopologyManager::new(TopologyManagerConfig {
+            bridges_per_group: 1,
+            ..Default::default()
+        });
+        for i in 1..=4 {
+            manager.add_player(&format!("p{i}"), &format!("peer{i}"));
+        }
+        let status = |rtt_ms: u32, connected: bool| PeerConnectionStatus {
+            peer_id: "peer1".to_string(),
+            rtt_ms,
+            packet_loss: 0.0,
+            connected,
+        };
+        // p2 has the best links, p3 is close behind
+        for _ in 0..5 {
+            for (player, rtt) in [("p2", 20), ("p3", 22), ("p4", 80)] {
+                manager.update_connection_status(player, &[status(rtt, true)], true);
+            }
+        }
+        manager.refresh_bridges();
+        assert!(manager.get_topology("p2").unwrap().is_bridge);
+        assert_eq!(manager.groups[0].standby_players, vec!["p3".to_string()]);
+
+        // The bridge loses its links: the standby takes over at once
+        let topology = manager
+            .update_connection_status("p2", &[status(20, false)], true)
+            .expect("failover changes the topology");
+        assert!(!topology.is_bridge);
+        assert!(manager.get_topology("p3").unwrap().is_bridge);
+
+        // Once back, p2 is not enough better to displace the new bridge
+        manager.update_connection_status("p2", &[status(20, true)], true);
+        manager.refresh_bridges();
+        assert!(manager.get_topology("p3").unwrap().is_bridge);
+        assert!(!manager.get_topology("p2").unwrap().is_bridge);
+    }
 }
@@ -1,5 +1,10 @@
 use std::collections::{HashMap, HashSet};
 
+use super::LatencyMatrix;
+
+/// Ranks peers without an RTT measurement behind every measured one
+const UNMEASURED_RTT_MS: f32 = 10_000.0;
+
 /// Mesh group containing players
 #[derive(Debug, Clone)]
 pub struct MeshGroup {
@@ -11,6 +16,10 @@ pub struct MeshGroup {
     pub players: HashMap<String, String>,
     /// Bridge node player IDs (typically 2 per group)
     pub bridge_players: HashSet<String>,
+    /// Players that take over from a failed bridge, in order
+    pub standby_players: Vec<String>,
+    /// Players without working WebRTC links, reached through the server relay instead
+    pub relayed_players: HashSet<String>,
 }
 
 impl MeshGroup {
@@ -20,6 +29,8 @@ impl MeshGroup {
             max_size,
             players: HashMap::new(),
             bridge_players: HashSet::new(),
+            standby_players: Vec::new(),
+            relayed_players: HashSet::new(),
         }
     }
 
@@ -46,6 +57,8 @@ impl MeshGroup {
     /// Remove a player from the group
     pub fn remove_player(&mut self, player_id: &str) -> bool {
         self.bridge_players.remove(player_id);
+        self.standby_players.retain(|pid| pid != player_id);
+        self.relayed_players.remove(player_id);
         self.players.remove(player_id).is_some()
     }
 
@@ -54,12 +67,39 @@ impl MeshGroup {
         self.bridge_players = bridge_ids;
     }
 
+    /// Replace a failed bridge with the first standby. Returns the new bridge, if any.
+    pub fn promote_standby(&mut self, failed_bridge: &str) -> Option<String> {
+        if !self.bridge_players.remove(failed_bridge) {
+            return None;
+        }
+        let standby = self.standby_players.first().cloned()?;
+        self.standby_players.remove(0);
+        self.bridge_players.insert(standby.clone());
+        Some(standby)
+    }
+
     /// Check if a player is a bridge
     pub fn is_bridge(&self, player_id: &str) -> bool {
         self.bridge_players.contains(player_id)
     }
 
+    /// Check if a player is reached through the server relay
+    pub fn is_relayed(&self, player_id: &str) -> bool {
+        self.relayed_players.contains(player_id)
+    }
+
+    /// Player IDs with working WebRTC links (everyone not relayed), the bridge candidates
+    pub fn direct_player_ids(&self) -> Vec<String> {
+        self.players
+            .keys()
+            .filter(|pid| !self.is_relayed(pid))
+            .cloned()
+            .collect()
+    }
+
     /// Get all player IDs except the given one
+    ///
+    /// Relayed players stay in: their WebRTC links are retried so they can leave the relay.
     pub fn get_other_players(&self, exclude_player_id: &str) -> Vec<(String, String)> {
         self.players
             .iter()
@@ -68,37 +108,72 @@ impl MeshGroup {
             .collect()
     }
 
-    /// Select random peers for connection (up to `max_connections`)
+    /// Select peers for connection (up to `max_connections`), lowest RTT first.
+    ///
+    /// The first pick is the player's successor on a low-latency tour of the group, so the
+    /// connection graph stays connected whatever the RTTs. Unmeasured peers rank after
+    /// measured ones, bridges first.
     pub fn select_peers_for_player(
         &self,
         player_id: &str,
         max_connections: usize,
+        latency: &LatencyMatrix,
     ) -> Vec<(String, String)> {
-        let others = self.get_other_players(player_id);
+        let mut others = self.get_other_players(player_id);
 
         if others.len() <= max_connections {
             // If fewer than max, connect to all
-            others
-        } else {
-            // Select a subset - prefer bridges and then random
-            let mut result: Vec<(String, String)> = Vec::new();
-
-            // First add bridges
-            for (pid, peer_id) in &others {
-                if self.bridge_players.contains(pid) && result.len() < max_connections {
-                    result.push((pid.clone(), peer_id.clone()));
-                }
-            }
-
-            // Then add non-bridges until we reach max
-            for (pid, peer_id) in &others {
-                if !self.bridge_players.contains(pid) && result.len() < max_connections {
-                    result.push((pid.clone(), peer_id.clone()));
-                }
-            }
-
-            result
+            return others;
+        }
+
+        let tour = self.latency_tour(latency);
+        let successor = tour
+            .iter()
+            .position(|pid| *pid == player_id)
+            .map(|i| tour[(i + 1) % tour.len()].clone());
+
+        let rank = |pid: &str| {
+            (
+                Some(pid) != successor.as_deref(),
+                latency.rtt(player_id, pid).unwrap_or(UNMEASURED_RTT_MS),
+                !self.is_bridge(pid),
+            )
+        };
+        others.sort_by(|(a, _), (b, _)| {
+            let (a_rank, b_rank) = (rank(a), rank(b));
+            a_rank
+                .0
+                .cmp(&b_rank.0)
+                .then(a_rank.1.total_cmp(&b_rank.1))
+                .then(a_rank.2.cmp(&b_rank.2))
+                .then_with(|| a.cmp(b))
+        });
+        others.truncate(max_connections);
+        others
+    }
+
+    /// Players as a cycle built nearest-neighbour first from the smallest `player_id`.
+    fn latency_tour(&self, latency: &LatencyMatrix) -> Vec<String> {
+        let mut remaining: Vec<&String> = self.players.keys().collect();
+        remaining.sort();
+        let mut tour = Vec::with_capacity(remaining.len());
+        if remaining.is_empty() {
+            return tour;
+        }
+
+        let mut current = remaining.remove(0);
+        tour.push(current.clone());
+        while !remaining.is_empty() {
+            let (next, _) = remaining
+                .iter()
+                .enumerate()
+                .map(|(i, pid)| (i, latency.rtt(current, pid).unwrap_or(UNMEASURED_RTT_MS)))
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .unwrap_or((0, UNMEASURED_RTT_MS));
+            current = remaining.remove(next);
+            tour.push(current.clone());
         }
+        tour
     }
 
     /// Get bridge players with their peer IDs
@@ -149,5 +224,48 @@ mod tests {
         assert!(group.is_bridge("p1"));
         assert!(group.is_bridge("p2"));
         assert!(!group.is_bridge("p0"));
+
+        group.standby_players = vec!["p0".to_string()];
+        assert_eq!(group.promote_standby("p1").as_deref(), Some("p0"));
+        assert!(group.is_bridge("p0") && !group.is_bridge("p1"));
+        assert!(group.standby_players.is_empty());
+        // Not a bridge (any more): nothing to replace
+        assert_eq!(group.promote_standby("p1"), None);
+    }
+
+    #[test]
+    fn test_select_peers_prefers_low_rtt_and_stays_connected() {
+        let mut group = MeshGroup::new(0, 10);
+        let mut latency = LatencyMatrix::default();
+        for i in 0..5 {
+            group.add_player(format!("p{i}"), format!("peer{i}"));
+        }
+        // A chain of close neighbours; every other pair is unmeasured
+        for i in 0..4 {
+            latency.record(&format!("p{i}"), &format!("p{}", i + 1), 10);
+        }
+
+        let ids = |peers: Vec<(String, String)>| -> Vec<String> {
+            peers.into_iter().map(|(pid, _)| pid).collect()
+        };
+        // Tour successor first, then the nearest remaining peer
+        assert_eq!(
+            ids(group.select_peers_for_player("p2", 2, &latency)),
+            ["p3", "p1"]
+        );
+        assert_eq!(
+            ids(group.select_peers_for_player("p4", 2, &latency)),
+            ["p0", "p3"]
+        );
+
+        // One connection each still links every player
+        let mut reached: HashSet<String> = HashSet::from(["p0".to_string()]);
+        let mut current = "p0".to_string();
+        for _ in 0..5 {
+            let next = group.select_peers_for_player(&current, 1, &latency);
+            current = next[0].0.clone();
+            reached.insert(current.clone());
+        }
+        assert_eq!(reached.len(), 5);
     }
 }
@@ -1,7 +1,13 @@
 mod bridge;
+mod delta;
+mod latency;
 mod manager;
 mod mesh_group;
+mod simulator;
 
 pub use bridge::BridgeSelector;
-pub use manager::{TopologyManager, TopologyManagerConfig};
+pub use delta::topology_delta;
+pub use latency::LatencyMatrix;
+pub use manager::{TopologyChurn, TopologyManager, TopologyManagerConfig};
 pub use mesh_group::MeshGroup;
+pub use simulator::{SimulationConfig, simulate};
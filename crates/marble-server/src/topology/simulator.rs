@@ -0,0 +1,447 @@
+//! Offline gossip propagation simulator.
+//!
+//! Builds a room topology with [`TopologyManager`] and floods messages over it using the
+//! client's relay rule ([`relay_targets`]), so mesh sizing, TTL and bridge settings can be
+//! evaluated without running browsers.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fmt;
+
+use marble_core::bevy::gossip::{broadcast_targets, relay_targets};
+use marble_proto::room::PeerConnectionStatus;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use super::{TopologyManager, TopologyManagerConfig};
+
+/// Simulation parameters
+#[derive(Debug, Clone)]
+pub struct SimulationConfig {
+    /// Topology of the simulated room
+    pub topology: TopologyManagerConfig,
+    /// Number of players in the room
+    pub players: usize,
+    /// Messages flooded per scenario, each from a random player
+    pub messages: usize,
+    /// One-way link latency is drawn uniformly from this range once per link
+    pub min_latency_ms: f64,
+    pub max_latency_ms: f64,
+    /// Probability that a single transmission is lost (0.0 ~ 1.0)
+    pub loss: f64,
+    /// TTL the origin gives each message (the client picks it per message class, see
+    /// [`MessageClass::default_ttl`](marble_core::bevy::gossip::MessageClass::default_ttl))
+    pub ttl: u32,
+    /// Seed for link latencies, losses and message origins
+    pub seed: u64,
+}
+
+/// Propagation results for one scenario
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScenarioReport {
+    /// Mean share of live players reached by a message (excluding its origin)
+    pub coverage: f64,
+    /// Worst coverage of a single message
+    pub min_coverage: f64,
+    /// Median time until a player first receives a message
+    pub p50_latency_ms: f64,
+    /// 99th percentile time until a player first receives a message
+    pub p99_latency_ms: f64,
+    /// Duplicate receptions per live player per message
+    pub duplicates_per_node: f64,
+}
+
+/// Results of a simulation run
+#[derive(Debug, Clone)]
+pub struct SimulationReport {
+    pub players: usize,
+    pub groups: usize,
+    pub bridges: usize,
+    /// All players online
+    pub healthy: ScenarioReport,
+    /// The first bridge goes silent before any message is sent; `None` without bridges
+    pub bridge_failure: Option<(String, ScenarioReport)>,
+}
+
+/// A simulated client as the gossip handler sees it
+#[derive(Debug, Clone, Default)]
+struct Node {
+    group: u32,
+    is_bridge: bool,
+    group_peers: Vec<usize>,
+    bridge_peers: Vec<usize>,
+}
+
+/// A message copy in flight
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Delivery {
+    arrival_us: u64,
+    to: usize,
+    from: usize,
+    ttl: u32,
+}
+
+struct Network {
+    player_ids: Vec<String>,
+    nodes: Vec<Node>,
+    /// One-way latency in microseconds, keyed by (lower index, higher index)
+    link_us: HashMap<(usize, usize), u64>,
+}
+
+impl Network {
+    fn link_key(a: usize, b: usize) -> (usize, usize) {
+        (a.min(b), a.max(b))
+    }
+
+    fn latency_us(&self, a: usize, b: usize) -> u64 {
+        self.link_us
+            .get(&Self::link_key(a, b))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// Run the healthy and bridge-failure scenarios
+pub fn simulate(config: &SimulationConfig) -> SimulationReport {
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let network = build_network(config, &mut rng);
+
+    let healthy = run_scenario(&network, config, None);
+    let bridge_failure = network
+        .nodes
+        .iter()
+        .position(|node| node.is_bridge)
+        .map(|failed| {
+            (
+                network.player_ids[failed].clone(),
+                run_scenario(&network, config, Some(failed)),
+            )
+        });
+
+    let mut groups: Vec<u32> = network.nodes.iter().map(|node| node.group).collect();
+    groups.sort_unstable();
+    groups.dedup();
+
+    SimulationReport {
+        players: network.nodes.len(),
+        groups: groups.len(),
+        bridges: network.nodes.iter().filter(|node| node.is_bridge).count(),
+        healthy,
+        bridge_failure,
+    }
+}
+
+/// Join every player, report link quality once so bridges and latency clustering act on
+/// it, then collect each player's peers from the final topology.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn build_network(config: &SimulationConfig, rng: &mut StdRng) -> Network {
+    let player_ids: Vec<String> = (0..config.players)
+        .map(|i| format!("player-{i:04}"))
+        .collect();
+    let index: HashMap<&str, usize> = player_ids
+        .iter()
+        .enumerate()
+        .map(|(i, id)| (id.as_str(), i))
+        .collect();
+
+    let mut manager = TopologyManager::new(config.topology.clone());
+    for id in &player_ids {
+        manager.add_player(id, &format!("peer-{id}"));
+    }
+
+    let mut link_us = HashMap::new();
+    let mut draw_latency = |a: usize, b: usize, rng: &mut StdRng| -> u64 {
+        *link_us.entry(Network::link_key(a, b)).or_insert_with(|| {
+            let ms = rng.random_range(config.min_latency_ms..=config.max_latency_ms);
+            (ms * 1000.0) as u64
+        })
+    };
+
+    for (i, id) in player_ids.iter().enumerate() {
+        let Some(topology) = manager.get_topology(id) else {
+            continue;
+        };
+        let statuses: Vec<PeerConnectionStatus> = topology
+            .connect_to
+            .iter()
+            .chain(&topology.bridge_peers)
+            .filter_map(|peer| {
+                let j = *index.get(peer.user_id.as_str())?;
+                let rtt_us = 2 * draw_latency(i, j, rng);
+                Some(PeerConnectionStatus {
+                    peer_id: peer.peer_id.clone(),
+                    rtt_ms: u32::try_from(rtt_us / 1000).unwrap_or(u32::MAX),
+                    packet_loss: config.loss as f32,
+                    connected: true,
+                })
+            })
+            .collect();
+        manager.update_connection_status(id, &statuses, true);
+    }
+    manager.refresh_bridges();
+
+    // WebRTC connections are bidirectional, so a peer is known to both ends
+    let mut nodes = vec![Node::default(); player_ids.len()];
+    for (i, id) in player_ids.iter().enumerate() {
+        let Some(topology) = manager.get_topology(id) else {
+            continue;
+        };
+        nodes[i].group = topology.mesh_group;
+        nodes[i].is_bridge = topology.is_bridge;
+        for peer in &topology.connect_to {
+            if let Some(&j) = index.get(peer.user_id.as_str()) {
+                nodes[i].group_peers.push(j);
+                nodes[j].group_peers.push(i);
+                draw_latency(i, j, rng);
+            }
+        }
+        for peer in &topology.bridge_peers {
+            if let Some(&j) = index.get(peer.user_id.as_str()) {
+                nodes[i].bridge_peers.push(j);
+                nodes[j].bridge_peers.push(i);
+                draw_latency(i, j, rng);
+            }
+        }
+    }
+    for node in &mut nodes {
+        node.group_peers.sort_unstable();
+        node.group_peers.dedup();
+        node.bridge_peers.sort_unstable();
+        node.bridge_peers.dedup();
+    }
+
+    Network {
+        player_ids,
+        nodes,
+        link_us,
+    }
+}
+
+/// Flood `config.messages` messages with `failed` (if any) dropping everything it receives
+#[allow(clippy::cast_precision_loss)]
+fn run_scenario(
+    network: &Network,
+    config: &SimulationConfig,
+    failed: Option<usize>,
+) -> ScenarioReport {
+    // Same seed per scenario so both see the same losses and origins where possible
+    let mut rng = StdRng::seed_from_u64(config.seed.wrapping_add(1));
+    let live: Vec<usize> = (0..network.nodes.len())
+        .filter(|&i| Some(i) != failed)
+        .collect();
+
+    let mut latencies_us = Vec::new();
+    let mut coverage_sum = 0.0;
+    let mut min_coverage = 1.0_f64;
+    let mut duplicates = 0u64;
+
+    for _ in 0..config.messages {
+        if live.len() < 2 {
+            break;
+        }
+        let origin = live[rng.random_range(0..live.len())];
+        let (reached, message_duplicates) =
+            flood(network, config, origin, failed, &mut rng, &mut latencies_us);
+
+        let coverage = reached as f64 / (live.len() - 1) as f64;
+        coverage_sum += coverage;
+        min_coverage = min_coverage.min(coverage);
+        duplicates += message_duplicates;
+    }
+
+    latencies_us.sort_unstable();
+    let messages = config.messages.max(1) as f64;
+    ScenarioReport {
+        coverage: coverage_sum / messages,
+        min_coverage,
+        p50_latency_ms: percentile(&latencies_us, 0.50) as f64 / 1000.0,
+        p99_latency_ms: percentile(&latencies_us, 0.99) as f64 / 1000.0,
+        duplicates_per_node: duplicates as f64 / live.len().max(1) as f64 / messages,
+    }
+}
+
+/// Spread one message from `origin` the way `GossipHandler` does: dedup by message id,
+/// relay with the TTL decremented, stop at TTL 0.
+///
+/// Pushes first-delivery times to `latencies_us`; returns players reached and duplicates.
+fn flood(
+    network: &Network,
+    config: &SimulationConfig,
+    origin: usize,
+    failed: Option<usize>,
+    rng: &mut StdRng,
+    latencies_us: &mut Vec<u64>,
+) -> (usize, u64) {
+    let origin_node = &network.nodes[origin];
+    let mut queue = BinaryHeap::new();
+    // Takes the queue explicitly so it can be popped between sends
+    let send = |queue: &mut BinaryHeap<Reverse<Delivery>>,
+                rng: &mut StdRng,
+                now_us: u64,
+                from: usize,
+                to: usize,
+                ttl: u32| {
+        if !rng.random_bool(config.loss) {
+            queue.push(Reverse(Delivery {
+                arrival_us: now_us + network.latency_us(from, to),
+                to,
+                from,
+                ttl,
+            }));
+        }
+    };
+
+    for to in broadcast_targets(
+        origin_node.is_bridge,
+        &origin_node.group_peers,
+        &origin_node.bridge_peers,
+    ) {
+        send(&mut queue, rng, 0, origin, to, config.ttl);
+    }
+
+    let mut seen = vec![false; network.nodes.len()];
+    seen[origin] = true;
+    let (mut reached, mut duplicates) = (0, 0);
+    while let Some(Reverse(delivery)) = queue.pop() {
+        if Some(delivery.to) == failed {
+            continue;
+        }
+        if seen[delivery.to] {
+            duplicates += 1;
+            continue;
+        }
+        seen[delivery.to] = true;
+        reached += 1;
+        latencies_us.push(delivery.arrival_us);
+
+        if delivery.ttl == 0 {
+            continue;
+        }
+        let node = &network.nodes[delivery.to];
+        for to in relay_targets(
+            node.is_bridge,
+            node.group,
+            origin_node.group,
+            &node.group_peers,
+            &node.bridge_peers,
+            delivery.from,
+        ) {
+            send(
+                &mut queue,
+                rng,
+                delivery.arrival_us,
+                delivery.to,
+                to,
+                delivery.ttl - 1,
+            );
+        }
+    }
+    (reached, duplicates)
+}
+
+/// Nearest-rank percentile of sorted samples
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+fn percentile(sorted: &[u64], p: f64) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+impl fmt::Display for ScenarioReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "coverage {:.2}% (worst {:.2}%), latency p50 {:.1} ms / p99 {:.1} ms, {:.2} duplicates per node",
+            self.coverage * 100.0,
+            self.min_coverage * 100.0,
+            self.p50_latency_ms,
+            self.p99_latency_ms,
+            self.duplicates_per_node,
+        )
+    }
+}
+
+impl fmt::Display for SimulationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} players in {} groups with {} bridges",
+            self.players, self.groups, self.bridges
+        )?;
+        write!(f, "healthy:        {}", self.healthy)?;
+        if let Some((bridge, report)) = &self.bridge_failure {
+            write!(
+                f,
+                "\nbridge failure: {report}\n  ({bridge} offline, coverage {:+.2} pp)",
+                (report.coverage - self.healthy.coverage) * 100.0
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(players: usize) -> SimulationConfig {
+        SimulationConfig {
+            topology: TopologyManagerConfig {
+                mesh_group_size: 10,
+                peer_connections: 3,
+                ..Default::default()
+            },
+            players,
+            messages: 50,
+            min_latency_ms: 20.0,
+            max_latency_ms: 80.0,
+            loss: 0.0,
+            ttl: 10,
+            seed: 7,
+        }
+    }
+
+    #[test]
+    fn test_lossless_flood_reaches_everyone() {
+        let report = simulate(&config(40));
+
+        assert_eq!(report.players, 40);
+        assert_eq!(report.groups, 4);
+        assert_eq!(report.bridges, 8);
+        assert!((report.healthy.coverage - 1.0).abs() < f64::EPSILON);
+        assert!(report.healthy.p50_latency_ms >= 20.0);
+        assert!(report.healthy.p50_latency_ms <= report.healthy.p99_latency_ms);
+        assert!(report.healthy.duplicates_per_node > 0.0);
+
+        let (_, failure) = report.bridge_failure.unwrap();
+        assert!(failure.coverage <= report.healthy.coverage);
+    }
+
+    #[test]
+    fn test_ttl_and_loss_limit_coverage() {
+        let mut short_ttl = config(40);
+        short_ttl.ttl = 0;
+        let report = simulate(&short_ttl);
+        assert!(report.healthy.coverage < 0.5);
+        assert!(report.healthy.duplicates_per_node.abs() < f64::EPSILON);
+
+        let mut lossy = config(40);
+        lossy.loss = 1.0;
+        let report = simulate(&lossy);
+        assert!(report.healthy.coverage.abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_percentile() {
+        let samples = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+        assert_eq!(percentile(&samples, 0.5), 5);
+        assert_eq!(percentile(&samples, 0.99), 10);
+        assert_eq!(percentile(&[], 0.5), 0);
+    }
+}
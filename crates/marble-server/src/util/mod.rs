@@ -28,3 +28,20 @@ pub fn required_str(s: &str, msg: &'static str) -> Result<(), tonic::Status> {
     }
 }
 
+/// `INVALID_ARGUMENT` carrying a `google.rpc.BadRequest` with one entry per field.
+pub fn invalid_fields<F, D>(
+    message: impl Into<String>,
+    violations: impl IntoIterator<Item = (F, D)>,
+) -> tonic::Status
+where
+    F: Into<String>,
+    D: Into<String>,
+{
+    use tonic_types::{ErrorDetails, StatusExt};
+
+    let mut details = ErrorDetails::new();
+    for (field, description) in violations {
+        details.add_bad_request_violation(field, description);
+    }
+    tonic::Status::with_error_details(tonic::Code::InvalidArgument, message, details)
+}